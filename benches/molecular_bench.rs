@@ -25,6 +25,8 @@ fn setup_engine_data(
     let graph = MolecularGraph {
         atomic_numbers,
         positions,
+        cell: None,
+        pbc: [false; 3],
     };
     let weights = DMatrix::from_element(feat_dim, feat_dim, 0.5);
     let model = GNNModel { weights };
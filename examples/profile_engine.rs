@@ -19,6 +19,8 @@ fn main() {
     let graph = MolecularGraph {
         atomic_numbers,
         positions,
+        cell: None,
+        pbc: [false; 3],
     };
     let weights = DMatrix::from_element(feat_dim, feat_dim, 0.5);
     let model = GNNModel { weights };
@@ -23,17 +23,35 @@ class ValenceEngine:
         # Pass the model weights into the fused parallel kernel
         return graph.run_fused_with_model(self.model, atom_features, cutoff, k)
 
+    def compute_forces(
+        self,
+        molecule: Molecule,
+        atom_features: np.ndarray,
+        cutoff: float = 5.0,
+        k: int = 16,
+        readout: np.ndarray | None = None,
+    ):
+        """
+        Returns (energy, forces) where energy is the readout-weighted sum of
+        all per-atom outputs and forces = -dE/dR with shape (n_atoms, 3).
+        """
+        graph = molecule.build_graph()
+        return graph.compute_forces(self.model, atom_features, cutoff, k, readout)
+
     def predict_batch(
         self,
         molecules: list[Molecule],
         features_list: list[np.ndarray],
         cutoff: float = 5.0,
         k: int = 16,
+        return_errors: bool = False,
     ):
         """
         High-throughput entry point. Takes a list of molecules and runs
         them in a single parallel sweep in Rust.
         Adds input validation and debug logging to catch invalid input and diagnose issues.
+        With return_errors=True, a molecule that cannot be evaluated yields a
+        GraphError (index and reason) in its slot instead of failing the batch.
         """
         # Input validation
         if not isinstance(molecules, list) or not all(
@@ -49,7 +67,7 @@ class ValenceEngine:
                 f"Number of molecules ({len(molecules)}) does not match number of feature arrays ({len(features_list)})."
             )
         for i, (mol, feats) in enumerate(zip(molecules, features_list)):
-            if len(mol.atomic_numbers) != feats.shape[0]:
+            if not return_errors and len(mol.atomic_numbers) != feats.shape[0]:
                 raise ValueError(
                     f"Feature array at index {i} does not match number of atoms in molecule: {len(mol.atomic_numbers)} vs {feats.shape[0]}"
                 )
@@ -65,6 +83,68 @@ class ValenceEngine:
         batch = _lowlevel.MolecularBatch(rust_graphs)
 
         # 3. Execute parallel batch inference
+        if return_errors:
+            return batch.try_run_batch_inference(self.model, features_list, cutoff, k)
         results = batch.run_batch_inference(self.model, features_list, cutoff, k)
 
         return results
+
+    def predict_packed(
+        self,
+        atomic_numbers: np.ndarray,
+        positions: np.ndarray,
+        graph_ptr: np.ndarray,
+        atom_features: np.ndarray,
+        cutoff: float = 5.0,
+        k: int = 16,
+        cells: np.ndarray | None = None,
+        pbc: np.ndarray | None = None,
+    ):
+        """
+        Packed variant of predict_batch. All molecules are concatenated along
+        the atom axis and molecule g owns rows graph_ptr[g]:graph_ptr[g + 1],
+        so the whole batch crosses into Rust as a handful of arrays. Periodic
+        materials pass one (3, 3) cell per graph in `cells`, all zeros for
+        molecules, and optionally an (n_graphs, 3) `pbc` mask. Returns one
+        (n_atoms_total, n_outputs) array in the same row order.
+        """
+        if self.model is None:
+            raise ValueError(
+                "Model weights are not loaded. Please initialize ValenceEngine with a valid weight_path."
+            )
+        batch = _lowlevel.MolecularBatch.from_packed(
+            np.ascontiguousarray(atomic_numbers, dtype=np.int32),
+            np.ascontiguousarray(positions, dtype=np.float32),
+            np.ascontiguousarray(graph_ptr, dtype=np.int64),
+            cells=None if cells is None else np.asarray(cells, dtype=np.float32),
+            pbc=None if pbc is None else np.asarray(pbc, dtype=bool),
+        )
+        return batch.run_packed_inference(
+            self.model, np.ascontiguousarray(atom_features, dtype=np.float32), cutoff, k
+        )
+
+    def predict_stream(
+        self,
+        items,
+        cutoff: float = 5.0,
+        k: int = 16,
+        chunk_size: int = 256,
+        max_in_flight: int = 2,
+    ):
+        """
+        Streaming variant of predict_batch for inputs that do not fit in memory.
+        `items` is any iterable of (Molecule, features) pairs and is consumed
+        lazily in chunks. Yields (index, output) pairs as chunks complete, where
+        index is the position of the item in `items`.
+        """
+        if self.model is None:
+            raise ValueError(
+                "Model weights are not loaded. Please initialize ValenceEngine with a valid weight_path."
+            )
+        pairs = (
+            (mol.build_graph(), np.ascontiguousarray(feats, dtype=np.float32))
+            for mol, feats in items
+        )
+        return _lowlevel.InferenceStream(
+            pairs, self.model, cutoff, k, chunk_size, max_in_flight
+        )
@@ -7,6 +7,8 @@ from . import _lowlevel  # ruff: noqa
 class Molecule(BaseModel):
     atomic_numbers: list[int] = Field(..., min_length=1)
     positions: list[list[float]]
+    cell: list[list[float]] | None = None
+    pbc: tuple[bool, bool, bool] | None = None
 
     @field_validator("atomic_numbers")
     @classmethod
@@ -25,6 +27,13 @@ class Molecule(BaseModel):
             raise ValueError("Each coordinate must be exactly 3D (x, y, z)")
         return v
 
+    @field_validator("cell")
+    @classmethod
+    def check_cell(cls, v):
+        if v is not None and (len(v) != 3 or any(len(row) != 3 for row in v)):
+            raise ValueError("Cell must be 3x3 (one lattice vector per row)")
+        return v
+
     def build_graph(self) -> _lowlevel.MolecularGraph:
         """
         Initializes the Rust-side graph object.
@@ -33,4 +42,5 @@ class Molecule(BaseModel):
         """
         # Convert to numpy array for zero-copy handoff in Rust
         pos_array = np.array(self.positions, dtype=np.float32)
-        return _lowlevel.MolecularGraph(self.atomic_numbers, pos_array)
+        cell = None if self.cell is None else np.array(self.cell, dtype=np.float32)
+        return _lowlevel.MolecularGraph(self.atomic_numbers, pos_array, cell, self.pbc)
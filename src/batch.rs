@@ -1,76 +1,519 @@
-use crate::{graph::MolecularGraph, model::GNNModel};
-use numpy::{ndarray, PyArray2, PyReadonlyArray2};
+use crate::graph::{check_feature_shape, checked_out, MolecularGraph};
+use crate::model::GNNModel;
+use crate::potential::{Evaluation, Potential};
+use crate::scheduler::{per_graph_cutoffs, per_graph_offsets, Scheduler};
+use nalgebra::{DVector, Matrix3, Vector3};
+use numpy::ndarray::s;
+use numpy::{
+    ndarray, PyArray1, PyArray2, PyArray3, PyReadonlyArray1, PyReadonlyArray2, PyReadonlyArray3,
+};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use rayon::prelude::*;
 
+/// `(atomic_numbers, positions, graph_ptr, charges, cells, pbc)` of a packed
+/// batch.
+type PackedArrays = (
+    Py<PyArray1<i32>>,
+    Py<PyArray2<f32>>,
+    Py<PyArray1<i64>>,
+    Py<PyArray1<f32>>,
+    Py<PyArray3<f32>>,
+    Py<PyArray2<bool>>,
+);
+
 #[pyclass]
 pub struct MolecularBatch {
     pub graphs: Vec<MolecularGraph>,
+    /// Total charge of each graph. The model does not read it; it is metadata
+    /// carried along for readouts downstream, and survives packing.
+    pub charges: Vec<f32>,
 }
 
 #[pymethods]
 impl MolecularBatch {
-    #[must_use]
     #[new]
+    #[pyo3(signature = (graphs, charges=None))]
     #[allow(clippy::needless_pass_by_value)]
-    pub fn new(graphs: Vec<MolecularGraph>) -> Self {
-        MolecularBatch { graphs }
+    /// Creates a batch; `charges` holds the total charge of each graph and
+    /// defaults to neutral.
+    ///
+    /// # Errors
+    /// Returns an error if `charges` does not hold one value per graph.
+    pub fn new(graphs: Vec<MolecularGraph>, charges: Option<Vec<f32>>) -> PyResult<Self> {
+        let charges = checked_charges(charges, graphs.len())?;
+        Ok(MolecularBatch { graphs, charges })
     }
-    /// Runs batch inference for all graphs in the batch.
+
+    /// Builds a batch from the packed layout: `atomic_numbers` and `positions`
+    /// of all graphs concatenated, with graph `g` owning rows
+    /// `graph_ptr[g]..graph_ptr[g + 1]`.
+    ///
+    /// `cells` holds the `(3, 3)` lattice vectors of each graph as rows, with an
+    /// all-zero cell for an isolated molecule, and `pbc` the periodic
+    /// directions of each graph. As for `MolecularGraph`, a graph is periodic
+    /// along every axis when it has a cell and `pbc` is omitted.
     ///
-    /// # Panics
-    /// Panics if the feature array row count does not match atom count.
+    /// # Errors
+    /// Returns an error if the arrays disagree in length, `graph_ptr` is not
+    /// a non-decreasing sequence from 0 to the number of atoms, `charges`,
+    /// `cells` or `pbc` do not hold one entry per graph, or a graph is periodic
+    /// without a valid cell.
+    #[staticmethod]
+    #[pyo3(signature = (atomic_numbers, positions, graph_ptr, charges=None, cells=None, pbc=None))]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn from_packed(
+        atomic_numbers: PyReadonlyArray1<i32>,
+        positions: PyReadonlyArray2<f32>,
+        graph_ptr: PyReadonlyArray1<i64>,
+        charges: Option<Vec<f32>>,
+        cells: Option<PyReadonlyArray3<f32>>,
+        pbc: Option<PyReadonlyArray2<bool>>,
+    ) -> PyResult<Self> {
+        let py = atomic_numbers.py();
+        let numbers = atomic_numbers.as_array();
+        let positions = positions.as_array();
+        let total = numbers.len();
+        if positions.shape() != [total, 3] {
+            return Err(PyValueError::new_err(format!(
+                "Expected positions of shape ({total}, 3), got {:?}",
+                positions.shape()
+            )));
+        }
+        let offsets = checked_offsets(&graph_ptr.as_array(), total)?;
+        let n_graphs = offsets.len() - 1;
+        let cells = cells.as_ref().map(PyReadonlyArray3::as_array);
+        if let Some(cells) = &cells {
+            if cells.shape() != [n_graphs, 3, 3] {
+                return Err(PyValueError::new_err(format!(
+                    "Expected cells of shape ({n_graphs}, 3, 3), got {:?}",
+                    cells.shape()
+                )));
+            }
+        }
+        let pbc = pbc.as_ref().map(PyReadonlyArray2::as_array);
+        if let Some(pbc) = &pbc {
+            if pbc.shape() != [n_graphs, 3] {
+                return Err(PyValueError::new_err(format!(
+                    "Expected pbc of shape ({n_graphs}, 3), got {:?}",
+                    pbc.shape()
+                )));
+            }
+        }
+
+        let graphs = offsets
+            .windows(2)
+            .enumerate()
+            .map(|(g, bounds)| {
+                let (start, end) = (bounds[0], bounds[1]);
+                let cell = cells
+                    .as_ref()
+                    .map(|cells| Matrix3::from_fn(|r, c| cells[[g, r, c]]))
+                    .filter(|cell| cell.iter().any(|&x| x != 0.0));
+                let periodic = pbc.as_ref().map_or([cell.is_some(); 3], |pbc| {
+                    [pbc[[g, 0]], pbc[[g, 1]], pbc[[g, 2]]]
+                });
+                MolecularGraph::from_parts(
+                    numbers.slice(s![start..end]).to_vec(),
+                    positions
+                        .slice(s![start..end, ..])
+                        .rows()
+                        .into_iter()
+                        .map(|row| Vector3::new(row[0], row[1], row[2]))
+                        .collect(),
+                    cell,
+                    periodic,
+                )
+                .map_err(|err| PyValueError::new_err(format!("Graph {g}: {}", err.value(py))))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        let charges = checked_charges(charges, graphs.len())?;
+        Ok(MolecularBatch { graphs, charges })
+    }
+
+    /// Total charge of each graph, shape `(n_graphs,)`.
+    #[getter]
     #[must_use]
+    pub fn charges(&self, py: Python<'_>) -> Py<PyArray1<f32>> {
+        PyArray1::from_slice(py, &self.charges).into()
+    }
+
+    /// Offsets of each graph in the packed layout, shape `(n_graphs + 1,)`.
+    #[getter]
+    #[must_use]
+    pub fn graph_ptr(&self, py: Python<'_>) -> Py<PyArray1<i64>> {
+        let offsets: Vec<i64> = self
+            .offsets()
+            .into_iter()
+            .map(|offset| i64::try_from(offset).unwrap_or(i64::MAX))
+            .collect();
+        PyArray1::from_vec(py, offsets).into()
+    }
+
+    /// Returns `(atomic_numbers, positions, graph_ptr, charges, cells, pbc)` in
+    /// the packed layout, with an all-zero cell for graphs without one.
+    /// `from_packed(*batch.to_packed())` rebuilds every graph with its cell,
+    /// periodicity and charge.
+    #[must_use]
+    pub fn to_packed(&self, py: Python<'_>) -> PackedArrays {
+        let numbers: Vec<i32> = self
+            .graphs
+            .iter()
+            .flat_map(|graph| graph.atomic_numbers.iter().copied())
+            .collect();
+        let positions: Vec<&Vector3<f32>> = self
+            .graphs
+            .iter()
+            .flat_map(|graph| graph.positions.iter())
+            .collect();
+        let positions =
+            ndarray::Array2::from_shape_fn((positions.len(), 3), |(i, k)| positions[i][k]);
+        let n_graphs = self.graphs.len();
+        let cells = ndarray::Array3::from_shape_fn((n_graphs, 3, 3), |(g, r, c)| {
+            self.graphs[g].cell.map_or(0.0, |cell| cell[(r, c)])
+        });
+        let pbc = ndarray::Array2::from_shape_fn((n_graphs, 3), |(g, k)| self.graphs[g].pbc[k]);
+        (
+            PyArray1::from_vec(py, numbers).into(),
+            PyArray2::from_array(py, &positions).into(),
+            self.graph_ptr(py),
+            self.charges(py),
+            PyArray3::from_owned_array(py, cells).into(),
+            PyArray2::from_owned_array(py, pbc).into(),
+        )
+    }
+
+    /// Batch inference on packed features, shape `(n_atoms_total, n_features)`,
+    /// whose rows follow `graph_ptr`. Returns the outputs of all graphs as one
+    /// `(n_atoms_total, n_outputs)` array in the same row order. `cutoff` and
+    /// `num_offsets` are scalars or one value per graph.
+    ///
+    /// The outputs are written straight into `out` when given, which must be a
+    /// writeable C-contiguous float32 array of that shape, and `out` itself is
+    /// returned.
+    ///
+    /// # Errors
+    /// Returns an error if the feature array, per-graph parameters or `out` do
+    /// not match the batch and model.
     #[allow(clippy::needless_pass_by_value)]
-    pub fn run_batch_inference(
+    #[pyo3(signature = (model, atom_features, cutoff, num_offsets, out=None))]
+    pub fn run_packed_inference<'py>(
+        &self,
+        model: &GNNModel,
+        atom_features: PyReadonlyArray2<'py, f32>,
+        cutoff: &Bound<'py, PyAny>,
+        num_offsets: &Bound<'py, PyAny>,
+        out: Option<Bound<'py, PyArray2<f32>>>,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let py = atom_features.py();
+        let cutoffs = per_graph_cutoffs(cutoff, self.graphs.len())?;
+        let num_offsets = per_graph_offsets(num_offsets, self.graphs.len())?;
+        let atom_view = atom_features.as_array();
+        let offsets = self.offsets();
+        let total = offsets[offsets.len() - 1];
+        let expected = [total, model.weights.ncols()];
+        if atom_view.shape() != expected {
+            return Err(PyValueError::new_err(format!(
+                "Expected packed atom features of shape ({}, {}), got {:?}",
+                expected[0],
+                expected[1],
+                atom_view.shape()
+            )));
+        }
+
+        let n_outputs = model.weights.nrows();
+        let out = out.unwrap_or_else(|| PyArray2::zeros(py, [total, n_outputs], false));
+        {
+            let mut buffer = checked_out(&out, [total, n_outputs])?;
+            let mut rest = buffer.as_slice_mut()?;
+            // Hand each graph its own block of output rows
+            let mut blocks = Vec::with_capacity(self.graphs.len());
+            for bounds in offsets.windows(2) {
+                let (block, tail) = rest.split_at_mut((bounds[1] - bounds[0]) * n_outputs);
+                blocks.push(block);
+                rest = tail;
+            }
+            let features: Vec<_> = offsets
+                .windows(2)
+                .map(|bounds| atom_view.slice(s![bounds[0]..bounds[1], ..]))
+                .collect();
+            py.detach(|| {
+                Scheduler::default().execute_into(
+                    &self.graphs,
+                    model,
+                    &features,
+                    &cutoffs,
+                    &num_offsets,
+                    blocks,
+                );
+            });
+        }
+        Ok(out)
+    }
+
+    /// Runs batch inference for all graphs in the batch.
+    ///
+    /// Graphs are grouped into chunks by `scheduler` (the default budgets when
+    /// omitted), which also decides whether each chunk is parallelised over
+    /// graphs or over the atoms of a single large graph. `cutoff` and
+    /// `num_offsets` are scalars or one value per graph, so molecules and
+    /// periodic materials can share a batch.
+    ///
+    /// The outputs are written straight into `out` when given: one writeable
+    /// C-contiguous float32 array of shape `(n_atoms, n_outputs)` per graph,
+    /// such as row slices of a packed buffer. These arrays are returned.
+    ///
+    /// # Errors
+    /// Returns an error if there is not one feature array of shape
+    /// `(n_atoms, n_features)` per graph, or the per-graph parameters or `out`
+    /// do not match the batch.
+    #[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
+    #[pyo3(signature = (model, all_atom_features, cutoff, num_offsets, scheduler=None, out=None))]
+    pub fn run_batch_inference<'py>(
         &self,
+        py: Python<'py>,
         model: &GNNModel,
         all_atom_features: Vec<PyReadonlyArray2<f32>>,
-        cutoff: f32,
-        num_offsets: usize,
-    ) -> Vec<Py<PyArray2<f32>>> {
-        #[allow(clippy::needless_pass_by_value)]
+        cutoff: &Bound<'py, PyAny>,
+        num_offsets: &Bound<'py, PyAny>,
+        scheduler: Option<Scheduler>,
+        out: Option<Vec<Bound<'py, PyArray2<f32>>>>,
+    ) -> PyResult<Vec<Bound<'py, PyArray2<f32>>>> {
+        let scheduler = scheduler.unwrap_or_default();
+        let cutoffs = per_graph_cutoffs(cutoff, self.graphs.len())?;
+        let num_offsets = per_graph_offsets(num_offsets, self.graphs.len())?;
+        if all_atom_features.len() != self.graphs.len() {
+            return Err(PyValueError::new_err(format!(
+                "Expected one feature array per graph ({}), got {}",
+                self.graphs.len(),
+                all_atom_features.len()
+            )));
+        }
         // Step 1: Extract to owned arrays (sequential, safe)
         let owned_atom_features: Vec<_> = all_atom_features
             .iter()
             .map(|pyarr| pyarr.as_array().to_owned())
             .collect();
+        for (index, (graph, feat_array)) in self.graphs.iter().zip(&owned_atom_features).enumerate()
+        {
+            check_feature_shape(&feat_array.view(), graph.positions.len(), model)
+                .map_err(|err| PyValueError::new_err(format!("Graph {index}: {err}")))?;
+        }
 
-        // Step 2: Pure Rust batch computation
-        let batch_results: Vec<ndarray::Array2<f32>> = self
-            .graphs
-            .par_iter()
-            .zip(owned_atom_features.par_iter())
-            .map(|(graph, feat_array)| {
-                assert_eq!(
-                    feat_array.shape()[0],
-                    graph.atomic_numbers.len(),
-                    "Feature array row count does not match atom count"
-                );
-                let fused_result = graph.run_fused_with_model_internal(
-                    model,
-                    &feat_array.view(),
-                    cutoff,
-                    num_offsets,
-                );
-                let n_atoms = graph.atomic_numbers.len();
-                let n_feats = feat_array.shape()[1];
-                let mut arr = ndarray::Array2::<f32>::zeros((n_atoms, n_feats));
-                for (row_idx, dv) in fused_result.into_iter().enumerate() {
-                    for (col_idx, val) in dv.iter().enumerate() {
-                        arr[[row_idx, col_idx]] = *val;
-                    }
+        // Step 2: Output buffers, either the caller's or fresh NumPy arrays
+        let n_outputs = model.weights.nrows();
+        let outs = match out {
+            Some(outs) if outs.len() != self.graphs.len() => {
+                return Err(PyValueError::new_err(format!(
+                    "Expected one out array per graph ({}), got {}",
+                    self.graphs.len(),
+                    outs.len()
+                )));
+            }
+            Some(outs) => outs,
+            None => self
+                .graphs
+                .iter()
+                .map(|graph| PyArray2::zeros(py, [graph.positions.len(), n_outputs], false))
+                .collect(),
+        };
+        {
+            let mut buffers = self
+                .graphs
+                .iter()
+                .zip(&outs)
+                .map(|(graph, out)| checked_out(out, [graph.positions.len(), n_outputs]))
+                .collect::<PyResult<Vec<_>>>()?;
+            let slices = buffers
+                .iter_mut()
+                .map(|buffer| buffer.as_slice_mut().map_err(PyErr::from))
+                .collect::<PyResult<Vec<_>>>()?;
+
+            // Step 3: Pure Rust batch computation, detached from the interpreter
+            py.detach(|| {
+                let views: Vec<_> = owned_atom_features.iter().map(|f| f.view()).collect();
+                scheduler.execute_into(&self.graphs, model, &views, &cutoffs, &num_offsets, slices);
+            });
+        }
+        Ok(outs)
+    }
+
+    /// Batch inference that reports failures per graph instead of failing the
+    /// whole batch.
+    ///
+    /// Returns one entry per graph: its output array, or a `GraphError` with
+    /// the graph index and the reason it could not be evaluated (mismatched or
+    /// non-finite inputs, or a failure during evaluation).
+    ///
+    /// # Errors
+    /// Returns an error if the per-graph parameters do not match the batch.
+    #[allow(clippy::needless_pass_by_value)]
+    #[pyo3(signature = (model, all_atom_features, cutoff, num_offsets, scheduler=None))]
+    pub fn try_run_batch_inference(
+        &self,
+        py: Python<'_>,
+        model: &GNNModel,
+        all_atom_features: Vec<Bound<'_, PyAny>>,
+        cutoff: &Bound<'_, PyAny>,
+        num_offsets: &Bound<'_, PyAny>,
+        scheduler: Option<Scheduler>,
+    ) -> PyResult<Vec<Py<PyAny>>> {
+        let scheduler = scheduler.unwrap_or_default();
+        let cutoffs = per_graph_cutoffs(cutoff, self.graphs.len())?;
+        let num_offsets = per_graph_offsets(num_offsets, self.graphs.len())?;
+        // Screen every graph up front; only the valid ones are evaluated
+        let mut outcomes: Vec<Result<ndarray::Array2<f32>, String>> = Vec::new();
+        let mut valid = Vec::new();
+        for (index, graph) in self.graphs.iter().enumerate() {
+            match checked_features(graph, all_atom_features.get(index), model) {
+                Ok(features) => {
+                    valid.push((index, features));
+                    outcomes.push(Ok(ndarray::Array2::zeros((0, 0))));
                 }
-                arr
-            })
-            .collect();
+                Err(reason) => outcomes.push(Err(reason)),
+            }
+        }
 
-        // Step 3: Convert results to Python objects inside a single GIL block
-        Python::attach(|py| {
-            batch_results
-                .into_iter()
-                .map(|arr| PyArray2::from_array(py, &arr).into())
+        let evaluated = py.detach(|| {
+            let graphs: Vec<&MolecularGraph> = valid
+                .iter()
+                .map(|(index, _)| &self.graphs[*index])
+                .collect();
+            let views: Vec<_> = valid.iter().map(|(_, features)| features.view()).collect();
+            let cutoffs: Vec<f32> = valid.iter().map(|(index, _)| cutoffs[*index]).collect();
+            let num_offsets: Vec<usize> =
+                valid.iter().map(|(index, _)| num_offsets[*index]).collect();
+            scheduler
+                .try_execute(&graphs, model, &views, &cutoffs, &num_offsets)
+                .into_par_iter()
+                .map(|outcome| outcome.map(|rows| rows_to_array(&rows, model.weights.nrows())))
                 .collect::<Vec<_>>()
-        })
+        });
+        for ((index, _), outcome) in valid.iter().zip(evaluated) {
+            outcomes[*index] = outcome;
+        }
+
+        outcomes
+            .into_iter()
+            .enumerate()
+            .map(|(index, outcome)| match outcome {
+                Ok(arr) => Ok(PyArray2::from_array(py, &arr).into_any().unbind()),
+                Err(reason) => Ok(Py::new(py, GraphError { index, reason })?.into_any()),
+            })
+            .collect()
+    }
+}
+
+impl From<Vec<MolecularGraph>> for MolecularBatch {
+    /// A batch of neutral graphs.
+    fn from(graphs: Vec<MolecularGraph>) -> Self {
+        let charges = vec![0.0; graphs.len()];
+        MolecularBatch { graphs, charges }
+    }
+}
+
+/// Why a graph in a batch could not be evaluated.
+#[pyclass]
+#[derive(Clone)]
+pub struct GraphError {
+    /// Position of the graph in the batch.
+    #[pyo3(get)]
+    pub index: usize,
+    #[pyo3(get)]
+    pub reason: String,
+}
+
+#[pymethods]
+impl GraphError {
+    fn __repr__(&self) -> String {
+        format!("GraphError(index={}, reason={:?})", self.index, self.reason)
+    }
+}
+
+impl MolecularBatch {
+    /// Row offsets of each graph in the packed layout, `n_graphs + 1` long.
+    #[must_use]
+    pub fn offsets(&self) -> Vec<usize> {
+        let mut offsets = Vec::with_capacity(self.graphs.len() + 1);
+        offsets.push(0);
+        for graph in &self.graphs {
+            offsets.push(offsets[offsets.len() - 1] + graph.positions.len());
+        }
+        offsets
+    }
+
+    /// Evaluates `potential` on every graph in parallel, with one feature
+    /// array shared by all graphs (as for images of the same system).
+    #[must_use]
+    pub fn evaluate(
+        &self,
+        potential: &Potential,
+        atom_view: &ndarray::ArrayView2<f32>,
+    ) -> Vec<Evaluation> {
+        self.graphs
+            .par_iter()
+            .map(|graph| potential.evaluate(graph, atom_view))
+            .collect()
+    }
+}
+
+/// Stacks per-atom output vectors into an `(n_atoms, cols)` array.
+fn rows_to_array(rows: &[DVector<f32>], cols: usize) -> ndarray::Array2<f32> {
+    ndarray::Array2::from_shape_fn((rows.len(), cols), |(i, j)| rows[i][j])
+}
+
+/// Per-graph total charges, neutral when omitted.
+pub(crate) fn checked_charges(charges: Option<Vec<f32>>, n_graphs: usize) -> PyResult<Vec<f32>> {
+    let charges = charges.unwrap_or_else(|| vec![0.0; n_graphs]);
+    if charges.len() != n_graphs {
+        return Err(PyValueError::new_err(format!(
+            "Expected one charge per graph ({n_graphs}), got {}",
+            charges.len()
+        )));
+    }
+    Ok(charges)
+}
+
+/// Validates a packed `graph_ptr` against `total` atoms.
+fn checked_offsets(graph_ptr: &ndarray::ArrayView1<i64>, total: usize) -> PyResult<Vec<usize>> {
+    let offsets = graph_ptr
+        .iter()
+        .map(|&offset| usize::try_from(offset))
+        .collect::<Result<Vec<usize>, _>>()
+        .map_err(|_| PyValueError::new_err("graph_ptr must not contain negative offsets"))?;
+    if offsets.first() != Some(&0) || offsets.last() != Some(&total) {
+        return Err(PyValueError::new_err(format!(
+            "graph_ptr must start at 0 and end at the number of atoms ({total})"
+        )));
+    }
+    if offsets.windows(2).any(|bounds| bounds[1] < bounds[0]) {
+        return Err(PyValueError::new_err("graph_ptr must be non-decreasing"));
+    }
+    Ok(offsets)
+}
+
+/// Copies the atom features of `graph`, checking them against the graph and
+/// model first.
+fn checked_features(
+    graph: &MolecularGraph,
+    features: Option<&Bound<'_, PyAny>>,
+    model: &GNNModel,
+) -> Result<ndarray::Array2<f32>, String> {
+    let features = features.ok_or("No atom features were given for this graph")?;
+    let features: PyReadonlyArray2<f32> = features
+        .extract()
+        .map_err(|_| "Atom features must be a 2D float32 array".to_string())?;
+    let features = features.as_array();
+    check_feature_shape(&features, graph.positions.len(), model)?;
+    if graph
+        .positions
+        .iter()
+        .any(|r| !r.iter().all(|x| x.is_finite()))
+    {
+        return Err("Positions contain non-finite values".to_string());
+    }
+    if !features.iter().all(|x| x.is_finite()) {
+        return Err("Atom features contain non-finite values".to_string());
     }
+    Ok(features.to_owned())
 }
@@ -0,0 +1,104 @@
+use hdrhistogram::Histogram;
+use nalgebra::{DMatrix, Vector3};
+use numpy::ndarray;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::time::Instant;
+use valence::graph::MolecularGraph;
+use valence::model::GNNModel;
+use valence::scheduler::Scheduler;
+
+const ITERATIONS: usize = 30;
+const FEATURES: usize = 16;
+const CUTOFF: f32 = 5.0;
+const NUM_OFFSETS: usize = 16;
+
+fn random_graph(rng: &mut StdRng, n_atoms: usize, box_length: f32) -> MolecularGraph {
+    MolecularGraph {
+        atomic_numbers: vec![6; n_atoms],
+        positions: (0..n_atoms)
+            .map(|_| Vector3::new(rng.random(), rng.random(), rng.random()) * box_length)
+            .collect(),
+        cell: None,
+        pbc: [false; 3],
+    }
+}
+
+fn summary(hist: &Histogram<u64>) -> (u64, u64, u64, u64) {
+    (
+        hist.value_at_quantile(0.50),
+        hist.value_at_quantile(0.95),
+        hist.value_at_quantile(0.99),
+        hist.max(),
+    )
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let json_mode = std::env::args().any(|a| a == "--json");
+
+    // A skewed batch: one large molecule among many small ones
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut graphs = vec![random_graph(&mut rng, 3000, 30.0)];
+    graphs.extend((0..400).map(|_| random_graph(&mut rng, 24, 4.0)));
+    let features: Vec<_> = graphs
+        .iter()
+        .map(|g| ndarray::Array2::from_elem((g.positions.len(), FEATURES), 1.0f32))
+        .collect();
+    let views: Vec<_> = features.iter().map(|f| f.view()).collect();
+    let model = GNNModel {
+        weights: DMatrix::from_element(FEATURES, FEATURES, 0.1),
+    };
+    let scheduler = Scheduler::default();
+    let cutoffs = vec![CUTOFF; graphs.len()];
+    let num_offsets = vec![NUM_OFFSETS; graphs.len()];
+
+    // Batch latencies in microseconds, up to a minute
+    let mut nested_hist = Histogram::<u64>::new_with_max(60_000_000, 3)?;
+    let mut scheduler_hist = Histogram::<u64>::new_with_max(60_000_000, 3)?;
+
+    if !json_mode {
+        println!(
+            "Benchmarking {ITERATIONS} batches of {} graphs...",
+            graphs.len()
+        );
+    }
+    for _ in 0..ITERATIONS {
+        // Graph-level par_iter with atom-level parallelism nested inside
+        let start = Instant::now();
+        let _: Vec<_> = graphs
+            .par_iter()
+            .zip(views.par_iter())
+            .map(|(graph, feats)| {
+                graph.run_fused_with_model_internal(&model, feats, CUTOFF, NUM_OFFSETS)
+            })
+            .collect();
+        nested_hist.record(u64::try_from(start.elapsed().as_micros()).unwrap_or(u64::MAX))?;
+
+        let start = Instant::now();
+        let _ = scheduler.execute(&graphs, &model, &views, &cutoffs, &num_offsets);
+        scheduler_hist.record(u64::try_from(start.elapsed().as_micros()).unwrap_or(u64::MAX))?;
+    }
+
+    let strategies = [
+        ("nested", summary(&nested_hist)),
+        ("scheduled", summary(&scheduler_hist)),
+    ];
+    if json_mode {
+        for (name, (p50, p95, p99, max)) in strategies {
+            println!(
+                "{{\"strategy\":\"{name}\",\"p50\":{p50},\"p95\":{p95},\"p99\":{p99},\"max\":{max}}}"
+            );
+        }
+        return Ok(());
+    }
+
+    println!(
+        "{:<10} {:>10} {:>10} {:>10} {:>10}",
+        "strategy", "p50", "p95", "p99", "max"
+    );
+    for (name, (p50, p95, p99, max)) in strategies {
+        println!("{name:<10} {p50:>8}us {p95:>8}us {p99:>8}us {max:>8}us");
+    }
+    Ok(())
+}
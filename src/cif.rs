@@ -0,0 +1,459 @@
+use crate::elements::atomic_number;
+use crate::graph::{cell_from_parameters, MolecularGraph};
+use nalgebra::{Matrix3, Vector3};
+use numpy::PyArray1;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+/// A crystal structure from one data block of a CIF file, expanded to the
+/// full unit cell.
+#[pyclass]
+#[derive(Clone)]
+pub struct CifStructure {
+    pub graph: MolecularGraph,
+    /// The data block name, without the `data_` prefix.
+    #[pyo3(get)]
+    pub name: String,
+    /// The `_atom_site_label` each atom was generated from.
+    #[pyo3(get)]
+    pub labels: Vec<String>,
+    #[pyo3(get)]
+    pub space_group: Option<String>,
+    pub occupancies: Vec<f32>,
+}
+
+#[pymethods]
+impl CifStructure {
+    #[getter]
+    #[must_use]
+    pub fn graph(&self) -> MolecularGraph {
+        self.graph.clone()
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn occupancies<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f32>> {
+        PyArray1::from_slice(py, &self.occupancies)
+    }
+
+    fn __len__(&self) -> usize {
+        self.labels.len()
+    }
+}
+
+/// Reads the crystal structures of a CIF file, one per data block with atom
+/// sites.
+///
+/// Every symmetry operation is applied to the asymmetric unit and images
+/// closer than `tolerance` Angstrom are merged. Sites with an occupancy below
+/// `min_occupancy` are dropped, and when species share a site the one with the
+/// highest occupancy is kept.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or a data block is malformed.
+#[pyfunction]
+#[pyo3(signature = (path, min_occupancy=0.0, tolerance=0.01))]
+pub fn read_cif(path: &str, min_occupancy: f32, tolerance: f64) -> PyResult<Vec<CifStructure>> {
+    let text = std::fs::read_to_string(path)?;
+    parse_blocks(&text)?
+        .iter()
+        .filter(|block| block.column("_atom_site_label").is_some())
+        .map(|block| build_structure(block, min_occupancy, tolerance))
+        .collect()
+}
+
+/// A `data_` block: single items and loops, with tags lowercased and `.`
+/// separators normalised to `_`.
+#[derive(Default)]
+struct Block {
+    name: String,
+    items: HashMap<String, String>,
+    loops: Vec<(Vec<String>, Vec<Vec<String>>)>,
+}
+
+impl Block {
+    fn item(&self, tag: &str) -> Option<&str> {
+        self.items.get(tag).map(String::as_str)
+    }
+
+    /// The values of `tag` in whichever loop holds it.
+    fn column(&self, tag: &str) -> Option<Vec<&str>> {
+        self.loops.iter().find_map(|(tags, rows)| {
+            let index = tags.iter().position(|t| t == tag)?;
+            Some(rows.iter().map(|row| row[index].as_str()).collect())
+        })
+    }
+
+    /// The values of `tag` in the loop that holds `anchor`, so that they line
+    /// up row for row with the values of `anchor`; `None` if that loop lacks
+    /// `tag`.
+    fn sibling_column(&self, anchor: &str, tag: &str) -> Option<Vec<&str>> {
+        let (tags, rows) = self
+            .loops
+            .iter()
+            .find(|(tags, _)| tags.iter().any(|t| t == anchor))?;
+        let index = tags.iter().position(|t| t == tag)?;
+        Some(rows.iter().map(|row| row[index].as_str()).collect())
+    }
+}
+
+/// A symmetry operation on fractional coordinates, as rotation and translation.
+type Operation = (Matrix3<f64>, Vector3<f64>);
+
+/// One token of a CIF file with the line it starts on; `quoted` tokens are
+/// never tags or keywords.
+struct Token<'a> {
+    text: &'a str,
+    quoted: bool,
+    line: usize,
+}
+
+fn tokenize(text: &str) -> PyResult<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut lines = text.lines().enumerate().peekable();
+    while let Some((index, line)) = lines.next() {
+        // Text fields run from a line starting with `;` to the next such line
+        if let Some(first) = line.strip_prefix(';') {
+            let start = first.as_ptr() as usize - text.as_ptr() as usize;
+            let mut end = start + first.len();
+            loop {
+                let Some((_, next)) = lines.next() else {
+                    return Err(parse_error(index + 1, "unterminated text field"));
+                };
+                if next.starts_with(';') {
+                    break;
+                }
+                end = next.as_ptr() as usize - text.as_ptr() as usize + next.len();
+            }
+            tokens.push(Token {
+                text: text[start..end].trim(),
+                quoted: true,
+                line: index + 1,
+            });
+            continue;
+        }
+
+        let mut rest = line.trim_start();
+        while !rest.is_empty() {
+            if rest.starts_with('#') {
+                break;
+            }
+            let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"');
+            let (token, remainder) = match quote {
+                // A quote only closes when followed by whitespace or the line end
+                Some(quote) => {
+                    let body = &rest[1..];
+                    let close = body
+                        .char_indices()
+                        .find(|&(i, c)| {
+                            c == quote
+                                && body[i + 1..].chars().next().is_none_or(char::is_whitespace)
+                        })
+                        .map(|(i, _)| i)
+                        .ok_or_else(|| parse_error(index + 1, "unterminated quoted string"))?;
+                    (&body[..close], &body[close + 1..])
+                }
+                None => rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len())),
+            };
+            tokens.push(Token {
+                text: token,
+                quoted: quote.is_some(),
+                line: index + 1,
+            });
+            rest = remainder.trim_start();
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_blocks(text: &str) -> PyResult<Vec<Block>> {
+    let tokens = tokenize(text)?;
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut i = 0;
+    let is_keyword = |token: &Token| {
+        !token.quoted
+            && (token.text.starts_with('_')
+                || token.text.eq_ignore_ascii_case("loop_")
+                || token.text.to_ascii_lowercase().starts_with("data_"))
+    };
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let lower = token.text.to_ascii_lowercase();
+        if token.quoted {
+            return Err(parse_error(
+                token.line,
+                &format!("unexpected value {:?}", token.text),
+            ));
+        }
+        if lower.starts_with("data_") {
+            blocks.push(Block {
+                name: token.text[5..].to_string(),
+                ..Block::default()
+            });
+            i += 1;
+            continue;
+        }
+        let Some(block) = blocks.last_mut() else {
+            return Err(parse_error(
+                token.line,
+                "content before the first data_ block",
+            ));
+        };
+        if lower == "loop_" {
+            i += 1;
+            let mut tags = Vec::new();
+            while i < tokens.len() && !tokens[i].quoted && tokens[i].text.starts_with('_') {
+                tags.push(normalise_tag(tokens[i].text));
+                i += 1;
+            }
+            let start = i;
+            while i < tokens.len() && !is_keyword(&tokens[i]) {
+                i += 1;
+            }
+            let values: Vec<String> = tokens[start..i]
+                .iter()
+                .map(|t| t.text.to_string())
+                .collect();
+            if tags.is_empty() || !values.len().is_multiple_of(tags.len()) {
+                return Err(parse_error(
+                    token.line,
+                    &format!("loop of {} tags has {} values", tags.len(), values.len()),
+                ));
+            }
+            let rows = values.chunks(tags.len()).map(<[String]>::to_vec).collect();
+            block.loops.push((tags, rows));
+        } else if token.text.starts_with('_') {
+            let Some(value) = tokens.get(i + 1).filter(|t| !is_keyword(t)) else {
+                return Err(parse_error(
+                    token.line,
+                    &format!("{} has no value", token.text),
+                ));
+            };
+            block
+                .items
+                .insert(normalise_tag(token.text), value.text.to_string());
+            i += 2;
+        } else {
+            // Global and save frames are not used by structure files
+            i += 1;
+        }
+    }
+    Ok(blocks)
+}
+
+fn normalise_tag(tag: &str) -> String {
+    tag.to_ascii_lowercase().replace('.', "_")
+}
+
+/// A CIF number without its standard uncertainty, e.g. `5.4307(2)`; `None`
+/// for the unknown (`?`) and inapplicable (`.`) markers.
+fn number(value: &str) -> Option<f64> {
+    value.split('(').next()?.parse().ok()
+}
+
+fn build_structure(block: &Block, min_occupancy: f32, tolerance: f64) -> PyResult<CifStructure> {
+    let error =
+        |message: &str| PyValueError::new_err(format!("CIF block {:?}: {message}", block.name));
+    let cell = unit_cell(block).map_err(|message| error(&message))?;
+    let to_cartesian: Matrix3<f64> = cell.cast::<f64>().transpose();
+    let operations = symmetry_operations(block).map_err(|message| error(&message))?;
+
+    // Every per-site column comes from the loop of the labels, so rows match
+    let site_column = |tag: &str| block.sibling_column("_atom_site_label", tag);
+    let labels = site_column("_atom_site_label").unwrap_or_default();
+    let symbols = site_column("_atom_site_type_symbol");
+    let occupancies = site_column("_atom_site_occupancy");
+    let fractional = ["x", "y", "z"].map(|axis| site_column(&format!("_atom_site_fract_{axis}")));
+    let cartesian = ["x", "y", "z"].map(|axis| site_column(&format!("_atom_site_cartn_{axis}")));
+    let from_cartesian = to_cartesian
+        .try_inverse()
+        .ok_or_else(|| error("the cell is singular"))?;
+
+    // Images are collected per site, then merged across sites by occupancy
+    let mut atoms: Vec<(i32, Vector3<f64>, f32, &str)> = Vec::new();
+    for (site, &label) in labels.iter().enumerate() {
+        let symbol = symbols.as_ref().map_or(label, |s| s[site]);
+        let z = element(symbol)
+            .or_else(|| element(label))
+            .ok_or_else(|| error(&format!("cannot determine the element of site {label:?}")))?;
+        #[allow(clippy::cast_possible_truncation)]
+        let occupancy = occupancies
+            .as_ref()
+            .and_then(|o| number(o[site]))
+            .unwrap_or(1.0) as f32;
+        if occupancy < min_occupancy {
+            continue;
+        }
+        let coordinate = |columns: &[Option<Vec<&str>>; 3]| -> Option<Vector3<f64>> {
+            let mut r = Vector3::zeros();
+            for (k, column) in columns.iter().enumerate() {
+                r[k] = number(column.as_ref()?[site])?;
+            }
+            Some(r)
+        };
+        let frac = coordinate(&fractional)
+            .or_else(|| coordinate(&cartesian).map(|r| from_cartesian * r))
+            .ok_or_else(|| error(&format!("site {label:?} has no valid coordinates")))?;
+
+        for (rotation, translation) in &operations {
+            let image = (rotation * frac + translation).map(|f| f - f.floor());
+            let duplicate = atoms.iter().position(|(_, other, _, _)| {
+                let delta = (image - other).map(|d| d - d.round());
+                (to_cartesian * delta).norm() < tolerance
+            });
+            match duplicate {
+                None => atoms.push((z, image, occupancy, label)),
+                Some(index) if atoms[index].2 < occupancy && atoms[index].3 != label => {
+                    atoms[index] = (z, image, occupancy, label);
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    let positions = atoms
+        .iter()
+        .map(|(_, frac, _, _)| (to_cartesian * frac).map(|x| x as f32))
+        .collect();
+    let graph = MolecularGraph::from_parts(
+        atoms.iter().map(|atom| atom.0).collect(),
+        positions,
+        Some(cell),
+        [true; 3],
+    )?;
+    let space_group = [
+        "_space_group_name_h-m_alt",
+        "_symmetry_space_group_name_h-m",
+    ]
+    .iter()
+    .find_map(|tag| block.item(tag))
+    .map(|name| name.trim().to_string());
+    Ok(CifStructure {
+        graph,
+        name: block.name.clone(),
+        labels: atoms.iter().map(|atom| atom.3.to_string()).collect(),
+        space_group,
+        occupancies: atoms.iter().map(|atom| atom.2).collect(),
+    })
+}
+
+/// The cell from the `_cell_length_*` and `_cell_angle_*` items.
+fn unit_cell(block: &Block) -> Result<Matrix3<f32>, String> {
+    let parameter = |tag: &str| {
+        block
+            .item(tag)
+            .and_then(number)
+            .ok_or_else(|| format!("missing or invalid {tag}"))
+    };
+    let lengths = [
+        parameter("_cell_length_a")?,
+        parameter("_cell_length_b")?,
+        parameter("_cell_length_c")?,
+    ];
+    let angles = [
+        parameter("_cell_angle_alpha")?,
+        parameter("_cell_angle_beta")?,
+        parameter("_cell_angle_gamma")?,
+    ];
+    cell_from_parameters(lengths, angles).map_err(|err| err.to_string())
+}
+
+/// The symmetry operations of the block, or the identity if none are listed.
+fn symmetry_operations(block: &Block) -> Result<Vec<Operation>, String> {
+    [
+        "_space_group_symop_operation_xyz",
+        "_symmetry_equiv_pos_as_xyz",
+    ]
+    .iter()
+    .find_map(|tag| {
+        block
+            .column(tag)
+            .or_else(|| block.item(tag).map(|op| vec![op]))
+    })
+    .unwrap_or_else(|| vec!["x,y,z"])
+    .into_iter()
+    .map(|op| parse_operation(op).ok_or_else(|| format!("invalid symmetry operation {op:?}")))
+    .collect()
+}
+
+/// Element from a type symbol or label such as `Fe3+` or `O12`: its leading
+/// letters, trying two before one.
+fn element(symbol: &str) -> Option<i32> {
+    let letters: String = symbol
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .take(2)
+        .collect();
+    atomic_number(&letters).or_else(|| atomic_number(letters.get(..1)?))
+}
+
+/// Parses a symmetry operation such as `-x+1/2, y, z-y` into a rotation and
+/// translation acting on fractional coordinates.
+fn parse_operation(operation: &str) -> Option<Operation> {
+    let rows: Vec<&str> = operation.split(',').collect();
+    if rows.len() != 3 {
+        return None;
+    }
+    let mut rotation = Matrix3::zeros();
+    let mut translation = Vector3::zeros();
+    for (row, expression) in rows.iter().enumerate() {
+        let expression: String = expression
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if expression.is_empty() {
+            return None;
+        }
+        // Split into signed terms such as `-x`, `+1/2` or `0.5`
+        let mut terms = Vec::new();
+        let mut start = 0;
+        for (i, c) in expression.char_indices().skip(1) {
+            if c == '+' || c == '-' {
+                terms.push(&expression[start..i]);
+                start = i;
+            }
+        }
+        terms.push(&expression[start..]);
+
+        for term in terms {
+            let (sign, term) = match term.strip_prefix('-') {
+                Some(rest) => (-1.0, rest),
+                None => (1.0, term.strip_prefix('+').unwrap_or(term)),
+            };
+            let axis = term.chars().last().and_then(|c| "xyz".find(c));
+            let coefficient = match axis {
+                Some(_) => {
+                    let factor = term[..term.len() - 1].trim_end_matches('*');
+                    if factor.is_empty() {
+                        1.0
+                    } else {
+                        fraction(factor)?
+                    }
+                }
+                None => fraction(term)?,
+            };
+            match axis {
+                Some(axis) => rotation[(row, axis)] += sign * coefficient,
+                None => translation[row] += sign * coefficient,
+            }
+        }
+    }
+    Some((rotation, translation))
+}
+
+/// A decimal or a fraction such as `1/3`.
+fn fraction(value: &str) -> Option<f64> {
+    match value.split_once('/') {
+        Some((numerator, denominator)) => {
+            Some(numerator.parse::<f64>().ok()? / denominator.parse::<f64>().ok()?)
+        }
+        None => value.parse().ok(),
+    }
+}
+
+fn parse_error(line: usize, message: &str) -> PyErr {
+    PyValueError::new_err(format!("CIF line {line}: {message}"))
+}
@@ -0,0 +1,479 @@
+//! Binary dataset format for large collections of graphs.
+//!
+//! A file holds a 64-byte header followed by 8-byte aligned little-endian
+//! sections, in this order:
+//!
+//! | section          | type | length                   |
+//! |------------------|------|--------------------------|
+//! | atom offsets     | u64  | `n_graphs + 1`           |
+//! | atomic numbers   | i32  | `n_atoms`                |
+//! | positions        | f32  | `n_atoms * 3`            |
+//! | features         | f32  | `n_atoms * n_features`   |
+//! | targets          | f32  | `n_graphs * n_targets`   |
+//! | cells            | f32  | `n_graphs * 9` if stored |
+//! | periodicity      | u8   | `n_graphs` if stored     |
+//!
+//! Graph `g` owns atoms `offsets[g]..offsets[g + 1]`. Each periodicity byte
+//! holds the three `pbc` flags in bits 0 to 2 and whether the graph has a
+//! cell in bit 3.
+
+use crate::batch::{checked_charges, MolecularBatch};
+use crate::graph::MolecularGraph;
+use memmap2::Mmap;
+use nalgebra::{Matrix3, Vector3};
+use numpy::ndarray;
+use numpy::{
+    AllowTypeChange, PyArray1, PyArray2, PyArrayLikeDyn, PyReadonlyArray2, PyUntypedArrayMethods,
+};
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+const MAGIC: &[u8; 8] = b"VALENCE\0";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 64;
+const HAS_CELLS: u32 = 1;
+
+/// A dataset file opened through a memory map. Graphs, features and targets
+/// are decoded on access, so opening is instant regardless of file size and
+/// only the pages that are read are loaded.
+#[pyclass]
+pub struct GraphDataset {
+    map: Mmap,
+    layout: Layout,
+}
+
+#[pymethods]
+impl GraphDataset {
+    #[new]
+    /// Maps the dataset at `path`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be mapped or is not a valid dataset.
+    pub fn new(path: &str) -> PyResult<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the map is read-only; like any memory map it assumes the file
+        // is not truncated while open, and every access is bounds-checked
+        // against the layout validated here.
+        let map = unsafe { Mmap::map(&file)? };
+        let layout = Layout::read(&map)?;
+        Ok(GraphDataset { map, layout })
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn n_atoms(&self) -> usize {
+        self.layout.n_atoms
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn n_features(&self) -> usize {
+        self.layout.n_features
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn n_targets(&self) -> usize {
+        self.layout.n_targets
+    }
+
+    fn __len__(&self) -> usize {
+        self.layout.n_graphs
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<MolecularGraph> {
+        let index = self.index(index)?;
+        self.graph(index)
+    }
+
+    /// The graphs at `indices` as a batch. The file stores no charges, so
+    /// `charges` gives the total charge of each selected graph and defaults to
+    /// neutral.
+    ///
+    /// # Errors
+    /// Returns an error if an index is out of range or `charges` does not hold
+    /// one value per index.
+    #[pyo3(signature = (indices, charges=None))]
+    pub fn batch(
+        &self,
+        indices: Vec<isize>,
+        charges: Option<Vec<f32>>,
+    ) -> PyResult<MolecularBatch> {
+        let graphs = indices
+            .into_iter()
+            .map(|index| self.graph(self.index(index)?))
+            .collect::<PyResult<Vec<_>>>()?;
+        let charges = checked_charges(charges, graphs.len())?;
+        Ok(MolecularBatch { graphs, charges })
+    }
+
+    /// The `(n_atoms, n_features)` feature arrays of the graphs at `indices`.
+    ///
+    /// # Errors
+    /// Returns an error if an index is out of range.
+    pub fn features<'py>(
+        &self,
+        py: Python<'py>,
+        indices: Vec<isize>,
+    ) -> PyResult<Vec<Bound<'py, PyArray2<f32>>>> {
+        let width = self.layout.n_features;
+        indices
+            .into_iter()
+            .map(|index| {
+                let atoms = self.atoms(self.index(index)?);
+                let values = self.f32s(
+                    self.layout.features,
+                    atoms.start * width,
+                    atoms.len() * width,
+                );
+                let array = ndarray::Array2::from_shape_vec((atoms.len(), width), values)
+                    .map_err(|err| PyValueError::new_err(err.to_string()))?;
+                Ok(PyArray2::from_owned_array(py, array))
+            })
+            .collect()
+    }
+
+    /// The targets of the graphs at `indices`, shaped `(len(indices), n_targets)`.
+    ///
+    /// # Errors
+    /// Returns an error if an index is out of range.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn targets<'py>(
+        &self,
+        py: Python<'py>,
+        indices: Vec<isize>,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let width = self.layout.n_targets;
+        let mut values = Vec::with_capacity(indices.len() * width);
+        for &index in &indices {
+            let g = self.index(index)?;
+            values.extend(self.f32s(self.layout.targets, g * width, width));
+        }
+        let array = ndarray::Array2::from_shape_vec((indices.len(), width), values)
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(PyArray2::from_owned_array(py, array))
+    }
+
+    /// The number of atoms of every graph.
+    #[must_use]
+    pub fn sizes<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u64>> {
+        let offsets: Vec<u64> = (0..=self.layout.n_graphs).map(|g| self.offset(g)).collect();
+        let sizes: Vec<u64> = offsets.windows(2).map(|w| w[1] - w[0]).collect();
+        PyArray1::from_vec(py, sizes)
+    }
+}
+
+impl GraphDataset {
+    /// Resolves a Python index, negative ones counting from the end.
+    fn index(&self, index: isize) -> PyResult<usize> {
+        let n = self.layout.n_graphs;
+        let resolved = if index < 0 {
+            n.checked_sub(index.unsigned_abs())
+        } else {
+            Some(index.unsigned_abs()).filter(|&i| i < n)
+        };
+        resolved.ok_or_else(|| {
+            PyIndexError::new_err(format!("Graph index {index} out of range for {n} graphs"))
+        })
+    }
+
+    fn offset(&self, g: usize) -> u64 {
+        let start = self.layout.offsets + 8 * g;
+        u64::from_le_bytes(self.map[start..start + 8].try_into().unwrap_or_default())
+    }
+
+    /// The atom range of graph `g`; offsets were checked when opening.
+    #[allow(clippy::cast_possible_truncation)]
+    fn atoms(&self, g: usize) -> std::ops::Range<usize> {
+        self.offset(g) as usize..self.offset(g + 1) as usize
+    }
+
+    /// Decodes `count` f32 values starting `first` values into the section at
+    /// byte offset `section`.
+    fn f32s(&self, section: usize, first: usize, count: usize) -> Vec<f32> {
+        let start = section + 4 * first;
+        self.map[start..start + 4 * count]
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap_or_default()))
+            .collect()
+    }
+
+    /// Decodes graph `g`, which must be in range.
+    ///
+    /// # Errors
+    /// Returns an error if the stored cell is degenerate.
+    pub(crate) fn graph(&self, g: usize) -> PyResult<MolecularGraph> {
+        let atoms = self.atoms(g);
+        let start = self.layout.atomic_numbers + 4 * atoms.start;
+        let atomic_numbers = self.map[start..start + 4 * atoms.len()]
+            .chunks_exact(4)
+            .map(|bytes| i32::from_le_bytes(bytes.try_into().unwrap_or_default()))
+            .collect();
+        let positions = self
+            .f32s(self.layout.positions, 3 * atoms.start, 3 * atoms.len())
+            .chunks_exact(3)
+            .map(|r| Vector3::new(r[0], r[1], r[2]))
+            .collect();
+        let (cell, pbc) = match self.layout.cells {
+            Some((cells, flags)) => {
+                let flags = self.map[flags + g];
+                let rows = self.f32s(cells, 9 * g, 9);
+                let cell = (flags & 8 != 0).then(|| Matrix3::from_row_slice(&rows));
+                (cell, [flags & 1 != 0, flags & 2 != 0, flags & 4 != 0])
+            }
+            None => (None, [false; 3]),
+        };
+        MolecularGraph::from_parts(atomic_numbers, positions, cell, pbc)
+    }
+}
+
+/// Writes `graphs` to a dataset file at `path`.
+///
+/// `features` holds one `(n_atoms, n_features)` array per graph and
+/// `targets` one row (or value) per graph. Cells and periodicity are stored
+/// when any graph has a cell.
+///
+/// # Errors
+/// Returns an error if the file cannot be written or the features or targets
+/// do not match the graphs.
+#[pyfunction]
+#[pyo3(signature = (path, graphs, features=None, targets=None))]
+#[allow(clippy::needless_pass_by_value)]
+pub fn write_dataset(
+    path: &str,
+    graphs: Vec<PyRef<'_, MolecularGraph>>,
+    features: Option<Vec<PyReadonlyArray2<'_, f32>>>,
+    targets: Option<PyArrayLikeDyn<'_, f32, AllowTypeChange>>,
+) -> PyResult<()> {
+    let n_graphs = graphs.len();
+    let n_atoms: usize = graphs.iter().map(|graph| graph.positions.len()).sum();
+
+    let n_features = match &features {
+        Some(features) => {
+            if features.len() != n_graphs {
+                return Err(PyValueError::new_err(format!(
+                    "Expected one feature array per graph ({n_graphs}), got {}",
+                    features.len()
+                )));
+            }
+            let width = features.first().map_or(0, |f| f.shape()[1]);
+            for (index, (graph, feats)) in graphs.iter().zip(features).enumerate() {
+                if feats.shape() != [graph.positions.len(), width] {
+                    return Err(PyValueError::new_err(format!(
+                        "Graph {index}: expected features of shape ({}, {width}), got {:?}",
+                        graph.positions.len(),
+                        feats.shape()
+                    )));
+                }
+            }
+            width
+        }
+        None => 0,
+    };
+    let n_targets = match targets.as_ref().map(|t| t.shape().to_vec()) {
+        None => 0,
+        Some(shape) if shape.first() == Some(&n_graphs) && shape.len() <= 2 => {
+            shape.get(1).copied().unwrap_or(1)
+        }
+        Some(shape) => {
+            return Err(PyValueError::new_err(format!(
+                "Expected targets with {n_graphs} rows, got shape {shape:?}"
+            )))
+        }
+    };
+    let has_cells = graphs.iter().any(|graph| graph.cell.is_some());
+
+    let mut out = Writer::new(BufWriter::new(File::create(path)?));
+    out.bytes(MAGIC)?;
+    out.bytes(&VERSION.to_le_bytes())?;
+    out.bytes(&(if has_cells { HAS_CELLS } else { 0 }).to_le_bytes())?;
+    for count in [n_graphs, n_atoms, n_features, n_targets] {
+        out.bytes(&(count as u64).to_le_bytes())?;
+    }
+    out.align(HEADER_LEN)?;
+
+    let mut offset = 0u64;
+    out.bytes(&offset.to_le_bytes())?;
+    for graph in &graphs {
+        offset += graph.positions.len() as u64;
+        out.bytes(&offset.to_le_bytes())?;
+    }
+    out.align(8)?;
+    for graph in &graphs {
+        for z in &graph.atomic_numbers {
+            out.bytes(&z.to_le_bytes())?;
+        }
+    }
+    out.align(8)?;
+    for graph in &graphs {
+        out.f32s(graph.positions.iter().flat_map(|r| [r.x, r.y, r.z]))?;
+    }
+    out.align(8)?;
+    for feats in features.iter().flatten() {
+        out.f32s(feats.as_array().iter().copied())?;
+    }
+    out.align(8)?;
+    if let Some(targets) = &targets {
+        out.f32s(targets.as_array().iter().copied())?;
+    }
+    out.align(8)?;
+    if has_cells {
+        for graph in &graphs {
+            let cell = graph.cell.unwrap_or_else(Matrix3::zeros);
+            out.f32s(cell.transpose().iter().copied())?;
+        }
+        for graph in &graphs {
+            let flags = graph
+                .pbc
+                .iter()
+                .enumerate()
+                .fold(u8::from(graph.cell.is_some()) << 3, |bits, (k, &p)| {
+                    bits | (u8::from(p) << k)
+                });
+            out.bytes(&[flags])?;
+        }
+        out.align(8)?;
+    }
+    out.finish()
+}
+
+/// Byte offsets of the sections of a dataset file.
+struct Layout {
+    n_graphs: usize,
+    n_atoms: usize,
+    n_features: usize,
+    n_targets: usize,
+    offsets: usize,
+    atomic_numbers: usize,
+    positions: usize,
+    features: usize,
+    targets: usize,
+    /// Offsets of the cells and periodicity flags, if stored.
+    cells: Option<(usize, usize)>,
+}
+
+impl Layout {
+    /// Reads and validates the header against the size of `data`.
+    fn read(data: &[u8]) -> PyResult<Self> {
+        let invalid = |message: &str| PyValueError::new_err(format!("Invalid dataset: {message}"));
+        if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+            return Err(invalid("missing header"));
+        }
+        let u32_at =
+            |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap_or_default());
+        let u64_at =
+            |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap_or_default());
+        if u32_at(8) != VERSION {
+            return Err(invalid(&format!("unsupported version {}", u32_at(8))));
+        }
+        let has_cells = u32_at(12) & HAS_CELLS != 0;
+        let count = |at: usize| usize::try_from(u64_at(at)).map_err(|_| invalid("count too large"));
+        let (n_graphs, n_atoms, n_features, n_targets) =
+            (count(16)?, count(24)?, count(32)?, count(40)?);
+
+        // Sections are laid out back to back; overflow means a corrupt header
+        let mut end = HEADER_LEN;
+        let mut section = |bytes: Option<usize>| -> PyResult<usize> {
+            let start = end;
+            end = bytes
+                .and_then(|b| start.checked_add(b))
+                .and_then(|e| e.checked_next_multiple_of(8))
+                .ok_or_else(|| invalid("section sizes overflow"))?;
+            Ok(start)
+        };
+        let offsets = section(n_graphs.checked_add(1).and_then(|n| n.checked_mul(8)))?;
+        let atomic_numbers = section(n_atoms.checked_mul(4))?;
+        let positions = section(n_atoms.checked_mul(12))?;
+        let features = section(
+            n_atoms
+                .checked_mul(n_features)
+                .and_then(|n| n.checked_mul(4)),
+        )?;
+        let targets = section(
+            n_graphs
+                .checked_mul(n_targets)
+                .and_then(|n| n.checked_mul(4)),
+        )?;
+        let cells = if has_cells {
+            let cells = section(n_graphs.checked_mul(36))?;
+            let flags = section(Some(n_graphs))?;
+            Some((cells, flags))
+        } else {
+            None
+        };
+        if data.len() < end {
+            return Err(invalid(&format!(
+                "expected at least {end} bytes, found {}",
+                data.len()
+            )));
+        }
+
+        // Offsets must run from 0 to n_atoms without decreasing
+        let mut previous = 0;
+        for g in 0..=n_graphs {
+            let offset = u64_at(offsets + 8 * g);
+            let valid = if g == 0 {
+                offset == 0
+            } else {
+                offset >= previous
+            };
+            if !valid || offset > n_atoms as u64 {
+                return Err(invalid("atom offsets are not sorted within 0..n_atoms"));
+            }
+            previous = offset;
+        }
+        if previous != n_atoms as u64 {
+            return Err(invalid("atom offsets do not end at n_atoms"));
+        }
+        Ok(Layout {
+            n_graphs,
+            n_atoms,
+            n_features,
+            n_targets,
+            offsets,
+            atomic_numbers,
+            positions,
+            features,
+            targets,
+            cells,
+        })
+    }
+}
+
+/// Writes sections while tracking the position for alignment padding.
+struct Writer<W> {
+    out: W,
+    written: usize,
+}
+
+impl<W: Write> Writer<W> {
+    fn new(out: W) -> Self {
+        Writer { out, written: 0 }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> PyResult<()> {
+        self.out.write_all(bytes)?;
+        self.written += bytes.len();
+        Ok(())
+    }
+
+    fn f32s(&mut self, values: impl Iterator<Item = f32>) -> PyResult<()> {
+        for value in values {
+            self.bytes(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Pads with zeros up to the next multiple of `alignment`.
+    fn align(&mut self, alignment: usize) -> PyResult<()> {
+        let padding = self.written.next_multiple_of(alignment) - self.written;
+        self.bytes(&vec![0; padding])
+    }
+
+    fn finish(mut self) -> PyResult<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
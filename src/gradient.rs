@@ -0,0 +1,211 @@
+use crate::graph::{MolecularGraph, RadialBasis};
+use crate::model::GNNModel;
+use nalgebra::{DMatrix, DVector, Matrix3, Vector3};
+use numpy::ndarray;
+use rayon::prelude::*;
+
+impl MolecularGraph {
+    /// Reverse-mode pass through Linear -> Aggregation -> RBF -> Distance.
+    ///
+    /// Takes the upstream gradient `dL/dY` (one row per atom, one column per
+    /// model output) and returns `dL/dR` for every atom position.
+    /// The radial envelope vanishes smoothly at the cutoff, so pairs crossing
+    /// it contribute no jump in energy or gradient.
+    #[must_use]
+    pub fn backward_positions_internal(
+        &self,
+        model: &GNNModel,
+        atom_view: &ndarray::ArrayView2<f32>,
+        grad_output: &ndarray::ArrayView2<f32>,
+        cutoff: f32,
+        num_offsets: usize,
+    ) -> Vec<Vector3<f32>> {
+        self.backward_with_strain_internal(model, atom_view, grad_output, cutoff, num_offsets)
+            .0
+    }
+
+    /// Same as `backward_positions_internal`, but also returns the strain
+    /// derivative `dL/d(eps) = sum_pairs (dL/dr_ij) (x) r_ij`, i.e. the virial
+    /// accumulated from pairwise displacement times pair force.
+    #[must_use]
+    pub fn backward_with_strain_internal(
+        &self,
+        model: &GNNModel,
+        atom_view: &ndarray::ArrayView2<f32>,
+        grad_output: &ndarray::ArrayView2<f32>,
+        cutoff: f32,
+        num_offsets: usize,
+    ) -> (Vec<Vector3<f32>>, Matrix3<f64>) {
+        let n = self.positions.len();
+        let basis = RadialBasis::new(cutoff, num_offsets);
+        let shifts = self.image_shifts(cutoff);
+
+        // 1. Linear layer: h_i = W^T g_i is the sensitivity of L to the aggregate of atom i
+        let sensitivities: Vec<DVector<f32>> = (0..n)
+            .into_par_iter()
+            .map(|i| {
+                let upstream = DVector::from_iterator(
+                    model.weights.nrows(),
+                    grad_output.row(i).iter().copied(),
+                );
+                model.weights.tr_mul(&upstream)
+            })
+            .collect();
+
+        // 2. Aggregation + RBF + Distance: each atom gathers the gradient of every pair it is part of
+        let per_atom: Vec<(Vector3<f32>, Matrix3<f64>)> = (0..n)
+            .into_par_iter()
+            .map(|i| {
+                let mut grad = Vector3::zeros();
+                let mut strain = Matrix3::zeros();
+                for j in 0..n {
+                    for (s, shift) in shifts.iter().enumerate() {
+                        if i == j && s == 0 {
+                            continue;
+                        }
+                        let r_ij = self.positions[i] - self.positions[j] - shift;
+                        let dist = f64::from(r_ij.norm());
+                        // Coincident atoms have no defined direction
+                        if dist <= basis.cutoff && dist > 0.0 {
+                            // d_ij enters both a_i (weighting x_j) and a_j (weighting x_i)
+                            let coupling = dot_row(&sensitivities[i], &atom_view.row(j))
+                                + dot_row(&sensitivities[j], &atom_view.row(i));
+                            let scale = basis.derivative(dist) * f64::from(coupling) / dist;
+                            #[allow(clippy::cast_possible_truncation)]
+                            {
+                                grad += r_ij * (scale as f32);
+                            }
+                            // Every pair is visited from both ends, hence the half
+                            let r = r_ij.cast::<f64>();
+                            strain += (r * r.transpose()) * (0.5 * scale);
+                        }
+                    }
+                }
+                (grad, strain)
+            })
+            .collect();
+
+        let strain = per_atom.iter().map(|(_, s)| s).sum();
+        (per_atom.into_iter().map(|(g, _)| g).collect(), strain)
+    }
+
+    /// Reverse-mode pass for the model parameters.
+    ///
+    /// Since `y_i = W a_i`, the weight gradient is `dL/dW = sum_i g_i a_i^T`,
+    /// returned with the same shape as `model.weights`.
+    #[must_use]
+    pub fn backward_weights_internal(
+        &self,
+        model: &GNNModel,
+        atom_view: &ndarray::ArrayView2<f32>,
+        grad_output: &ndarray::ArrayView2<f32>,
+        cutoff: f32,
+        num_offsets: usize,
+    ) -> DMatrix<f32> {
+        let (rows, cols) = model.weights.shape();
+        let aggregated = self.compute_core_fused(cutoff, num_offsets, atom_view);
+        aggregated
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, agg)| {
+                let upstream = DVector::from_iterator(rows, grad_output.row(i).iter().copied());
+                upstream * agg.transpose()
+            })
+            .reduce(|| DMatrix::zeros(rows, cols), |acc, outer| acc + outer)
+    }
+
+    /// Jacobian of the summed aggregate `A = sum_i a_i` with respect to positions.
+    ///
+    /// Entry `[k][b]` is `dA_b / dR_k`. Because the model is linear in `W`, any
+    /// readout force is `F_k = -sum_b h_b dA_b/dR_k` with `h = W^T readout`.
+    #[must_use]
+    pub fn aggregate_jacobian_internal(
+        &self,
+        atom_view: &ndarray::ArrayView2<f32>,
+        cutoff: f32,
+        num_offsets: usize,
+    ) -> Vec<Vec<Vector3<f32>>> {
+        let n = self.positions.len();
+        let num_feats = atom_view.shape()[1];
+        let basis = RadialBasis::new(cutoff, num_offsets);
+        let shifts = self.image_shifts(cutoff);
+
+        (0..n)
+            .into_par_iter()
+            .map(|k| {
+                let mut jacobian = vec![Vector3::zeros(); num_feats];
+                for j in 0..n {
+                    for (s, shift) in shifts.iter().enumerate() {
+                        if k == j && s == 0 {
+                            continue;
+                        }
+                        let r_kj = self.positions[k] - self.positions[j] - shift;
+                        let dist = f64::from(r_kj.norm());
+                        if dist <= basis.cutoff && dist > 0.0 {
+                            #[allow(clippy::cast_possible_truncation)]
+                            let direction = r_kj * ((basis.derivative(dist) / dist) as f32);
+                            for (b, column) in jacobian.iter_mut().enumerate() {
+                                *column += direction * (atom_view[[k, b]] + atom_view[[j, b]]);
+                            }
+                        }
+                    }
+                }
+                jacobian
+            })
+            .collect()
+    }
+
+    /// Scalar readout `E = sum_i readout . y_i` and its gradient `dE/dR`.
+    #[must_use]
+    pub fn energy_and_gradient_internal(
+        &self,
+        model: &GNNModel,
+        atom_view: &ndarray::ArrayView2<f32>,
+        readout: &DVector<f32>,
+        cutoff: f32,
+        num_offsets: usize,
+    ) -> (f32, Vec<Vector3<f32>>) {
+        let (energy, gradient, _) =
+            self.energy_gradient_strain_internal(model, atom_view, readout, cutoff, num_offsets);
+        (energy, gradient)
+    }
+
+    /// Scalar readout, its gradient `dE/dR` and its strain derivative `dE/d(eps)`.
+    ///
+    /// Dividing the strain derivative by the cell volume gives the stress tensor.
+    #[must_use]
+    pub fn energy_gradient_strain_internal(
+        &self,
+        model: &GNNModel,
+        atom_view: &ndarray::ArrayView2<f32>,
+        readout: &DVector<f32>,
+        cutoff: f32,
+        num_offsets: usize,
+    ) -> (f32, Vec<Vector3<f32>>, Matrix3<f64>) {
+        let n = self.positions.len();
+        let aggregated = self.compute_core_fused(cutoff, num_offsets, atom_view);
+        let total = aggregated
+            .iter()
+            .fold(DVector::zeros(atom_view.shape()[1]), |acc, agg| acc + agg);
+        let energy = readout.dot(&(&model.weights * total));
+
+        let grad_output = ndarray::Array2::from_shape_fn((n, readout.len()), |(_, f)| readout[f]);
+        let (gradient, strain) = self.backward_with_strain_internal(
+            model,
+            atom_view,
+            &grad_output.view(),
+            cutoff,
+            num_offsets,
+        );
+        (energy, gradient, strain)
+    }
+}
+
+#[inline]
+fn dot_row(sensitivity: &DVector<f32>, features: &ndarray::ArrayView1<f32>) -> f32 {
+    sensitivity
+        .iter()
+        .zip(features.iter())
+        .map(|(h, x)| h * x)
+        .sum()
+}
@@ -1,8 +1,13 @@
 use crate::model::GNNModel;
-use nalgebra::{DVector, Vector3};
+use nalgebra::{DVector, DVectorViewMut, Matrix3, Vector3};
 use numpy::ndarray;
-use numpy::{PyArray2, PyArrayMethods, PyReadonlyArray2};
+use numpy::{
+    AllowTypeChange, PyArray1, PyArray2, PyArrayLike1, PyArrayLike2, PyArrayLikeDyn,
+    PyArrayMethods, PyReadonlyArray1, PyReadonlyArray2, PyReadwriteArray2, PyUntypedArrayMethods,
+};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use rayon::prelude::*;
 
 #[pyclass]
@@ -11,6 +16,9 @@ pub struct MolecularGraph {
     #[pyo3(get)]
     pub atomic_numbers: Vec<i32>,
     pub positions: Vec<Vector3<f32>>,
+    /// Lattice vectors as rows; `None` for isolated molecules.
+    pub cell: Option<Matrix3<f32>>,
+    pub pbc: [bool; 3],
 }
 
 #[pymethods]
@@ -18,115 +26,598 @@ impl MolecularGraph {
     #[new]
     /// Creates a new `MolecularGraph`.
     ///
+    /// `cell` holds the lattice vectors as rows. When a cell is given and
+    /// `pbc` is omitted, all three directions are periodic.
+    ///
     /// # Errors
-    /// Returns an error if the positions array is not shaped correctly.
+    /// Returns an error if the positions array is not shaped correctly or the
+    /// periodic cell is missing or degenerate.
+    #[pyo3(signature = (atomic_numbers, positions, cell=None, pbc=None))]
     #[allow(clippy::needless_pass_by_value)]
-    pub fn new(atomic_numbers: Vec<i32>, positions: PyReadonlyArray2<f32>) -> PyResult<Self> {
+    pub fn new(
+        atomic_numbers: Vec<i32>,
+        positions: PyReadonlyArray2<f32>,
+        cell: Option<PyReadonlyArray2<f32>>,
+        pbc: Option<[bool; 3]>,
+    ) -> PyResult<Self> {
         let pos_view = positions.as_array();
         let pos: Vec<Vector3<f32>> = pos_view
             .axis_iter(ndarray::Axis(0))
             .map(|row| Vector3::new(row[0], row[1], row[2]))
             .collect();
-        Ok(MolecularGraph {
-            atomic_numbers,
-            positions: pos,
+        let cell = cell.as_ref().map(cell_from_array).transpose()?;
+        let pbc = pbc.unwrap_or([cell.is_some(); 3]);
+        Self::from_parts(atomic_numbers, pos, cell, pbc)
+    }
+
+    /// Cartesian positions as an `(n_atoms, 3)` array.
+    #[getter]
+    #[must_use]
+    pub fn positions(&self, py: Python<'_>) -> Py<PyArray2<f32>> {
+        let rows = ndarray::Array2::from_shape_fn((self.positions.len(), 3), |(i, c)| {
+            self.positions[i][c]
+        });
+        PyArray2::from_array(py, &rows).into()
+    }
+
+    /// Lattice vectors as rows, or `None` for isolated molecules.
+    #[getter]
+    #[must_use]
+    pub fn cell(&self, py: Python<'_>) -> Option<Py<PyArray2<f32>>> {
+        self.cell.map(|cell| {
+            let rows = ndarray::Array2::from_shape_fn((3, 3), |(r, c)| cell[(r, c)]);
+            PyArray2::from_array(py, &rows).into()
         })
     }
 
+    #[getter]
+    #[must_use]
+    pub fn pbc(&self) -> [bool; 3] {
+        self.pbc
+    }
+
+    /// Builds a graph from an `ase.Atoms` object.
+    ///
+    /// `numbers`, `positions`, `cell` and `pbc` are read as arrays
+    /// straight from the attributes, converting dtypes where needed. An
+    /// all-zero ASE cell means no cell.
+    ///
+    /// # Errors
+    /// Returns an error if an attribute is missing or has the wrong shape, or
+    /// a periodic direction has no cell.
+    #[staticmethod]
+    pub fn from_ase(atoms: &Bound<'_, PyAny>) -> PyResult<Self> {
+        // ASE stores int64 numbers and float64 positions, which are borrowed as is
+        let numbers: PyArrayLike1<i64, AllowTypeChange> = atoms.getattr("numbers")?.extract()?;
+        let positions: PyArrayLike2<f64, AllowTypeChange> =
+            atoms.getattr("positions")?.extract()?;
+        let cell: PyArrayLike2<f32, AllowTypeChange> = atoms.getattr("cell")?.extract()?;
+        let pbc: PyArrayLike1<bool, AllowTypeChange> = atoms.getattr("pbc")?.extract()?;
+
+        let positions = positions.as_array();
+        if positions.shape()[1] != 3 {
+            return Err(PyValueError::new_err(format!(
+                "Expected positions of shape (n_atoms, 3), got {:?}",
+                positions.shape()
+            )));
+        }
+        let cell = cell_from_array(&cell)?;
+        let pbc = pbc.as_array().to_vec();
+        let [a, b, c] = pbc[..] else {
+            return Err(PyValueError::new_err(format!(
+                "Expected 3 pbc flags, got {}",
+                pbc.len()
+            )));
+        };
+        let numbers = numbers
+            .as_array()
+            .iter()
+            .map(|&z| {
+                i32::try_from(z)
+                    .map_err(|_| PyValueError::new_err(format!("Invalid atomic number {z}")))
+            })
+            .collect::<PyResult<_>>()?;
+        #[allow(clippy::cast_possible_truncation)]
+        let positions = positions
+            .outer_iter()
+            .map(|r| Vector3::new(r[0], r[1], r[2]).cast::<f32>())
+            .collect();
+        Self::from_parts(
+            numbers,
+            positions,
+            (cell != Matrix3::zeros()).then_some(cell),
+            [a, b, c],
+        )
+    }
+
+    /// Converts the graph to an `ase.Atoms` object.
+    ///
+    /// `predictions`, of shape `(n_atoms,)` or `(n_atoms, k)`, is attached
+    /// as the per-atom array `prediction_name`.
+    ///
+    /// # Errors
+    /// Returns an error if ASE cannot be imported or the predictions do not
+    /// have one row per atom.
+    #[pyo3(signature = (predictions=None, prediction_name="prediction"))]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn to_ase<'py>(
+        &self,
+        py: Python<'py>,
+        predictions: Option<PyArrayLikeDyn<'py, f64, AllowTypeChange>>,
+        prediction_name: &str,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let n = self.positions.len();
+        if let Some(shape) = predictions.as_ref().map(|p| p.shape().to_vec()) {
+            if shape.first() != Some(&n) || shape.len() > 2 {
+                return Err(PyValueError::new_err(format!(
+                    "Expected predictions with {n} rows, got shape {shape:?}"
+                )));
+            }
+        }
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("numbers", PyArray1::from_slice(py, &self.atomic_numbers))?;
+        kwargs.set_item("positions", self.positions(py))?;
+        if let Some(cell) = self.cell(py) {
+            kwargs.set_item("cell", cell)?;
+        }
+        kwargs.set_item("pbc", self.pbc)?;
+        let atoms = py
+            .import("ase")?
+            .getattr("Atoms")?
+            .call((), Some(&kwargs))?;
+        if let Some(predictions) = predictions {
+            atoms.call_method1("set_array", (prediction_name, predictions.as_any()))?;
+        }
+        Ok(atoms)
+    }
+
     /// The flagship high-performance forward pass.
     /// Fuses: Neighbor Search -> RBF Expansion -> Aggregation -> Linear Transformation.
-    #[must_use]
+    ///
+    /// The output is written straight into `out` when given, which must be a
+    /// writeable C-contiguous float32 array of shape `(n_atoms, n_outputs)`,
+    /// and `out` itself is returned.
+    ///
+    /// # Errors
+    /// Returns an error if the features do not match the graph and model, or
+    /// `out` has the wrong shape or layout or is already borrowed.
+    #[allow(clippy::needless_pass_by_value)]
+    #[pyo3(signature = (model, atom_features, cutoff, num_offsets, out=None))]
+    pub fn run_fused_with_model<'py>(
+        &self,
+        model: &GNNModel,
+        atom_features: PyReadonlyArray2<'py, f32>,
+        cutoff: f32,
+        num_offsets: usize,
+        out: Option<Bound<'py, PyArray2<f32>>>,
+    ) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let py = atom_features.py();
+        let atom_view = atom_features.as_array();
+        check_feature_shape(&atom_view, self.positions.len(), model)
+            .map_err(PyValueError::new_err)?;
+        let shape = [self.positions.len(), model.weights.nrows()];
+        let out = out.unwrap_or_else(|| PyArray2::zeros(py, shape, false));
+        {
+            let mut buffer = checked_out(&out, shape)?;
+            let buffer = buffer.as_slice_mut()?;
+            // The heavy lifting runs detached so other Python threads keep going
+            py.detach(|| self.write_fused(model, cutoff, num_offsets, &atom_view, buffer, true));
+        }
+        Ok(out)
+    }
+
+    /// Energy and forces for the scalar readout `E = sum_i readout . y_i`.
+    ///
+    /// `readout` defaults to all ones, i.e. the sum of every model output.
+    /// Returns `(energy, forces)` where `forces = -dE/dR` has shape `(n_atoms, 3)`.
+    ///
+    /// # Errors
+    /// Returns an error if the feature shape or the readout length do not match the graph and model.
+    #[pyo3(signature = (model, atom_features, cutoff, num_offsets, readout=None))]
     #[allow(clippy::needless_pass_by_value)]
-    pub fn run_fused_with_model(
+    pub fn compute_forces(
         &self,
         model: &GNNModel,
         atom_features: PyReadonlyArray2<f32>,
         cutoff: f32,
         num_offsets: usize,
-    ) -> Py<PyArray2<f32>> {
-        #[allow(clippy::needless_pass_by_value)]
+        readout: Option<PyReadonlyArray1<f32>>,
+    ) -> PyResult<(f32, Py<PyArray2<f32>>)> {
         let py = atom_features.py();
-        let n = self.positions.len();
         let atom_view = atom_features.as_array();
+        check_feature_shape(&atom_view, self.positions.len(), model)
+            .map_err(PyValueError::new_err)?;
+        let readout = readout_vector(readout.as_ref(), model)?;
 
-        // 1. Core Computation: Search and Aggregate
-        let aggregated_results = self.compute_core_fused(cutoff, num_offsets, &atom_view);
+        let (energy, gradient) =
+            self.energy_and_gradient_internal(model, &atom_view, &readout, cutoff, num_offsets);
+        let forces = ndarray::Array2::from_shape_fn((gradient.len(), 3), |(i, k)| -gradient[i][k]);
+        Ok((energy, PyArray2::from_array(py, &forces).into()))
+    }
 
-        // 2. Linear Transformation and Output Formatting
-        // Result = Weights * Aggregated_Features
-        let results: Vec<Vec<f32>> = aggregated_results
-            .into_par_iter()
-            .map(|agg| {
-                let updated_vec = &model.weights * agg;
-                updated_vec.as_slice().to_vec()
-            })
-            .collect();
+    /// Energy, forces and the 3x3 stress tensor of a periodic graph.
+    ///
+    /// The stress is `(1/V) dE/d(eps)`, accumulated from pairwise displacement
+    /// times pair force during the same gradient pass that produces the forces.
+    /// Energies are taken to be in eV and lengths in Angstrom; `units` is one of
+    /// `"eV/A^3"`, `"GPa"`, `"kbar"` or `"bar"`.
+    ///
+    /// # Errors
+    /// Returns an error if the graph is not periodic, the units are unknown, or
+    /// the inputs do not match the graph and model.
+    #[pyo3(signature = (model, atom_features, cutoff, num_offsets, readout=None, units="eV/A^3"))]
+    #[allow(clippy::needless_pass_by_value)]
+    #[allow(clippy::type_complexity)]
+    pub fn compute_forces_and_stress(
+        &self,
+        model: &GNNModel,
+        atom_features: PyReadonlyArray2<f32>,
+        cutoff: f32,
+        num_offsets: usize,
+        readout: Option<PyReadonlyArray1<f32>>,
+        units: &str,
+    ) -> PyResult<(f32, Py<PyArray2<f32>>, Py<PyArray2<f32>>)> {
+        let py = atom_features.py();
+        let atom_view = atom_features.as_array();
+        let Some(volume) = self.volume() else {
+            return Err(PyValueError::new_err(
+                "Stress requires a periodic graph with a cell",
+            ));
+        };
+        check_feature_shape(&atom_view, self.positions.len(), model)
+            .map_err(PyValueError::new_err)?;
+        let factor = stress_unit_factor(units)?;
+        let readout = readout_vector(readout.as_ref(), model)?;
 
-        // 3. Buffer Transfer to Python Memory
-        let out_cols = results[0].len();
-        let out_array = PyArray2::zeros(py, [n, out_cols], false);
-        let mut out_view = unsafe { out_array.as_array_mut() };
+        let (energy, gradient, strain) =
+            self.energy_gradient_strain_internal(model, &atom_view, &readout, cutoff, num_offsets);
+        let forces = ndarray::Array2::from_shape_fn((gradient.len(), 3), |(i, k)| -gradient[i][k]);
+        #[allow(clippy::cast_possible_truncation)]
+        let stress = ndarray::Array2::from_shape_fn((3, 3), |(a, b)| {
+            (strain[(a, b)] / f64::from(volume) * factor) as f32
+        });
+        Ok((
+            energy,
+            PyArray2::from_array(py, &forces).into(),
+            PyArray2::from_array(py, &stress).into(),
+        ))
+    }
+}
 
-        for (i, row) in results.into_iter().enumerate() {
-            for (j, &val) in row.iter().enumerate() {
-                out_view[[i, j]] = val;
+impl MolecularGraph {
+    /// Rust-side constructor with the same validation as the Python one.
+    ///
+    /// # Errors
+    /// Returns an error if a periodic direction has no cell or the cell is degenerate.
+    pub fn from_parts(
+        atomic_numbers: Vec<i32>,
+        positions: Vec<Vector3<f32>>,
+        cell: Option<Matrix3<f32>>,
+        pbc: [bool; 3],
+    ) -> PyResult<Self> {
+        if atomic_numbers.len() != positions.len() {
+            return Err(PyValueError::new_err(format!(
+                "Got {} atomic numbers but {} positions",
+                atomic_numbers.len(),
+                positions.len()
+            )));
+        }
+        check_periodicity(cell.as_ref(), pbc)?;
+        Ok(MolecularGraph {
+            atomic_numbers,
+            positions,
+            cell,
+            pbc,
+        })
+    }
+}
+
+/// Reads a `(3, 3)` array of lattice vectors as rows.
+pub(crate) fn cell_from_array(cell: &PyReadonlyArray2<f32>) -> PyResult<Matrix3<f32>> {
+    let view = cell.as_array();
+    if view.shape() != [3, 3] {
+        return Err(PyValueError::new_err(format!(
+            "Cell must have shape (3, 3), got {:?}",
+            view.shape()
+        )));
+    }
+    Ok(Matrix3::from_fn(|r, c| view[[r, c]]))
+}
+
+/// Checks that periodic directions have a cell with volume.
+pub(crate) fn check_periodicity(cell: Option<&Matrix3<f32>>, pbc: [bool; 3]) -> PyResult<()> {
+    if pbc.iter().any(|&p| p) {
+        match cell {
+            None => {
+                return Err(PyValueError::new_err(
+                    "Periodic boundary conditions require a cell",
+                ))
+            }
+            Some(c) if c.determinant().abs() < f32::EPSILON => {
+                return Err(PyValueError::new_err("Periodic cell has zero volume"))
             }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Cell with lattice vectors as rows from lengths `a, b, c` and angles
+/// `alpha, beta, gamma` in degrees, with `a` along x and `b` in the xy plane.
+///
+/// # Errors
+/// Returns an error if the parameters do not describe a cell with volume.
+pub fn cell_from_parameters(lengths: [f64; 3], angles: [f64; 3]) -> PyResult<Matrix3<f32>> {
+    let [a, b, c] = lengths;
+    let [cos_alpha, cos_beta, cos_gamma] = angles.map(|angle| angle.to_radians().cos());
+    let sin_gamma = angles[2].to_radians().sin();
+    let cx = c * cos_beta;
+    let cy = c * (cos_alpha - cos_beta * cos_gamma) / sin_gamma;
+    let cz_squared = c * c - cx * cx - cy * cy;
+    let valid = lengths.iter().all(|&l| l > 0.0) && sin_gamma.abs() > 1e-8 && cz_squared > 0.0;
+    if !valid {
+        return Err(PyValueError::new_err(format!(
+            "Invalid cell parameters {lengths:?} {angles:?}"
+        )));
+    }
+    let cell = Matrix3::new(
+        a,
+        0.0,
+        0.0,
+        b * cos_gamma,
+        b * sin_gamma,
+        0.0,
+        cx,
+        cy,
+        cz_squared.sqrt(),
+    );
+    Ok(cell.cast::<f32>())
+}
+
+/// Readout vector for scalar energies; defaults to summing every model output.
+pub(crate) fn readout_vector(
+    readout: Option<&PyReadonlyArray1<f32>>,
+    model: &GNNModel,
+) -> PyResult<DVector<f32>> {
+    let rows = model.weights.nrows();
+    let readout = match readout {
+        Some(r) => DVector::from_iterator(r.len(), r.as_array().iter().copied()),
+        None => DVector::from_element(rows, 1.0),
+    };
+    if readout.len() != rows {
+        return Err(PyValueError::new_err(format!(
+            "Readout has length {} but the model has {rows} outputs",
+            readout.len()
+        )));
+    }
+    Ok(readout)
+}
+
+/// Checks that `features` has one row per atom and one column per model input.
+///
+/// The error is a bare message so that batch callers can prefix the index of
+/// the offending graph.
+pub(crate) fn check_feature_shape(
+    features: &ndarray::ArrayView2<f32>,
+    n_atoms: usize,
+    model: &GNNModel,
+) -> Result<(), String> {
+    let expected = [n_atoms, model.weights.ncols()];
+    if features.shape() != expected {
+        return Err(format!(
+            "Expected atom features of shape ({}, {}), got {:?}",
+            expected[0],
+            expected[1],
+            features.shape()
+        ));
+    }
+    Ok(())
+}
+
+/// Conversion factor from eV/A^3 to the requested stress unit.
+pub(crate) fn stress_unit_factor(units: &str) -> PyResult<f64> {
+    match units {
+        "eV/A^3" => Ok(1.0),
+        "GPa" => Ok(EV_PER_A3_IN_GPA),
+        "kbar" => Ok(EV_PER_A3_IN_GPA * 10.0),
+        "bar" => Ok(EV_PER_A3_IN_BAR),
+        other => Err(PyValueError::new_err(format!(
+            "Unknown stress units '{other}', expected one of eV/A^3, GPa, kbar, bar"
+        ))),
+    }
+}
+
+/// Stress conversion factor, since 1 eV/A^3 = 160.21766208 `GPa`.
+pub(crate) const EV_PER_A3_IN_GPA: f64 = 160.217_662_08;
+/// 1 eV/A^3 in bar.
+pub(crate) const EV_PER_A3_IN_BAR: f64 = EV_PER_A3_IN_GPA * 1.0e4;
+
+/// Exponent `p` of the polynomial cutoff envelope.
+const ENVELOPE_EXPONENT: i32 = 6;
+
+/// Gaussian radial basis shared by the forward and backward passes.
+/// The edge weight is the sum over all centers, damped by a polynomial envelope
+/// so that it and its derivative go to zero at the cutoff:
+/// `phi(d) = u(d / cutoff) sum_k exp(-gamma (d - mu_k)^2)` with
+/// `u(x) = 1 - (p+1)(p+2)/2 x^p + p(p+2) x^(p+1) - p(p+1)/2 x^(p+2)`.
+pub(crate) struct RadialBasis {
+    pub(crate) cutoff: f64,
+    centers: Vec<f64>,
+    gamma: f64,
+}
+
+impl RadialBasis {
+    pub(crate) fn new(cutoff: f32, num_offsets: usize) -> Self {
+        #[allow(clippy::cast_precision_loss)]
+        let num_offsets_f64 = num_offsets as f64;
+        let cutoff_f64 = f64::from(cutoff);
+        #[allow(clippy::cast_precision_loss)]
+        let centers: Vec<f64> = (0..num_offsets)
+            .map(|i| (i as f64) * cutoff_f64 / num_offsets_f64)
+            .collect();
+        let gamma = 0.5 / (cutoff_f64 / num_offsets_f64).powi(2);
+        RadialBasis {
+            cutoff: cutoff_f64,
+            centers,
+            gamma,
         }
-        out_array.into()
+    }
+
+    #[inline]
+    pub(crate) fn value(&self, dist: f64) -> f64 {
+        self.gaussians(dist) * self.envelope(dist)
+    }
+
+    /// `d phi / d dist`.
+    #[inline]
+    pub(crate) fn derivative(&self, dist: f64) -> f64 {
+        let gaussians_derivative: f64 = self
+            .centers
+            .iter()
+            .map(|&mu| {
+                let delta = dist - mu;
+                -2.0 * self.gamma * delta * (-(self.gamma * delta.powi(2))).exp()
+            })
+            .sum();
+        gaussians_derivative * self.envelope(dist)
+            + self.gaussians(dist) * self.envelope_derivative(dist)
+    }
+
+    #[inline]
+    fn gaussians(&self, dist: f64) -> f64 {
+        self.centers
+            .iter()
+            .map(|&mu| (-(self.gamma * (dist - mu).powi(2))).exp())
+            .sum()
+    }
+
+    /// `u(dist / cutoff)`, which is zero from the cutoff on.
+    #[inline]
+    fn envelope(&self, dist: f64) -> f64 {
+        let x = dist / self.cutoff;
+        if x >= 1.0 {
+            return 0.0;
+        }
+        let p = f64::from(ENVELOPE_EXPONENT);
+        1.0 - (p + 1.0) * (p + 2.0) / 2.0 * x.powi(ENVELOPE_EXPONENT)
+            + p * (p + 2.0) * x.powi(ENVELOPE_EXPONENT + 1)
+            - p * (p + 1.0) / 2.0 * x.powi(ENVELOPE_EXPONENT + 2)
+    }
+
+    /// `d u(dist / cutoff) / d dist = -p(p+1)(p+2)/2 x^(p-1) (1-x)^2 / cutoff`.
+    #[inline]
+    fn envelope_derivative(&self, dist: f64) -> f64 {
+        let x = dist / self.cutoff;
+        if x >= 1.0 {
+            return 0.0;
+        }
+        let p = f64::from(ENVELOPE_EXPONENT);
+        -p * (p + 1.0) * (p + 2.0) / 2.0 * x.powi(ENVELOPE_EXPONENT - 1) * (1.0 - x).powi(2)
+            / self.cutoff
     }
 }
 
 impl MolecularGraph {
     /// Internal logic to handle the heavy O(N^2) math.
     /// This is the "Engine Room" of the project.
-    fn compute_core_fused(
+    pub(crate) fn compute_core_fused(
         &self,
         cutoff: f32,
         num_offsets: usize,
         atom_view: &ndarray::ArrayView2<f32>,
     ) -> Vec<DVector<f32>> {
         let n = self.positions.len();
-        let num_feats = atom_view.shape()[1];
 
         // Pre-calculate RBF constants to avoid repetitive math in the inner loop
-        #[allow(clippy::cast_precision_loss)]
-        let num_offsets_f64 = num_offsets as f64;
-        let cutoff_f64 = f64::from(cutoff);
-        #[allow(clippy::cast_precision_loss)]
-        let centers: Vec<f64> = (0..num_offsets)
-            .map(|i| (i as f64) * cutoff_f64 / num_offsets_f64)
-            .collect();
-        let gamma = 0.5 / (cutoff_f64 / num_offsets_f64).powi(2);
+        let basis = RadialBasis::new(cutoff, num_offsets);
+        let shifts = self.image_shifts(cutoff);
 
         (0..n)
             .into_par_iter()
-            .map(|i| {
-                let mut aggregated = DVector::zeros(num_feats);
-                for j in 0..n {
-                    if i == j {
-                        continue;
-                    }
+            .map(|i| self.aggregate_atom(i, &basis, &shifts, atom_view))
+            .collect()
+    }
+
+    /// Single-threaded [`Self::compute_core_fused`], for callers that already
+    /// parallelise over graphs.
+    pub(crate) fn compute_core_serial(
+        &self,
+        cutoff: f32,
+        num_offsets: usize,
+        atom_view: &ndarray::ArrayView2<f32>,
+    ) -> Vec<DVector<f32>> {
+        let basis = RadialBasis::new(cutoff, num_offsets);
+        let shifts = self.image_shifts(cutoff);
+
+        (0..self.positions.len())
+            .map(|i| self.aggregate_atom(i, &basis, &shifts, atom_view))
+            .collect()
+    }
+
+    /// Writes the model output of every atom into consecutive rows of `out`,
+    /// without intermediate buffers. Atoms are spread over the thread pool
+    /// when `parallel` is set.
+    pub(crate) fn write_fused(
+        &self,
+        model: &GNNModel,
+        cutoff: f32,
+        num_offsets: usize,
+        atom_view: &ndarray::ArrayView2<f32>,
+        out: &mut [f32],
+        parallel: bool,
+    ) {
+        let n_outputs = model.weights.nrows();
+        if n_outputs == 0 {
+            return;
+        }
+        let basis = RadialBasis::new(cutoff, num_offsets);
+        let shifts = self.image_shifts(cutoff);
+        let write_row = |(i, row): (usize, &mut [f32])| {
+            let aggregated = self.aggregate_atom(i, &basis, &shifts, atom_view);
+            DVectorViewMut::from_slice(row, n_outputs).gemv(1.0, &model.weights, &aggregated, 0.0);
+        };
+        if parallel {
+            out.par_chunks_mut(n_outputs)
+                .enumerate()
+                .for_each(write_row);
+        } else {
+            out.chunks_mut(n_outputs).enumerate().for_each(write_row);
+        }
+    }
+
+    /// RBF-weighted sum of the features of every neighbour of atom `i`.
+    fn aggregate_atom(
+        &self,
+        i: usize,
+        basis: &RadialBasis,
+        shifts: &[Vector3<f32>],
+        atom_view: &ndarray::ArrayView2<f32>,
+    ) -> DVector<f32> {
+        let num_feats = atom_view.shape()[1];
+        let cutoff_f64 = basis.cutoff;
+        let mut aggregated = DVector::zeros(num_feats);
+        for j in 0..self.positions.len() {
+            for (s, shift) in shifts.iter().enumerate() {
+                if i == j && s == 0 {
+                    continue;
+                }
 
-                    // Euclidean distance calculation
-                    let dist = f64::from((self.positions[i] - self.positions[j]).norm());
-
-                    if dist <= cutoff_f64 {
-                        // Optimized RBF weight sum
-                        let rbf_weight: f64 = centers
-                            .iter()
-                            .map(|&mu| (-(gamma * (dist - mu).powi(2))).exp())
-                            .sum();
-
-                        // Scatter-Add neighboring features into the local accumulator
-                        #[allow(clippy::cast_possible_truncation)]
-                        for f in 0..num_feats {
-                            aggregated[f] += (rbf_weight as f32) * atom_view[[j, f]];
-                        }
+                // Euclidean distance calculation (to the periodic image of j)
+                let dist = f64::from((self.positions[i] - self.positions[j] - shift).norm());
+
+                if dist <= cutoff_f64 {
+                    // Optimized RBF weight sum
+                    let rbf_weight = basis.value(dist);
+
+                    // Scatter-Add neighboring features into the local accumulator
+                    #[allow(clippy::cast_possible_truncation)]
+                    for f in 0..num_feats {
+                        aggregated[f] += (rbf_weight as f32) * atom_view[[j, f]];
                     }
                 }
-                aggregated
-            })
-            .collect()
+            }
+        }
+        aggregated
     }
 }
 
@@ -147,3 +638,24 @@ impl MolecularGraph {
             .collect()
     }
 }
+
+/// Borrows `out` for writing after checking that it is a C-contiguous array of
+/// the given shape.
+pub(crate) fn checked_out<'py>(
+    out: &Bound<'py, PyArray2<f32>>,
+    shape: [usize; 2],
+) -> PyResult<PyReadwriteArray2<'py, f32>> {
+    if out.shape() != shape {
+        return Err(PyValueError::new_err(format!(
+            "Expected out of shape ({}, {}), got {:?}",
+            shape[0],
+            shape[1],
+            out.shape()
+        )));
+    }
+    if !out.is_c_contiguous() {
+        return Err(PyValueError::new_err("out must be C-contiguous"));
+    }
+    out.try_readwrite()
+        .map_err(|err| PyValueError::new_err(format!("out cannot be written to: {err}")))
+}
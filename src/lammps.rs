@@ -0,0 +1,626 @@
+use crate::elements::{atomic_mass, atomic_number};
+use crate::graph::MolecularGraph;
+use nalgebra::{Matrix3, Vector3};
+use numpy::PyArray1;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// A LAMMPS snapshot: atoms sorted by id, positions relative to the box origin.
+#[pyclass]
+#[derive(Clone)]
+pub struct LammpsFrame {
+    pub graph: MolecularGraph,
+    /// The dump timestep; `None` for data files.
+    #[pyo3(get)]
+    pub timestep: Option<i64>,
+    #[pyo3(get)]
+    pub ids: Vec<i64>,
+    #[pyo3(get)]
+    pub types: Vec<i32>,
+    /// Dump columns other than id, type, element and coordinates, or the
+    /// charge (`q`) and molecule (`mol`) columns of data files.
+    pub columns: Vec<(String, Vec<f64>)>,
+}
+
+#[pymethods]
+impl LammpsFrame {
+    #[getter]
+    #[must_use]
+    pub fn graph(&self) -> MolecularGraph {
+        self.graph.clone()
+    }
+
+    /// Extra per-atom columns by name, as arrays in atom order.
+    ///
+    /// # Errors
+    /// Returns an error if the dict cannot be built.
+    #[getter]
+    pub fn columns<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (name, values) in &self.columns {
+            dict.set_item(name, PyArray1::from_slice(py, values))?;
+        }
+        Ok(dict)
+    }
+
+    fn __len__(&self) -> usize {
+        self.ids.len()
+    }
+}
+
+/// Lazily reads the frames of a LAMMPS text dump.
+#[pyclass]
+pub struct LammpsDumpReader {
+    lines: Lines<BufReader<File>>,
+    elements: HashMap<i32, i32>,
+}
+
+#[pymethods]
+impl LammpsDumpReader {
+    #[new]
+    #[pyo3(signature = (path, type_map=None))]
+    /// Opens `path` for reading; see [`read_lammps_dump`] for `type_map`.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened or `type_map` is invalid.
+    pub fn new(path: &str, type_map: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        Ok(LammpsDumpReader {
+            lines: Lines::new(BufReader::new(File::open(path)?)),
+            elements: type_elements(type_map)?,
+        })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<LammpsFrame>> {
+        read_dump_frame(&mut self.lines, &self.elements)
+    }
+}
+
+/// Reads every frame of a LAMMPS text dump (`dump atom` or `dump custom`).
+///
+/// `type_map` maps atom types to element symbols or atomic numbers; it can be
+/// omitted if the dump has an `element` column. Positions may be given as
+/// `x y z`, scaled `xs ys zs` or their unwrapped variants, and boundary
+/// flags other than `pp` make a direction non-periodic.
+///
+/// # Errors
+/// Returns an error if the file cannot be read, a frame is malformed, or an
+/// atom type has no element.
+#[pyfunction]
+#[pyo3(signature = (path, type_map=None))]
+pub fn read_lammps_dump(
+    path: &str,
+    type_map: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Vec<LammpsFrame>> {
+    let elements = type_elements(type_map)?;
+    let mut lines = Lines::new(BufReader::new(File::open(path)?));
+    let mut frames = Vec::new();
+    while let Some(frame) = read_dump_frame(&mut lines, &elements)? {
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Reads a LAMMPS data file as written by `write_data`.
+///
+/// `atom_style` is one of `atomic`, `charge`, `full`, `molecular`, `bond` or
+/// `angle`, and defaults to the style named in the `Atoms` section comment, or
+/// `atomic`. Atom types missing from `type_map` get the element whose standard
+/// mass matches the `Masses` section. The box is periodic in every direction.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or is malformed, the atom
+/// style is not supported, or an atom type has no element.
+#[pyfunction]
+#[pyo3(signature = (path, type_map=None, atom_style=None))]
+pub fn read_lammps_data(
+    path: &str,
+    type_map: Option<&Bound<'_, PyDict>>,
+    atom_style: Option<&str>,
+) -> PyResult<LammpsFrame> {
+    let mut elements = type_elements(type_map)?;
+    let text = std::fs::read_to_string(path)?;
+    let data = parse_data(&text, atom_style)?;
+    for (kind, mass) in data.masses {
+        if let Some(z) = element_with_mass(mass) {
+            elements.entry(kind).or_insert(z);
+        }
+    }
+    data.atoms.into_frame(None, data.cell, [true; 3], &elements)
+}
+
+/// Lines of the input, counted for error messages.
+pub struct Lines<R> {
+    reader: R,
+    line: usize,
+}
+
+impl<R: BufRead> Lines<R> {
+    pub fn new(reader: R) -> Self {
+        Lines { reader, line: 0 }
+    }
+
+    /// The next trimmed line, or `None` at the end of the input.
+    fn next(&mut self) -> PyResult<Option<String>> {
+        let mut text = String::new();
+        if self.reader.read_line(&mut text)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        Ok(Some(text.trim().to_string()))
+    }
+
+    /// The next line, which must exist.
+    fn expect(&mut self) -> PyResult<String> {
+        self.next()?
+            .ok_or_else(|| parse_error(self.line + 1, "the frame ended early"))
+    }
+}
+
+/// Per-atom data of a snapshot, before types are mapped to elements.
+#[derive(Default)]
+struct Atoms {
+    ids: Vec<i64>,
+    types: Vec<i32>,
+    /// Atomic numbers when the file names elements itself.
+    elements: Option<Vec<i32>>,
+    positions: Vec<Vector3<f64>>,
+    columns: Vec<(String, Vec<f64>)>,
+}
+
+impl Atoms {
+    /// A frame with atoms sorted by id and types mapped through `elements`
+    /// unless the file named them.
+    fn into_frame(
+        self,
+        timestep: Option<i64>,
+        cell: Matrix3<f32>,
+        pbc: [bool; 3],
+        elements: &HashMap<i32, i32>,
+    ) -> PyResult<LammpsFrame> {
+        let atomic_numbers = match self.elements {
+            Some(numbers) => numbers,
+            None => self
+                .types
+                .iter()
+                .map(|kind| {
+                    elements.get(kind).copied().ok_or_else(|| {
+                        PyValueError::new_err(format!(
+                            "Atom type {kind} has no element; pass it in type_map"
+                        ))
+                    })
+                })
+                .collect::<PyResult<_>>()?,
+        };
+        let mut order: Vec<usize> = (0..self.ids.len()).collect();
+        order.sort_by_key(|&i| self.ids[i]);
+        let sorted = |values: &[f64]| order.iter().map(|&i| values[i]).collect();
+
+        #[allow(clippy::cast_possible_truncation)]
+        let positions = order
+            .iter()
+            .map(|&i| self.positions[i].map(|x| x as f32))
+            .collect();
+        Ok(LammpsFrame {
+            graph: MolecularGraph::from_parts(
+                order.iter().map(|&i| atomic_numbers[i]).collect(),
+                positions,
+                Some(cell),
+                pbc,
+            )?,
+            timestep,
+            ids: order.iter().map(|&i| self.ids[i]).collect(),
+            types: order.iter().map(|&i| self.types[i]).collect(),
+            columns: self
+                .columns
+                .iter()
+                .map(|(name, values)| (name.clone(), sorted(values)))
+                .collect(),
+        })
+    }
+}
+
+/// The parts of a data file the reader uses.
+struct DataFile {
+    cell: Matrix3<f32>,
+    masses: Vec<(i32, f64)>,
+    atoms: Atoms,
+}
+
+fn parse_data(text: &str, atom_style: Option<&str>) -> PyResult<DataFile> {
+    let lines: Vec<&str> = text.lines().collect();
+    let content = |line: &str| line.split('#').next().unwrap_or("").trim().to_string();
+
+    // The header runs from the second line up to the first section name
+    let (mut n_atoms, mut n_types) = (0, 0);
+    let mut bounds = [[0.0; 2]; 3];
+    let mut tilts = [0.0; 3];
+    let mut index = 1;
+    while index < lines.len() {
+        let line = content(lines[index]);
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let invalid = || parse_error(index + 1, &format!("invalid header line {line:?}"));
+        let numbers = |count: usize| -> PyResult<Vec<f64>> {
+            fields[..count]
+                .iter()
+                .map(|f| f.parse().map_err(|_| invalid()))
+                .collect()
+        };
+        match fields.as_slice() {
+            [] => {}
+            [count, "atoms"] => n_atoms = count.parse().map_err(|_| invalid())?,
+            [count, "atom", "types"] => n_types = count.parse().map_err(|_| invalid())?,
+            [_, _, lo, hi] if lo.ends_with("lo") && hi.ends_with("hi") => {
+                let axis = "xyz".find(&lo[..1]).ok_or_else(invalid)?;
+                let values = numbers(2)?;
+                bounds[axis] = [values[0], values[1]];
+            }
+            [_, _, _, "xy", "xz", "yz"] => {
+                let values = numbers(3)?;
+                tilts = [values[0], values[1], values[2]];
+            }
+            // Counts of bonds, angles and so on
+            [count, ..] if count.parse::<f64>().is_ok() => {}
+            _ => break,
+        }
+        index += 1;
+    }
+
+    let mut masses = Vec::new();
+    let mut rows = Vec::new();
+    let mut style = atom_style.map(str::to_string);
+    while index < lines.len() {
+        let (name, hint) = lines[index].split_once('#').unwrap_or((lines[index], ""));
+        let name = name.trim();
+        let count = match name {
+            "Masses" => n_types,
+            "Atoms" => {
+                if style.is_none() && !hint.trim().is_empty() {
+                    style = Some(hint.trim().to_string());
+                }
+                n_atoms
+            }
+            _ => 0,
+        };
+        index += 1;
+        // Section rows follow a blank line; other sections are skipped
+        let mut read = 0;
+        while read < count {
+            let Some(line) = lines.get(index).map(|line| content(line)) else {
+                return Err(parse_error(
+                    index,
+                    &format!("the {name} section ended early"),
+                ));
+            };
+            index += 1;
+            if line.is_empty() {
+                continue;
+            }
+            if name == "Masses" {
+                let mut fields = line.split_whitespace();
+                let (Some(Ok(kind)), Some(Ok(mass))) = (
+                    fields.next().map(str::parse::<i32>),
+                    fields.next().map(str::parse::<f64>),
+                ) else {
+                    return Err(parse_error(index, &format!("invalid mass line {line:?}")));
+                };
+                masses.push((kind, mass));
+            } else {
+                rows.push((index, line));
+            }
+            read += 1;
+        }
+    }
+
+    let (origin, cell) = box_cell(bounds, tilts);
+    let atoms = data_atoms(&rows, style.as_deref().unwrap_or("atomic"), origin)?;
+    Ok(DataFile {
+        cell,
+        masses,
+        atoms,
+    })
+}
+
+/// Parses `Atoms` section rows, given with their line numbers.
+fn data_atoms(rows: &[(usize, String)], style: &str, origin: Vector3<f64>) -> PyResult<Atoms> {
+    // Columns of the type, charge, molecule id and x coordinate
+    let (type_column, charge_column, molecule_column, x_column) = match style {
+        "atomic" => (1, None, None, 2),
+        "charge" => (1, Some(2), None, 3),
+        "full" => (2, Some(3), Some(1), 4),
+        "molecular" | "bond" | "angle" => (2, None, Some(1), 3),
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "Unsupported atom style {other:?}, expected atomic, charge, full, molecular, bond or angle"
+            )))
+        }
+    };
+    let mut atoms = Atoms::default();
+    let mut charges = Vec::new();
+    let mut molecules = Vec::new();
+    for (line, row) in rows {
+        let fields: Vec<&str> = row.split_whitespace().collect();
+        let invalid = || parse_error(*line, &format!("expected a {style} atom line, got {row:?}"));
+        let value = |column: usize| -> PyResult<f64> {
+            fields
+                .get(column)
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(invalid)
+        };
+        atoms.ids.push(fields[0].parse().map_err(|_| invalid())?);
+        let kind = fields.get(type_column).and_then(|f| f.parse().ok());
+        atoms.types.push(kind.ok_or_else(invalid)?);
+        let r = Vector3::new(value(x_column)?, value(x_column + 1)?, value(x_column + 2)?);
+        atoms.positions.push(r - origin);
+        if let Some(column) = charge_column {
+            charges.push(value(column)?);
+        }
+        if let Some(column) = molecule_column {
+            molecules.push(value(column)?);
+        }
+    }
+    if charge_column.is_some() {
+        atoms.columns.push(("q".to_string(), charges));
+    }
+    if molecule_column.is_some() {
+        atoms.columns.push(("mol".to_string(), molecules));
+    }
+    Ok(atoms)
+}
+
+/// Parses the next frame of a dump, or `None` at the end of the input.
+///
+/// # Errors
+/// Returns an error if the input cannot be read or the frame is malformed.
+pub(crate) fn read_dump_frame<R: BufRead>(
+    lines: &mut Lines<R>,
+    elements: &HashMap<i32, i32>,
+) -> PyResult<Option<LammpsFrame>> {
+    let mut timestep = None;
+    let mut n_atoms = None;
+    let mut dump_box = None;
+    // Set after an ITEM this reader does not use, such as UNITS or TIME,
+    // whose value lines are skipped up to the next ITEM
+    let mut skipping = false;
+    loop {
+        let Some(item) = lines.next()? else {
+            return Ok(None);
+        };
+        if item.is_empty() {
+            continue;
+        }
+        let Some(item) = item.strip_prefix("ITEM:").map(str::trim) else {
+            if skipping {
+                continue;
+            }
+            return Err(parse_error(
+                lines.line,
+                &format!("expected an ITEM: line, got {item:?}"),
+            ));
+        };
+        skipping = false;
+        if item.starts_with("TIMESTEP") {
+            let value = lines.expect()?;
+            timestep = Some(
+                value
+                    .parse()
+                    .map_err(|_| parse_error(lines.line, "invalid timestep"))?,
+            );
+        } else if item.starts_with("NUMBER OF ATOMS") {
+            let value = lines.expect()?;
+            n_atoms = Some(
+                value
+                    .parse()
+                    .map_err(|_| parse_error(lines.line, "invalid atom count"))?,
+            );
+        } else if let Some(flags) = item.strip_prefix("BOX BOUNDS") {
+            dump_box = Some(read_box(lines, flags)?);
+        } else if let Some(names) = item.strip_prefix("ATOMS") {
+            let names: Vec<&str> = names.split_whitespace().collect();
+            let (Some(n_atoms), Some((origin, cell, pbc))) = (n_atoms, dump_box) else {
+                return Err(parse_error(
+                    lines.line,
+                    "ATOMS before NUMBER OF ATOMS and BOX BOUNDS",
+                ));
+            };
+            let atoms = read_atoms(lines, &names, n_atoms, origin, cell)?;
+            return atoms.into_frame(timestep, cell, pbc, elements).map(Some);
+        } else {
+            skipping = true;
+        }
+    }
+}
+
+/// Reads the three lines of `ITEM: BOX BOUNDS`, returning the origin, cell
+/// and periodicity.
+fn read_box<R: BufRead>(
+    lines: &mut Lines<R>,
+    flags: &str,
+) -> PyResult<(Vector3<f64>, Matrix3<f32>, [bool; 3])> {
+    let flags: Vec<&str> = flags.split_whitespace().collect();
+    let triclinic = flags.first() == Some(&"xy");
+    let mut pbc = [true; 3];
+    for (axis, flag) in flags
+        .iter()
+        .skip(if triclinic { 3 } else { 0 })
+        .take(3)
+        .enumerate()
+    {
+        pbc[axis] = *flag == "pp";
+    }
+    let mut rows = [[0.0; 3]; 3];
+    for row in &mut rows {
+        let text = lines.expect()?;
+        let values: Option<Vec<f64>> = text.split_whitespace().map(|v| v.parse().ok()).collect();
+        match values {
+            Some(values) if values.len() == if triclinic { 3 } else { 2 } => {
+                row[..values.len()].copy_from_slice(&values);
+            }
+            _ => {
+                return Err(parse_error(
+                    lines.line,
+                    &format!("invalid box bounds {text:?}"),
+                ))
+            }
+        }
+    }
+
+    // Triclinic bounds enclose the tilted box; shrink them to its edges
+    let tilts = [rows[0][2], rows[1][2], rows[2][2]];
+    let [xy, xz, yz] = tilts;
+    let bounds = [
+        [
+            rows[0][0] - 0.0f64.min(xy).min(xz).min(xy + xz),
+            rows[0][1] - 0.0f64.max(xy).max(xz).max(xy + xz),
+        ],
+        [rows[1][0] - 0.0f64.min(yz), rows[1][1] - 0.0f64.max(yz)],
+        [rows[2][0], rows[2][1]],
+    ];
+    let (origin, cell) = box_cell(bounds, tilts);
+    Ok((origin, cell, pbc))
+}
+
+/// Reads the rows of `ITEM: ATOMS` with the given column names.
+fn read_atoms<R: BufRead>(
+    lines: &mut Lines<R>,
+    names: &[&str],
+    n_atoms: usize,
+    origin: Vector3<f64>,
+    cell: Matrix3<f32>,
+) -> PyResult<Atoms> {
+    let find = |name: &str| names.iter().position(|n| *n == name);
+    let coordinates = [
+        ["x", "y", "z"],
+        ["xu", "yu", "zu"],
+        ["xs", "ys", "zs"],
+        ["xsu", "ysu", "zsu"],
+    ]
+    .iter()
+    .enumerate()
+    .find_map(|(kind, axes)| Some(([find(axes[0])?, find(axes[1])?, find(axes[2])?], kind >= 2)));
+    let (Some((xyz, scaled)), Some(id_column)) = (coordinates, find("id")) else {
+        return Err(parse_error(
+            lines.line,
+            "the dump needs id and x y z (or xs ys zs) columns",
+        ));
+    };
+    let type_column = find("type");
+    let element_column = find("element");
+    if type_column.is_none() && element_column.is_none() {
+        return Err(parse_error(
+            lines.line,
+            "the dump needs a type or element column",
+        ));
+    }
+    let extra: Vec<usize> = (0..names.len())
+        .filter(|c| {
+            ![Some(id_column), type_column, element_column].contains(&Some(*c)) && !xyz.contains(c)
+        })
+        .collect();
+
+    let to_cartesian = cell.cast::<f64>().transpose();
+    // NUMBER OF ATOMS comes from the file, so nothing is reserved for it up front
+    let mut atoms = Atoms {
+        elements: element_column.map(|_| Vec::new()),
+        columns: extra
+            .iter()
+            .map(|&c| (names[c].to_string(), Vec::new()))
+            .collect(),
+        ..Atoms::default()
+    };
+    for _ in 0..n_atoms {
+        let text = lines.expect()?;
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        if fields.len() != names.len() {
+            return Err(parse_error(
+                lines.line,
+                &format!("expected {} columns, got {}", names.len(), fields.len()),
+            ));
+        }
+        let line = lines.line;
+        let invalid = |column: usize| {
+            parse_error(
+                line,
+                &format!("invalid {} value {:?}", names[column], fields[column]),
+            )
+        };
+        let number = |column: usize| -> PyResult<f64> {
+            fields[column].parse().map_err(|_| invalid(column))
+        };
+        atoms
+            .ids
+            .push(fields[id_column].parse().map_err(|_| invalid(id_column))?);
+        atoms.types.push(match type_column {
+            Some(column) => fields[column].parse().map_err(|_| invalid(column))?,
+            None => 0,
+        });
+        if let (Some(column), Some(elements)) = (element_column, atoms.elements.as_mut()) {
+            let z = atomic_number(fields[column]).ok_or_else(|| {
+                parse_error(line, &format!("unknown element {:?}", fields[column]))
+            })?;
+            elements.push(z);
+        }
+        let r = Vector3::new(number(xyz[0])?, number(xyz[1])?, number(xyz[2])?);
+        atoms
+            .positions
+            .push(if scaled { to_cartesian * r } else { r - origin });
+        for (&column, (_, values)) in extra.iter().zip(&mut atoms.columns) {
+            values.push(number(column)?);
+        }
+    }
+    Ok(atoms)
+}
+
+/// Box origin and cell rows from LAMMPS bounds and `xy xz yz` tilt factors.
+fn box_cell(bounds: [[f64; 2]; 3], tilts: [f64; 3]) -> (Vector3<f64>, Matrix3<f32>) {
+    let [xy, xz, yz] = tilts;
+    let cell = Matrix3::new(
+        bounds[0][1] - bounds[0][0],
+        0.0,
+        0.0,
+        xy,
+        bounds[1][1] - bounds[1][0],
+        0.0,
+        xz,
+        yz,
+        bounds[2][1] - bounds[2][0],
+    );
+    let origin = Vector3::new(bounds[0][0], bounds[1][0], bounds[2][0]);
+    (origin, cell.cast::<f32>())
+}
+
+/// Converts a `{type: symbol or atomic number}` mapping to atomic numbers.
+fn type_elements(type_map: Option<&Bound<'_, PyDict>>) -> PyResult<HashMap<i32, i32>> {
+    let Some(type_map) = type_map else {
+        return Ok(HashMap::new());
+    };
+    type_map
+        .iter()
+        .map(|(kind, element)| {
+            let kind: i32 = kind.extract()?;
+            let z = match element.extract::<i32>() {
+                Ok(z) => Some(z),
+                Err(_) => atomic_number(&element.extract::<String>()?),
+            };
+            z.filter(|&z| z > 0).map(|z| (kind, z)).ok_or_else(|| {
+                PyValueError::new_err(format!("Unknown element {element} for atom type {kind}"))
+            })
+        })
+        .collect()
+}
+
+/// The element whose standard atomic mass is within 0.1 of `mass`.
+fn element_with_mass(mass: f64) -> Option<i32> {
+    (1..=118).find(|&z| atomic_mass(z).is_some_and(|m| (m - mass).abs() < 0.1))
+}
+
+fn parse_error(line: usize, message: &str) -> PyErr {
+    PyValueError::new_err(format!("LAMMPS line {line}: {message}"))
+}
@@ -1,18 +1,89 @@
 use pyo3::prelude::*;
 // Declare the modules
 pub mod batch;
+pub mod cif;
+pub mod dataset;
+pub mod elements;
+pub mod gradient;
 pub mod graph;
+pub mod lammps;
+pub mod md;
 pub mod model;
+pub mod neb;
+pub mod neighbors;
+pub mod optimize;
+pub mod pdb;
+pub mod pool;
+pub mod potential;
+pub mod scheduler;
+pub mod sdf;
+pub mod stream;
+pub mod thermostat;
+pub mod train;
+pub mod trajectory;
+pub mod vibrations;
+pub mod xyz;
 
 // Bring the structs into scope
-use crate::batch::MolecularBatch;
+use crate::batch::{GraphError, MolecularBatch};
+use crate::cif::{read_cif, CifStructure};
+use crate::dataset::{write_dataset, GraphDataset};
 use crate::graph::MolecularGraph;
+use crate::lammps::{read_lammps_data, read_lammps_dump, LammpsDumpReader, LammpsFrame};
+use crate::md::MolecularDynamics;
 use crate::model::GNNModel;
+use crate::neb::NEB;
+use crate::optimize::{Relaxation, Relaxer};
+use crate::pdb::{read_pdb, PdbStructure};
+use crate::pool::InferencePool;
+use crate::potential::Potential;
+use crate::scheduler::Scheduler;
+use crate::sdf::{read_sdf, SdfReader, SdfRecord};
+use crate::stream::InferenceStream;
+use crate::thermostat::{Barostat, Thermostat};
+use crate::train::{LRSchedule, Loss, Optimizer, Trainer};
+use crate::trajectory::Trajectory;
+use crate::vibrations::Vibrations;
+use crate::xyz::{read_xyz, write_xyz, XyzFrame, XyzReader};
 
 #[pymodule]
 fn _lowlevel(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<MolecularGraph>()?;
     m.add_class::<GNNModel>()?;
     m.add_class::<MolecularBatch>()?;
+    m.add_class::<GraphError>()?;
+    m.add_class::<Optimizer>()?;
+    m.add_class::<LRSchedule>()?;
+    m.add_class::<Loss>()?;
+    m.add_class::<Trainer>()?;
+    m.add_class::<Potential>()?;
+    m.add_class::<Vibrations>()?;
+    m.add_class::<MolecularDynamics>()?;
+    m.add_class::<Thermostat>()?;
+    m.add_class::<Barostat>()?;
+    m.add_class::<Relaxer>()?;
+    m.add_class::<Relaxation>()?;
+    m.add_class::<NEB>()?;
+    m.add_class::<Scheduler>()?;
+    m.add_class::<InferenceStream>()?;
+    m.add_class::<InferencePool>()?;
+    m.add_class::<XyzFrame>()?;
+    m.add_class::<XyzReader>()?;
+    m.add_class::<PdbStructure>()?;
+    m.add_class::<SdfRecord>()?;
+    m.add_class::<SdfReader>()?;
+    m.add_class::<CifStructure>()?;
+    m.add_class::<LammpsFrame>()?;
+    m.add_class::<LammpsDumpReader>()?;
+    m.add_class::<GraphDataset>()?;
+    m.add_class::<Trajectory>()?;
+    m.add_function(wrap_pyfunction!(read_xyz, m)?)?;
+    m.add_function(wrap_pyfunction!(write_xyz, m)?)?;
+    m.add_function(wrap_pyfunction!(read_pdb, m)?)?;
+    m.add_function(wrap_pyfunction!(read_sdf, m)?)?;
+    m.add_function(wrap_pyfunction!(read_cif, m)?)?;
+    m.add_function(wrap_pyfunction!(read_lammps_data, m)?)?;
+    m.add_function(wrap_pyfunction!(read_lammps_dump, m)?)?;
+    m.add_function(wrap_pyfunction!(write_dataset, m)?)?;
     Ok(())
 }
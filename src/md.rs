@@ -0,0 +1,358 @@
+use crate::graph::{MolecularGraph, EV_PER_A3_IN_BAR};
+use crate::potential::Potential;
+use crate::thermostat::{Barostat, NoseHooverChain, Thermostat};
+use nalgebra::{Matrix3, Vector3};
+use numpy::{ndarray, PyArray2, PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Natural time unit of (eV, Angstrom, amu), `sqrt(amu A^2 / eV)`, in femtoseconds.
+pub const FS_PER_TIME_UNIT: f64 = 10.180_505_7;
+/// Boltzmann constant in eV/K.
+pub const BOLTZMANN_EV: f64 = 8.617_333_262e-5;
+
+/// Velocity Verlet molecular dynamics on a `MolecularGraph` with model forces.
+///
+/// Positions are in Angstrom, energies in eV, masses in amu, time in fs and
+/// velocities in Angstrom/fs. Integration is carried out in double precision;
+/// the graph is refreshed from it before every force evaluation. Without a
+/// thermostat or barostat the dynamics is microcanonical (NVE).
+#[pyclass]
+pub struct MolecularDynamics {
+    pub(crate) graph: MolecularGraph,
+    pub(crate) atom_features: ndarray::Array2<f32>,
+    pub(crate) potential: Potential,
+    pub(crate) masses: Vec<f64>,
+    pub(crate) positions: Vec<Vector3<f64>>,
+    /// In Angstrom per natural time unit.
+    pub(crate) velocities: Vec<Vector3<f64>>,
+    pub(crate) forces: Vec<Vector3<f64>>,
+    pub(crate) potential_energy: f64,
+    /// `dE/d(eps)` at the current positions.
+    pub(crate) strain: Matrix3<f64>,
+    /// In natural time units.
+    pub(crate) timestep: f64,
+    pub(crate) step: usize,
+    pub(crate) thermostat: Option<Thermostat>,
+    pub(crate) chain: NoseHooverChain,
+    /// Kinetic energy removed by the thermostat so far, in eV.
+    pub(crate) thermostat_energy: f64,
+    pub(crate) barostat: Option<Barostat>,
+    pub(crate) rng: StdRng,
+}
+
+#[pymethods]
+impl MolecularDynamics {
+    /// Sets up a simulation with a `timestep` in fs.
+    ///
+    /// Initial velocities are taken from `velocities` (Angstrom/fs), drawn from a
+    /// Maxwell-Boltzmann distribution at `temperature` (K) with `seed`, or zero.
+    /// The centre-of-mass motion of drawn velocities is removed.
+    ///
+    /// An optional `thermostat` controls the temperature and a `barostat` the
+    /// pressure of periodic graphs; stochastic thermostats draw from the same
+    /// seeded generator, so runs are reproducible.
+    ///
+    /// # Errors
+    /// Returns an error if masses or velocities do not match the atoms, or a
+    /// barostat is given for a non-periodic graph.
+    #[new]
+    #[pyo3(signature = (graph, atom_features, potential, timestep, masses=None, velocities=None, temperature=None, seed=0, thermostat=None, barostat=None))]
+    #[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
+    pub fn new(
+        graph: &MolecularGraph,
+        atom_features: PyReadonlyArray2<f32>,
+        potential: &Potential,
+        timestep: f64,
+        masses: Option<PyReadonlyArray1<f64>>,
+        velocities: Option<PyReadonlyArray2<f64>>,
+        temperature: Option<f64>,
+        seed: u64,
+        thermostat: Option<Thermostat>,
+        barostat: Option<Barostat>,
+    ) -> PyResult<Self> {
+        let atom_view = atom_features.as_array();
+        potential.check_features(graph, &atom_view)?;
+        let n = graph.positions.len();
+        let masses = match masses {
+            Some(m) => m.as_array().to_vec(),
+            None => graph.masses()?,
+        };
+        if masses.len() != n {
+            return Err(PyValueError::new_err(format!(
+                "Got {} masses for {n} atoms",
+                masses.len()
+            )));
+        }
+        if barostat.is_some() && graph.periodic_cell().is_none() {
+            return Err(PyValueError::new_err("A barostat needs a periodic cell"));
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let velocities = match (velocities, temperature) {
+            (Some(v), _) => {
+                let view = v.as_array();
+                if view.shape() != [n, 3] {
+                    return Err(PyValueError::new_err(format!(
+                        "Expected velocities of shape ({n}, 3), got {:?}",
+                        view.shape()
+                    )));
+                }
+                (0..n)
+                    .map(|i| {
+                        Vector3::new(view[[i, 0]], view[[i, 1]], view[[i, 2]]) * FS_PER_TIME_UNIT
+                    })
+                    .collect()
+            }
+            (None, Some(t)) => maxwell_boltzmann(&masses, t, &mut rng),
+            (None, None) => vec![Vector3::zeros(); n],
+        };
+
+        let mut md = MolecularDynamics {
+            graph: graph.clone(),
+            atom_features: atom_view.to_owned(),
+            potential: potential.clone(),
+            masses,
+            positions: graph.positions.iter().map(|r| r.cast::<f64>()).collect(),
+            velocities,
+            forces: Vec::new(),
+            potential_energy: 0.0,
+            strain: Matrix3::zeros(),
+            timestep: timestep / FS_PER_TIME_UNIT,
+            step: 0,
+            chain: thermostat
+                .as_ref()
+                .map(Thermostat::initial_chain)
+                .unwrap_or_default(),
+            thermostat,
+            thermostat_energy: 0.0,
+            barostat,
+            rng,
+        };
+        md.compute_forces();
+        Ok(md)
+    }
+
+    /// Advances `steps` steps of velocity Verlet, with the thermostat and
+    /// barostat applied when configured.
+    ///
+    /// Every `interval` steps, `trajectory_callback(step, positions, velocities)`
+    /// and `energy_callback(step, potential_energy, kinetic_energy, temperature)`
+    /// are called when given.
+    ///
+    /// # Errors
+    /// Propagates exceptions raised by the callbacks.
+    #[pyo3(signature = (steps, interval=1, trajectory_callback=None, energy_callback=None))]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn run(
+        &mut self,
+        py: Python<'_>,
+        steps: usize,
+        interval: usize,
+        trajectory_callback: Option<Bound<'_, PyAny>>,
+        energy_callback: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        let interval = interval.max(1);
+        for _ in 0..steps {
+            self.advance();
+            if self.step.is_multiple_of(interval) {
+                if let Some(cb) = &trajectory_callback {
+                    cb.call1((self.step, self.positions(py), self.velocities(py)))?;
+                }
+                if let Some(cb) = &energy_callback {
+                    cb.call1((
+                        self.step,
+                        self.potential_energy,
+                        self.kinetic_energy(),
+                        self.temperature(),
+                    ))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Current positions in Angstrom, shape `(n_atoms, 3)`.
+    #[getter]
+    #[must_use]
+    pub fn positions(&self, py: Python<'_>) -> Py<PyArray2<f64>> {
+        PyArray2::from_array(py, &rows_to_array(&self.positions, 1.0)).into()
+    }
+
+    /// Current velocities in Angstrom/fs, shape `(n_atoms, 3)`.
+    #[getter]
+    #[must_use]
+    pub fn velocities(&self, py: Python<'_>) -> Py<PyArray2<f64>> {
+        PyArray2::from_array(py, &rows_to_array(&self.velocities, 1.0 / FS_PER_TIME_UNIT)).into()
+    }
+
+    /// Current forces in eV/Angstrom, shape `(n_atoms, 3)`.
+    #[getter]
+    #[must_use]
+    pub fn forces(&self, py: Python<'_>) -> Py<PyArray2<f64>> {
+        PyArray2::from_array(py, &rows_to_array(&self.forces, 1.0)).into()
+    }
+
+    /// A copy of the graph at the current positions.
+    #[getter]
+    #[must_use]
+    pub fn graph(&self) -> MolecularGraph {
+        self.graph.clone()
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// Elapsed simulation time in fs.
+    #[getter]
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn time(&self) -> f64 {
+        self.step as f64 * self.timestep * FS_PER_TIME_UNIT
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn potential_energy(&self) -> f64 {
+        self.potential_energy
+    }
+
+    /// Total energy plus the energy taken out by the thermostat, in eV.
+    ///
+    /// Conserved up to integration error for every thermostat; drifts only
+    /// under a barostat.
+    #[getter]
+    #[must_use]
+    pub fn conserved_energy(&self) -> f64 {
+        self.potential_energy + self.kinetic_energy() + self.thermostat_energy
+    }
+
+    /// Instantaneous pressure in bar including the kinetic term, or `None`
+    /// for non-periodic graphs.
+    #[getter]
+    #[must_use]
+    pub fn pressure(&self) -> Option<f64> {
+        self.pressure_internal().map(|p| p * EV_PER_A3_IN_BAR)
+    }
+
+    /// The cell in Angstrom, which changes under a barostat.
+    #[getter]
+    #[must_use]
+    pub fn cell(&self, py: Python<'_>) -> Option<Py<PyArray2<f32>>> {
+        self.graph.cell(py)
+    }
+
+    /// `sum_i m_i v_i^2 / 2` in eV.
+    #[getter]
+    #[must_use]
+    pub fn kinetic_energy(&self) -> f64 {
+        self.masses
+            .iter()
+            .zip(&self.velocities)
+            .map(|(m, v)| 0.5 * m * v.norm_squared())
+            .sum()
+    }
+
+    /// Instantaneous temperature in K, with the centre-of-mass degrees of freedom
+    /// removed unless a Langevin thermostat couples to them.
+    #[getter]
+    #[must_use]
+    pub fn temperature(&self) -> f64 {
+        2.0 * self.kinetic_energy() / (self.degrees_of_freedom() * BOLTZMANN_EV)
+    }
+}
+
+impl MolecularDynamics {
+    /// One velocity Verlet step: half kick, drift, force update, half kick.
+    ///
+    /// The thermostat acts on both ends of the step; the barostat rescales the
+    /// cell after the drift, using the pressure of the previous force evaluation.
+    pub(crate) fn advance(&mut self) {
+        let dt = self.timestep;
+        self.thermostat_pre_step();
+        self.kick(0.5 * dt);
+        for (r, v) in self.positions.iter_mut().zip(&self.velocities) {
+            *r += v * dt;
+        }
+        self.barostat_step();
+        self.compute_forces();
+        self.kick(0.5 * dt);
+        self.thermostat_post_step();
+        self.step += 1;
+    }
+
+    pub(crate) fn kick(&mut self, dt: f64) {
+        for ((v, f), m) in self
+            .velocities
+            .iter_mut()
+            .zip(&self.forces)
+            .zip(&self.masses)
+        {
+            *v += f * (dt / m);
+        }
+    }
+
+    /// Refreshes the graph from the integrator positions and evaluates the model.
+    pub(crate) fn compute_forces(&mut self) {
+        for (target, r) in self.graph.positions.iter_mut().zip(&self.positions) {
+            *target = r.cast::<f32>();
+        }
+        let evaluation = self
+            .potential
+            .evaluate(&self.graph, &self.atom_features.view());
+        self.potential_energy = f64::from(evaluation.energy);
+        self.forces = evaluation.forces.iter().map(|f| f.cast::<f64>()).collect();
+        self.strain = evaluation.strain;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn degrees_of_freedom(&self) -> f64 {
+        let n = self.positions.len();
+        let fixed_momentum = !self
+            .thermostat
+            .as_ref()
+            .is_some_and(Thermostat::couples_center_of_mass);
+        if n > 1 && fixed_momentum {
+            (3 * n - 3) as f64
+        } else {
+            (3 * n.max(1)) as f64
+        }
+    }
+}
+
+/// Velocities drawn at `temperature` (natural units), with zero total momentum.
+fn maxwell_boltzmann(masses: &[f64], temperature: f64, rng: &mut StdRng) -> Vec<Vector3<f64>> {
+    let mut velocities: Vec<Vector3<f64>> = masses
+        .iter()
+        .map(|m| {
+            let sigma = (BOLTZMANN_EV * temperature / m).sqrt();
+            Vector3::new(
+                standard_normal(rng),
+                standard_normal(rng),
+                standard_normal(rng),
+            ) * sigma
+        })
+        .collect();
+    let total_mass: f64 = masses.iter().sum();
+    let momentum: Vector3<f64> = masses.iter().zip(&velocities).map(|(m, v)| v * *m).sum();
+    for v in &mut velocities {
+        *v -= momentum / total_mass;
+    }
+    velocities
+}
+
+/// Box-Muller sample from N(0, 1).
+pub(crate) fn standard_normal(rng: &mut StdRng) -> f64 {
+    let u1: f64 = 1.0 - rng.random::<f64>();
+    let u2: f64 = rng.random::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+fn rows_to_array(rows: &[Vector3<f64>], scale: f64) -> ndarray::Array2<f64> {
+    ndarray::Array2::from_shape_fn((rows.len(), 3), |(i, k)| rows[i][k] * scale)
+}
@@ -1,8 +1,11 @@
+use crate::graph::MolecularGraph;
 use nalgebra::DMatrix;
-use numpy::PyReadonlyArray2;
+use numpy::{ndarray, PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
 #[pyclass]
+#[derive(Clone)]
 pub struct GNNModel {
     pub weights: DMatrix<f32>,
 }
@@ -19,4 +22,77 @@ impl GNNModel {
         let weights = DMatrix::from_iterator(rows, cols, view.iter().copied());
         GNNModel { weights }
     }
+
+    /// The weights in the same array layout they were constructed from.
+    #[getter]
+    #[must_use]
+    pub fn weights(&self, py: Python<'_>) -> Py<PyArray2<f32>> {
+        PyArray2::from_array(py, &to_numpy_layout(&self.weights)).into()
+    }
+
+    /// Replaces the weights in place, e.g. after an optimizer step.
+    ///
+    /// # Errors
+    /// Returns an error if the new weights do not have the current shape.
+    #[setter]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn set_weights(&mut self, weights_raw: PyReadonlyArray2<f32>) -> PyResult<()> {
+        let view = weights_raw.as_array();
+        let (rows, cols) = (view.shape()[0], view.shape()[1]);
+        if (rows, cols) != self.weights.shape() {
+            return Err(PyValueError::new_err(format!(
+                "Expected weights of shape {:?}, got ({rows}, {cols})",
+                self.weights.shape()
+            )));
+        }
+        self.weights = DMatrix::from_iterator(rows, cols, view.iter().copied());
+        Ok(())
+    }
+
+    /// Reverse-mode gradient of a loss with respect to the weights.
+    ///
+    /// `grad_output` is `dL/dY` for the output of `run_fused_with_model` on the
+    /// same inputs. The result has the layout of `weights`, so
+    /// `model.weights -= lr * grad` is a valid update.
+    ///
+    /// # Errors
+    /// Returns an error if the feature or gradient arrays are not shaped for this graph and model.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn backward(
+        &self,
+        graph: &MolecularGraph,
+        atom_features: PyReadonlyArray2<f32>,
+        grad_output: PyReadonlyArray2<f32>,
+        cutoff: f32,
+        num_offsets: usize,
+    ) -> PyResult<Py<PyArray2<f32>>> {
+        let py = atom_features.py();
+        let atom_view = atom_features.as_array();
+        let grad_view = grad_output.as_array();
+        let n = graph.positions.len();
+        let (rows, cols) = self.weights.shape();
+        if atom_view.shape() != [n, cols] {
+            return Err(PyValueError::new_err(format!(
+                "Expected atom features of shape ({n}, {cols}), got {:?}",
+                atom_view.shape()
+            )));
+        }
+        if grad_view.shape() != [n, rows] {
+            return Err(PyValueError::new_err(format!(
+                "Expected grad_output of shape ({n}, {rows}), got {:?}",
+                grad_view.shape()
+            )));
+        }
+
+        let grad =
+            graph.backward_weights_internal(self, &atom_view, &grad_view, cutoff, num_offsets);
+        Ok(PyArray2::from_array(py, &to_numpy_layout(&grad)).into())
+    }
+}
+
+/// Inverse of the constructor's conversion: the numpy array is filled, in
+/// row-major order, with the column-major storage of the matrix.
+pub(crate) fn to_numpy_layout(matrix: &DMatrix<f32>) -> ndarray::Array2<f32> {
+    ndarray::Array2::from_shape_vec(matrix.shape(), matrix.as_slice().to_vec())
+        .expect("DMatrix storage always matches its shape")
 }
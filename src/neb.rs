@@ -0,0 +1,447 @@
+use crate::batch::MolecularBatch;
+use crate::graph::MolecularGraph;
+use crate::optimize::{frozen_mask, minimize, Relaxer};
+use crate::potential::{Evaluation, Potential};
+use nalgebra::Vector3;
+use numpy::{ndarray, PyArray1, PyArray3, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Nudged elastic band between two fixed end points.
+///
+/// The interior images are held in a `MolecularBatch` and evaluated together
+/// in parallel. Forces use the improved tangent of Henkelman and Jónsson;
+/// with `climb=True` the highest interior image climbs to the saddle point
+/// (CI-NEB).
+#[pyclass]
+pub struct NEB {
+    initial: MolecularGraph,
+    last: MolecularGraph,
+    band: MolecularBatch,
+    atom_features: ndarray::Array2<f32>,
+    potential: Potential,
+    /// In eV/A^2.
+    spring: f64,
+    climb: bool,
+    /// One flag per atom, the same in every interior image.
+    frozen: Vec<bool>,
+    /// Energy of every image, end points included.
+    energies: Vec<f64>,
+    /// Model forces of every image, end points included.
+    forces: Vec<Vec<Vector3<f32>>>,
+    steps: usize,
+}
+
+#[pymethods]
+impl NEB {
+    /// Builds a band from `images`, end points included, which all share
+    /// `atom_features`. `spring` is in eV/A^2; atoms listed in `fixed` keep
+    /// their positions in every image.
+    ///
+    /// # Errors
+    /// Returns an error for fewer than three images, images with different
+    /// atoms, misshaped features, or fixed indices out of range.
+    #[new]
+    #[pyo3(signature = (images, atom_features, potential, spring=0.1, climb=false, fixed=None))]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn new(
+        images: Vec<MolecularGraph>,
+        atom_features: PyReadonlyArray2<f32>,
+        potential: &Potential,
+        spring: f64,
+        climb: bool,
+        fixed: Option<Vec<usize>>,
+    ) -> PyResult<Self> {
+        if images.len() < 3 {
+            return Err(PyValueError::new_err(format!(
+                "A band needs at least 3 images, got {}",
+                images.len()
+            )));
+        }
+        if images
+            .iter()
+            .any(|image| image.atomic_numbers != images[0].atomic_numbers)
+        {
+            return Err(PyValueError::new_err(
+                "All images must have the same atoms in the same order",
+            ));
+        }
+        let atom_view = atom_features.as_array();
+        potential.check_features(&images[0], &atom_view)?;
+        let frozen = frozen_mask(fixed, images[0].positions.len())?;
+
+        let initial = images[0].clone();
+        let last = images[images.len() - 1].clone();
+        let interior = images[1..images.len() - 1].to_vec();
+        let ends = [
+            potential.evaluate(&initial, &atom_view),
+            potential.evaluate(&last, &atom_view),
+        ];
+        let mut neb = NEB {
+            initial,
+            last,
+            band: MolecularBatch::from(interior),
+            atom_features: atom_view.to_owned(),
+            potential: potential.clone(),
+            spring,
+            climb,
+            frozen,
+            energies: Vec::new(),
+            forces: Vec::new(),
+            steps: 0,
+        };
+        let interior = neb.band.evaluate(&neb.potential, &neb.atom_features.view());
+        neb.store(&ends, &interior);
+        Ok(neb)
+    }
+
+    /// Images between `initial` and `final`, end points included, for a band
+    /// with `n_images` interior images.
+    ///
+    /// `method` is `"linear"` for straight Cartesian interpolation or `"idpp"`
+    /// to relax each linear image towards interpolated pair distances (image
+    /// dependent pair potential), which avoids atoms passing through each other.
+    ///
+    /// # Errors
+    /// Returns an error if the end points differ in atoms or `method` is unknown.
+    #[staticmethod]
+    #[pyo3(signature = (initial, r#final, n_images, method="linear"))]
+    pub fn interpolate(
+        initial: &MolecularGraph,
+        r#final: &MolecularGraph,
+        n_images: usize,
+        method: &str,
+    ) -> PyResult<Vec<MolecularGraph>> {
+        if initial.atomic_numbers != r#final.atomic_numbers {
+            return Err(PyValueError::new_err(
+                "End points must have the same atoms in the same order",
+            ));
+        }
+        let idpp = match method {
+            "linear" => false,
+            "idpp" => true,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown interpolation '{other}', expected 'linear' or 'idpp'"
+                )))
+            }
+        };
+
+        let mut images = vec![initial.clone()];
+        for k in 1..=n_images {
+            #[allow(clippy::cast_precision_loss)]
+            let fraction = k as f32 / (n_images + 1) as f32;
+            let mut image = initial.clone();
+            for (r, (a, b)) in image
+                .positions
+                .iter_mut()
+                .zip(initial.positions.iter().zip(&r#final.positions))
+            {
+                *r = a + (b - a) * fraction;
+            }
+            if idpp {
+                image.positions = idpp_relax(
+                    &image.positions,
+                    &initial.positions,
+                    &r#final.positions,
+                    f64::from(fraction),
+                );
+            }
+            images.push(image);
+        }
+        images.push(r#final.clone());
+        Ok(images)
+    }
+
+    /// Optimizes the interior images with `relaxer` (FIRE by default) until
+    /// the largest NEB force drops below its `fmax`. Returns whether the band
+    /// converged; may be called again to continue.
+    #[pyo3(signature = (relaxer=None))]
+    pub fn run(&mut self, relaxer: Option<Relaxer>) -> bool {
+        let relaxer = relaxer.unwrap_or_default();
+        let n = self.initial.positions.len();
+        let ends = [
+            self.potential
+                .evaluate(&self.initial, &self.atom_features.view()),
+            self.potential
+                .evaluate(&self.last, &self.atom_features.view()),
+        ];
+        let x: Vec<f64> = self
+            .band
+            .graphs
+            .iter()
+            .flat_map(|graph| graph.positions.iter())
+            .flat_map(|r| [f64::from(r.x), f64::from(r.y), f64::from(r.z)])
+            .collect();
+        let frozen = self.frozen.repeat(self.band.graphs.len());
+
+        let mut latest = Vec::new();
+        let objective = |x: &[f64]| {
+            for (graph, coordinates) in self.band.graphs.iter_mut().zip(x.chunks(3 * n)) {
+                for (r, c) in graph.positions.iter_mut().zip(coordinates.chunks_exact(3)) {
+                    *r = Vector3::new(c[0], c[1], c[2]).cast::<f32>();
+                }
+            }
+            let evaluations = self
+                .band
+                .evaluate(&self.potential, &self.atom_features.view());
+
+            let mut path: Vec<&[Vector3<f32>]> = vec![&self.initial.positions];
+            path.extend(self.band.graphs.iter().map(|g| g.positions.as_slice()));
+            path.push(&self.last.positions);
+            let mut energies = vec![f64::from(ends[0].energy)];
+            energies.extend(evaluations.iter().map(|e| f64::from(e.energy)));
+            energies.push(f64::from(ends[1].energy));
+
+            let forces = band_forces(&path, &energies, &evaluations, self.spring, self.climb);
+            latest = evaluations;
+            let gradient = forces
+                .iter()
+                .flat_map(|image| image.iter().flat_map(|f| [-f.x, -f.y, -f.z]))
+                .collect();
+            (energies[1..energies.len() - 1].iter().sum(), gradient)
+        };
+        let outcome = minimize(&relaxer, x, objective, &frozen);
+
+        self.steps += outcome.steps;
+        self.store(&ends, &latest);
+        outcome.converged
+    }
+
+    /// All images, end points included.
+    #[getter]
+    #[must_use]
+    pub fn images(&self) -> Vec<MolecularGraph> {
+        let mut images = vec![self.initial.clone()];
+        images.extend(self.band.graphs.iter().cloned());
+        images.push(self.last.clone());
+        images
+    }
+
+    /// Positions of every image, shape `(n_images, n_atoms, 3)`.
+    #[getter]
+    #[must_use]
+    pub fn positions(&self, py: Python<'_>) -> Py<PyArray3<f32>> {
+        let images = self.images();
+        let n = self.initial.positions.len();
+        let array = ndarray::Array3::from_shape_fn((images.len(), n, 3), |(m, i, k)| {
+            images[m].positions[i][k]
+        });
+        PyArray3::from_array(py, &array).into()
+    }
+
+    /// Energy of every image in eV.
+    #[getter]
+    #[must_use]
+    pub fn energies(&self, py: Python<'_>) -> Py<PyArray1<f64>> {
+        PyArray1::from_slice(py, &self.energies).into()
+    }
+
+    /// Model forces of every image, shape `(n_images, n_atoms, 3)`.
+    #[getter]
+    #[must_use]
+    pub fn forces(&self, py: Python<'_>) -> Py<PyArray3<f32>> {
+        let n = self.initial.positions.len();
+        let array = ndarray::Array3::from_shape_fn((self.forces.len(), n, 3), |(m, i, k)| {
+            self.forces[m][i][k]
+        });
+        PyArray3::from_array(py, &array).into()
+    }
+
+    /// Highest image energy relative to the initial image, in eV.
+    #[getter]
+    #[must_use]
+    pub fn barrier(&self) -> f64 {
+        let highest = self
+            .energies
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        highest - self.energies[0]
+    }
+
+    /// Optimizer steps taken over all calls to `run`.
+    #[getter]
+    #[must_use]
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+}
+
+impl NEB {
+    fn store(&mut self, ends: &[Evaluation; 2], interior: &[Evaluation]) {
+        let mut all = Vec::with_capacity(interior.len() + 2);
+        all.push(&ends[0]);
+        all.extend(interior.iter());
+        all.push(&ends[1]);
+        self.energies = all.iter().map(|e| f64::from(e.energy)).collect();
+        self.forces = all.iter().map(|e| e.forces.clone()).collect();
+    }
+}
+
+/// NEB forces on the interior images of `path`.
+///
+/// The true force is projected perpendicular to the tangent and a spring
+/// force along it keeps the images evenly spaced; the climbing image instead
+/// feels the true force with its tangential component inverted.
+fn band_forces(
+    path: &[&[Vector3<f32>]],
+    energies: &[f64],
+    evaluations: &[Evaluation],
+    spring: f64,
+    climb: bool,
+) -> Vec<Vec<Vector3<f64>>> {
+    let climber = climb.then(|| {
+        (1..path.len() - 1)
+            .max_by(|&a, &b| energies[a].total_cmp(&energies[b]))
+            .unwrap_or(1)
+    });
+
+    (1..path.len() - 1)
+        .map(|m| {
+            let ahead = displacement(path[m], path[m + 1]);
+            let behind = displacement(path[m - 1], path[m]);
+            let tangent = tangent(
+                &ahead,
+                &behind,
+                energies[m - 1],
+                energies[m],
+                energies[m + 1],
+            );
+            let true_forces: Vec<Vector3<f64>> = evaluations[m - 1]
+                .forces
+                .iter()
+                .map(|f| f.cast::<f64>())
+                .collect();
+            let along = dot(&true_forces, &tangent);
+
+            if climber == Some(m) {
+                return true_forces
+                    .iter()
+                    .zip(&tangent)
+                    .map(|(f, t)| f - t * (2.0 * along))
+                    .collect();
+            }
+            let stretch = spring * (norm(&ahead) - norm(&behind));
+            true_forces
+                .iter()
+                .zip(&tangent)
+                .map(|(f, t)| f - t * along + t * stretch)
+                .collect()
+        })
+        .collect()
+}
+
+/// Improved tangent: points towards the higher neighbour, blended by the
+/// energy differences at extrema along the path.
+fn tangent(
+    ahead: &[Vector3<f64>],
+    behind: &[Vector3<f64>],
+    previous: f64,
+    current: f64,
+    next: f64,
+) -> Vec<Vector3<f64>> {
+    let (weight_ahead, weight_behind) = if next > current && current > previous {
+        (1.0, 0.0)
+    } else if next < current && current < previous {
+        (0.0, 1.0)
+    } else {
+        let larger = (next - current).abs().max((previous - current).abs());
+        let smaller = (next - current).abs().min((previous - current).abs());
+        if next > previous {
+            (larger, smaller)
+        } else {
+            (smaller, larger)
+        }
+    };
+    let mut tangent: Vec<Vector3<f64>> = ahead
+        .iter()
+        .zip(behind)
+        .map(|(a, b)| a * weight_ahead + b * weight_behind)
+        .collect();
+    let length = norm(&tangent);
+    if length > 0.0 {
+        for t in &mut tangent {
+            *t /= length;
+        }
+    }
+    tangent
+}
+
+fn displacement(from: &[Vector3<f32>], to: &[Vector3<f32>]) -> Vec<Vector3<f64>> {
+    from.iter()
+        .zip(to)
+        .map(|(a, b)| (b - a).cast::<f64>())
+        .collect()
+}
+
+fn dot(a: &[Vector3<f64>], b: &[Vector3<f64>]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x.dot(y)).sum()
+}
+
+fn norm(a: &[Vector3<f64>]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Relaxes `start` on the IDPP surface `S = sum_{i<j} (d_ij^target - d_ij)^2 / d_ij^4`,
+/// whose target distances are interpolated between the end points.
+fn idpp_relax(
+    start: &[Vector3<f32>],
+    initial: &[Vector3<f32>],
+    last: &[Vector3<f32>],
+    fraction: f64,
+) -> Vec<Vector3<f32>> {
+    let n = start.len();
+    let distance = |positions: &[Vector3<f32>], i: usize, j: usize| {
+        f64::from((positions[i] - positions[j]).norm())
+    };
+    let targets: Vec<Vec<f64>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| {
+                    let a = distance(initial, i, j);
+                    a + fraction * (distance(last, i, j) - a)
+                })
+                .collect()
+        })
+        .collect();
+
+    let objective = |x: &[f64]| {
+        let positions: Vec<Vector3<f64>> = x
+            .chunks_exact(3)
+            .map(|c| Vector3::new(c[0], c[1], c[2]))
+            .collect();
+        let mut value = 0.0;
+        let mut gradient = vec![0.0; x.len()];
+        for i in 0..n {
+            for j in i + 1..n {
+                let r = positions[i] - positions[j];
+                let d = r.norm();
+                if d < 1e-8 {
+                    continue;
+                }
+                let residual = targets[i][j] - d;
+                value += residual * residual / d.powi(4);
+                let slope = -2.0 * residual / d.powi(4) - 4.0 * residual * residual / d.powi(5);
+                let g = r * (slope / d);
+                for k in 0..3 {
+                    gradient[3 * i + k] += g[k];
+                    gradient[3 * j + k] -= g[k];
+                }
+            }
+        }
+        (value, gradient)
+    };
+
+    let x = start
+        .iter()
+        .flat_map(|r| [f64::from(r.x), f64::from(r.y), f64::from(r.z)])
+        .collect();
+    let relaxer = Relaxer::fire(1e-3, 1000, 0.1, None, 0.1, 1.0);
+    let outcome = minimize(&relaxer, x, objective, &vec![false; n]);
+    outcome
+        .x
+        .chunks_exact(3)
+        .map(|c| Vector3::new(c[0], c[1], c[2]).cast::<f32>())
+        .collect()
+}
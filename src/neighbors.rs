@@ -0,0 +1,127 @@
+use crate::graph::MolecularGraph;
+use nalgebra::{Matrix3, Vector3};
+
+impl MolecularGraph {
+    /// Lattice translations that can bring a periodic image of any atom within `cutoff`.
+    ///
+    /// The zero shift is always first, so callers skip self-interaction with
+    /// `i == j && shift_index == 0`. Non-periodic graphs get only the zero shift.
+    #[must_use]
+    pub fn image_shifts(&self, cutoff: f32) -> Vec<Vector3<f32>> {
+        let Some(cell) = self.periodic_cell() else {
+            return vec![Vector3::zeros()];
+        };
+        // Rows of (cell^T)^-1 are the reciprocal vectors b_k with a_i . b_k = delta_ik
+        let reciprocal = reciprocal_vectors(&cell);
+
+        let mut repeats = [0i32; 3];
+        for (axis, repeat) in repeats.iter_mut().enumerate() {
+            if !self.pbc[axis] {
+                continue;
+            }
+            let b = reciprocal.row(axis).transpose();
+            // Atoms need not be wrapped into the cell, so widen by their fractional spread
+            let (lo, hi) = self
+                .positions
+                .iter()
+                .map(|r| r.dot(&b))
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), f| {
+                    (lo.min(f), hi.max(f))
+                });
+            let spread = if self.positions.is_empty() {
+                0.0
+            } else {
+                hi - lo
+            };
+            // 1 / |b_k| is the spacing between lattice planes along axis k
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                *repeat = (cutoff * b.norm() + spread).ceil() as i32;
+            }
+        }
+
+        let mut shifts = vec![Vector3::zeros()];
+        for na in -repeats[0]..=repeats[0] {
+            for nb in -repeats[1]..=repeats[1] {
+                for nc in -repeats[2]..=repeats[2] {
+                    if na == 0 && nb == 0 && nc == 0 {
+                        continue;
+                    }
+                    #[allow(clippy::cast_precision_loss)]
+                    let n = Vector3::new(na as f32, nb as f32, nc as f32);
+                    shifts.push(cell.transpose() * n);
+                }
+            }
+        }
+        shifts
+    }
+
+    /// The cell, if at least one direction is periodic.
+    #[must_use]
+    pub fn periodic_cell(&self) -> Option<Matrix3<f32>> {
+        self.cell.filter(|_| self.pbc.iter().any(|&p| p))
+    }
+
+    /// Cell volume, or `None` for non-periodic graphs.
+    #[must_use]
+    pub fn volume(&self) -> Option<f32> {
+        self.periodic_cell().map(|cell| cell.determinant().abs())
+    }
+}
+
+/// Neighbours within `cutoff + skin` of a reference configuration.
+///
+/// Until some atom has moved more than `skin / 2` from the reference, every
+/// pair within `cutoff` is still in the list, so consecutive frames of a
+/// trajectory can filter it instead of searching all pairs again.
+pub(crate) struct VerletList {
+    reference: Vec<Vector3<f32>>,
+    skin: f32,
+    /// Neighbours of each atom as `(j, shift)`, in the order of a full search.
+    pub(crate) pairs: Vec<Vec<(usize, Vector3<f32>)>>,
+}
+
+impl VerletList {
+    pub(crate) fn build(graph: &MolecularGraph, cutoff: f32, skin: f32) -> Self {
+        let radius = cutoff + skin;
+        let shifts = graph.image_shifts(radius);
+        // Compared in double precision like the aggregation, so no pair is lost at zero skin
+        let radius_f64 = f64::from(radius);
+        let positions = &graph.positions;
+        let pairs = (0..positions.len())
+            .map(|i| {
+                let mut neighbours = Vec::new();
+                for (j, r_j) in positions.iter().enumerate() {
+                    for (s, shift) in shifts.iter().enumerate() {
+                        let dist = f64::from((positions[i] - r_j - shift).norm());
+                        if (i != j || s != 0) && dist <= radius_f64 {
+                            neighbours.push((j, *shift));
+                        }
+                    }
+                }
+                neighbours
+            })
+            .collect();
+        VerletList {
+            reference: positions.clone(),
+            skin,
+            pairs,
+        }
+    }
+
+    /// Whether the list still holds every pair within the cutoff at `positions`.
+    pub(crate) fn is_valid(&self, positions: &[Vector3<f32>]) -> bool {
+        let limit = 0.5 * self.skin;
+        positions
+            .iter()
+            .zip(&self.reference)
+            .all(|(r, r0)| (r - r0).norm() <= limit)
+    }
+}
+
+/// Reciprocal vectors (without the 2 pi factor) as the rows of a matrix.
+pub(crate) fn reciprocal_vectors(cell: &Matrix3<f32>) -> Matrix3<f32> {
+    cell.transpose()
+        .try_inverse()
+        .unwrap_or_else(Matrix3::zeros)
+}
@@ -0,0 +1,520 @@
+use crate::graph::MolecularGraph;
+use crate::potential::{vectors_to_array, Potential};
+use nalgebra::{Matrix3, Vector3};
+use numpy::{ndarray, PyArray1, PyArray2, PyArray3, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy)]
+enum RelaxerKind {
+    Fire { dt: f64, dt_max: f64 },
+    Lbfgs { memory: usize, alpha: f64 },
+}
+
+/// Geometry optimizer that minimizes the energy of a `Potential`.
+///
+/// Coordinates are moved in blocks of three (one atom, or one lattice vector
+/// of the deformation gradient when the cell is relaxed); `max_step` bounds
+/// the largest block displacement per step, in Angstrom.
+#[pyclass]
+#[derive(Clone)]
+pub struct Relaxer {
+    kind: RelaxerKind,
+    fmax: f64,
+    max_steps: usize,
+    max_step: f64,
+    energy_tol: Option<f64>,
+}
+
+#[pymethods]
+impl Relaxer {
+    /// Fast inertial relaxation engine (Bitzek et al.), with unit masses.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (fmax=0.05, max_steps=1000, max_step=0.2, energy_tol=None, dt=0.1, dt_max=1.0))]
+    pub fn fire(
+        fmax: f64,
+        max_steps: usize,
+        max_step: f64,
+        energy_tol: Option<f64>,
+        dt: f64,
+        dt_max: f64,
+    ) -> Self {
+        Relaxer {
+            kind: RelaxerKind::Fire { dt, dt_max },
+            fmax,
+            max_steps,
+            max_step,
+            energy_tol,
+        }
+    }
+
+    /// Limited-memory BFGS keeping `memory` correction pairs, starting from the
+    /// inverse Hessian `1 / alpha` (alpha in eV/A^2). No line search is done;
+    /// steps are only bounded by `max_step`.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (fmax=0.05, max_steps=1000, max_step=0.2, energy_tol=None, memory=20, alpha=70.0))]
+    pub fn lbfgs(
+        fmax: f64,
+        max_steps: usize,
+        max_step: f64,
+        energy_tol: Option<f64>,
+        memory: usize,
+        alpha: f64,
+    ) -> Self {
+        Relaxer {
+            kind: RelaxerKind::Lbfgs {
+                memory: memory.max(1),
+                alpha,
+            },
+            fmax,
+            max_steps,
+            max_step,
+            energy_tol,
+        }
+    }
+
+    /// Relaxes `graph` until the largest force drops below `fmax` (eV/A), the
+    /// energy changes by less than `energy_tol` (eV) in one step, or
+    /// `max_steps` is reached.
+    ///
+    /// Atoms listed in `fixed` do not move. With `relax_cell=True` the cell of
+    /// a periodic graph is relaxed towards zero stress as well, through a
+    /// deformation gradient scaled by the number of atoms.
+    ///
+    /// # Errors
+    /// Returns an error if the inputs are misshaped, a fixed index is out of
+    /// range, or the cell is relaxed for a non-periodic graph.
+    #[pyo3(signature = (graph, atom_features, potential, fixed=None, relax_cell=false))]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn run(
+        &self,
+        graph: &MolecularGraph,
+        atom_features: PyReadonlyArray2<f32>,
+        potential: &Potential,
+        fixed: Option<Vec<usize>>,
+        relax_cell: bool,
+    ) -> PyResult<Relaxation> {
+        let atom_view = atom_features.as_array();
+        potential.check_features(graph, &atom_view)?;
+        let frozen = frozen_mask(fixed, graph.positions.len())?;
+
+        if relax_cell && graph.periodic_cell().is_none() {
+            return Err(PyValueError::new_err(
+                "Relaxing the cell needs a periodic graph",
+            ));
+        }
+        Ok(self.relax(graph, &atom_view, potential, &frozen, relax_cell))
+    }
+}
+
+impl Default for Relaxer {
+    /// FIRE with its default settings.
+    fn default() -> Self {
+        Relaxer::fire(0.05, 1000, 0.2, None, 0.1, 1.0)
+    }
+}
+
+impl Relaxer {
+    /// Relaxation of validated inputs; `frozen` has one flag per atom.
+    pub(crate) fn relax(
+        &self,
+        graph: &MolecularGraph,
+        atom_view: &ndarray::ArrayView2<f32>,
+        potential: &Potential,
+        frozen: &[bool],
+        relax_cell: bool,
+    ) -> Relaxation {
+        let n = graph.positions.len();
+        let mut frozen = frozen.to_vec();
+        let base_cell = graph
+            .periodic_cell()
+            .filter(|_| relax_cell)
+            .map(nalgebra::Matrix::cast::<f64>);
+        if base_cell.is_some() {
+            frozen.extend([false; 3]);
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let cell_factor = n.max(1) as f64;
+
+        let mut x: Vec<f64> = graph
+            .positions
+            .iter()
+            .flat_map(|r| [f64::from(r.x), f64::from(r.y), f64::from(r.z)])
+            .collect();
+        if base_cell.is_some() {
+            x.extend(Matrix3::<f64>::identity().iter().map(|v| v * cell_factor));
+        }
+
+        let mut current = graph.clone();
+        let mut forces = Vec::new();
+        let mut trajectory = Vec::new();
+        let mut cells = Vec::new();
+        let objective = |x: &[f64]| {
+            let (positions, cell, deformation) = unpack(x, n, base_cell, cell_factor);
+            current.positions = positions;
+            current.cell = cell.or(current.cell);
+            let evaluation = potential.evaluate(&current, atom_view);
+            let mut gradient: Vec<f64> = Vec::with_capacity(x.len());
+            for f in &evaluation.forces {
+                // dE/d(r~) = F^T dE/dr for positions in the undeformed frame
+                let g = deformation.transpose() * -f.cast::<f64>();
+                gradient.extend(g.iter());
+            }
+            if base_cell.is_some() {
+                let inverse = deformation.try_inverse().unwrap_or_else(Matrix3::identity);
+                let cell_gradient = evaluation.strain * inverse.transpose() / cell_factor;
+                gradient.extend(cell_gradient.transpose().iter());
+            }
+            trajectory.push(current.positions.clone());
+            cells.extend(cell);
+            forces.clone_from(&evaluation.forces);
+            (f64::from(evaluation.energy), gradient)
+        };
+
+        let outcome = minimize(self, x, objective, &frozen);
+        let (positions, cell, _) = unpack(&outcome.x, n, base_cell, cell_factor);
+        let mut relaxed = graph.clone();
+        relaxed.positions = positions;
+        relaxed.cell = cell.or(relaxed.cell);
+
+        Relaxation {
+            graph: relaxed,
+            energy: outcome.energy,
+            forces,
+            converged: outcome.converged,
+            steps: outcome.steps,
+            energies: outcome.energies,
+            trajectory,
+            cells,
+        }
+    }
+}
+
+/// Result of `Relaxer.run`.
+#[pyclass]
+pub struct Relaxation {
+    graph: MolecularGraph,
+    energy: f64,
+    forces: Vec<Vector3<f32>>,
+    converged: bool,
+    steps: usize,
+    energies: Vec<f64>,
+    trajectory: Vec<Vec<Vector3<f32>>>,
+    cells: Vec<Matrix3<f32>>,
+}
+
+#[pymethods]
+impl Relaxation {
+    /// The relaxed geometry.
+    #[getter]
+    #[must_use]
+    pub fn graph(&self) -> MolecularGraph {
+        self.graph.clone()
+    }
+
+    /// Relaxed positions in Angstrom, shape `(n_atoms, 3)`.
+    #[getter]
+    #[must_use]
+    pub fn positions(&self, py: Python<'_>) -> Py<PyArray2<f32>> {
+        PyArray2::from_array(py, &vectors_to_array(&self.graph.positions)).into()
+    }
+
+    /// Final energy in eV.
+    #[getter]
+    #[must_use]
+    pub fn energy(&self) -> f64 {
+        self.energy
+    }
+
+    /// Final forces in eV/A, shape `(n_atoms, 3)`; fixed atoms included.
+    #[getter]
+    #[must_use]
+    pub fn forces(&self, py: Python<'_>) -> Py<PyArray2<f32>> {
+        PyArray2::from_array(py, &vectors_to_array(&self.forces)).into()
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// Number of optimizer steps taken.
+    #[getter]
+    #[must_use]
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Energy of every evaluated geometry, starting with the input.
+    #[getter]
+    #[must_use]
+    pub fn energies(&self, py: Python<'_>) -> Py<PyArray1<f64>> {
+        PyArray1::from_slice(py, &self.energies).into()
+    }
+
+    /// Positions of every evaluated geometry, shape `(n_frames, n_atoms, 3)`.
+    #[getter]
+    #[must_use]
+    pub fn trajectory(&self, py: Python<'_>) -> Py<PyArray3<f32>> {
+        let n = self.graph.positions.len();
+        let array = ndarray::Array3::from_shape_fn((self.trajectory.len(), n, 3), |(f, i, k)| {
+            self.trajectory[f][i][k]
+        });
+        PyArray3::from_array(py, &array).into()
+    }
+
+    /// Cell of every evaluated geometry, shape `(n_frames, 3, 3)`, or `None`
+    /// when the cell was not relaxed.
+    #[getter]
+    #[must_use]
+    pub fn cells(&self, py: Python<'_>) -> Option<Py<PyArray3<f32>>> {
+        if self.cells.is_empty() {
+            return None;
+        }
+        let array = ndarray::Array3::from_shape_fn((self.cells.len(), 3, 3), |(f, r, c)| {
+            self.cells[f][(r, c)]
+        });
+        Some(PyArray3::from_array(py, &array).into())
+    }
+}
+
+/// One flag per atom, set for the indices in `fixed`.
+///
+/// # Errors
+/// Returns an error for indices out of range.
+pub(crate) fn frozen_mask(fixed: Option<Vec<usize>>, n: usize) -> PyResult<Vec<bool>> {
+    let mut frozen = vec![false; n];
+    for index in fixed.unwrap_or_default() {
+        *frozen.get_mut(index).ok_or_else(|| {
+            PyValueError::new_err(format!("Fixed atom {index} out of range for {n} atoms"))
+        })? = true;
+    }
+    Ok(frozen)
+}
+
+/// Splits flat coordinates into positions, the deformed cell and the deformation gradient.
+fn unpack(
+    x: &[f64],
+    n: usize,
+    base_cell: Option<Matrix3<f64>>,
+    cell_factor: f64,
+) -> (Vec<Vector3<f32>>, Option<Matrix3<f32>>, Matrix3<f64>) {
+    let deformation = match base_cell {
+        Some(_) => Matrix3::from_row_slice(&x[3 * n..3 * n + 9]) / cell_factor,
+        None => Matrix3::identity(),
+    };
+    let positions = x[..3 * n]
+        .chunks_exact(3)
+        .map(|r| (deformation * Vector3::new(r[0], r[1], r[2])).cast::<f32>())
+        .collect();
+    // Lattice vectors are rows, so each deforms as a^T -> (F a)^T
+    let cell = base_cell.map(|cell| (cell * deformation.transpose()).cast::<f32>());
+    (positions, cell, deformation)
+}
+
+/// Outcome of `minimize` over flat coordinates.
+pub(crate) struct Minimum {
+    pub(crate) x: Vec<f64>,
+    pub(crate) energy: f64,
+    pub(crate) energies: Vec<f64>,
+    pub(crate) steps: usize,
+    pub(crate) converged: bool,
+}
+
+/// Minimizes `objective`, which returns the energy and gradient at `x`.
+///
+/// `frozen` flags blocks of three coordinates whose gradient is zeroed, so
+/// they never move. The objective may return a gradient that is not the
+/// derivative of the energy (as for nudged elastic bands); only the
+/// `energy_tol` criterion looks at the energy.
+pub(crate) fn minimize<F>(
+    relaxer: &Relaxer,
+    mut x: Vec<f64>,
+    mut objective: F,
+    frozen: &[bool],
+) -> Minimum
+where
+    F: FnMut(&[f64]) -> (f64, Vec<f64>),
+{
+    let evaluate = |objective: &mut F, x: &[f64]| {
+        let (energy, mut gradient) = objective(x);
+        for (block, &fixed) in gradient.chunks_mut(3).zip(frozen) {
+            if fixed {
+                block.fill(0.0);
+            }
+        }
+        (energy, gradient)
+    };
+
+    let mut state = MinimizerState::new(relaxer.kind, x.len());
+    let (mut energy, mut gradient) = evaluate(&mut objective, &x);
+    let mut energies = vec![energy];
+    let mut steps = 0;
+    let mut converged = max_block_norm(&gradient) < relaxer.fmax;
+    while !converged && steps < relaxer.max_steps {
+        let mut step = state.direction(relaxer.kind, &gradient);
+        let largest = max_block_norm(&step);
+        if largest > relaxer.max_step {
+            for s in &mut step {
+                *s *= relaxer.max_step / largest;
+            }
+        }
+        for (xi, s) in x.iter_mut().zip(&step) {
+            *xi += s;
+        }
+
+        let (new_energy, new_gradient) = evaluate(&mut objective, &x);
+        state.update(relaxer.kind, &step, &gradient, &new_gradient);
+        let change = (new_energy - energy).abs();
+        energy = new_energy;
+        gradient = new_gradient;
+        energies.push(energy);
+        steps += 1;
+        converged = max_block_norm(&gradient) < relaxer.fmax
+            || relaxer.energy_tol.is_some_and(|tol| change < tol);
+    }
+
+    Minimum {
+        x,
+        energy,
+        energies,
+        steps,
+        converged,
+    }
+}
+
+/// Largest Euclidean norm over consecutive blocks of three.
+pub(crate) fn max_block_norm(values: &[f64]) -> f64 {
+    values
+        .chunks(3)
+        .map(|block| block.iter().map(|v| v * v).sum::<f64>().sqrt())
+        .fold(0.0, f64::max)
+}
+
+enum MinimizerState {
+    Fire {
+        velocity: Vec<f64>,
+        dt: f64,
+        alpha: f64,
+        downhill_steps: usize,
+    },
+    Lbfgs {
+        /// `(s, y, 1 / y.s)` for the most recent steps, oldest first.
+        history: VecDeque<(Vec<f64>, Vec<f64>, f64)>,
+    },
+}
+
+const FIRE_ALPHA_START: f64 = 0.1;
+const FIRE_N_MIN: usize = 5;
+const FIRE_F_INC: f64 = 1.1;
+const FIRE_F_DEC: f64 = 0.5;
+const FIRE_F_ALPHA: f64 = 0.99;
+
+impl MinimizerState {
+    fn new(kind: RelaxerKind, dim: usize) -> Self {
+        match kind {
+            RelaxerKind::Fire { dt, .. } => MinimizerState::Fire {
+                velocity: vec![0.0; dim],
+                dt,
+                alpha: FIRE_ALPHA_START,
+                downhill_steps: 0,
+            },
+            RelaxerKind::Lbfgs { memory, .. } => MinimizerState::Lbfgs {
+                history: VecDeque::with_capacity(memory),
+            },
+        }
+    }
+
+    /// Proposed step from the current gradient, before max-step limiting.
+    fn direction(&mut self, kind: RelaxerKind, gradient: &[f64]) -> Vec<f64> {
+        match (self, kind) {
+            (
+                MinimizerState::Fire {
+                    velocity,
+                    dt,
+                    alpha,
+                    downhill_steps,
+                },
+                RelaxerKind::Fire { dt_max, .. },
+            ) => {
+                let power: f64 = velocity.iter().zip(gradient).map(|(v, g)| -v * g).sum();
+                if power > 0.0 {
+                    // Steer the velocity towards the force
+                    let v_norm = velocity.iter().map(|v| v * v).sum::<f64>().sqrt();
+                    let f_norm = gradient.iter().map(|g| g * g).sum::<f64>().sqrt();
+                    if f_norm > 0.0 {
+                        for (v, g) in velocity.iter_mut().zip(gradient) {
+                            *v = (1.0 - *alpha) * *v - *alpha * g / f_norm * v_norm;
+                        }
+                    }
+                    *downhill_steps += 1;
+                    if *downhill_steps > FIRE_N_MIN {
+                        *dt = (*dt * FIRE_F_INC).min(dt_max);
+                        *alpha *= FIRE_F_ALPHA;
+                    }
+                } else {
+                    velocity.fill(0.0);
+                    *dt *= FIRE_F_DEC;
+                    *alpha = FIRE_ALPHA_START;
+                    *downhill_steps = 0;
+                }
+                for (v, g) in velocity.iter_mut().zip(gradient) {
+                    *v -= *dt * g;
+                }
+                velocity.iter().map(|v| *dt * v).collect()
+            }
+            (MinimizerState::Lbfgs { history }, RelaxerKind::Lbfgs { alpha, .. }) => {
+                // Two-loop recursion for -H g
+                let mut q = gradient.to_vec();
+                let mut coefficients = Vec::with_capacity(history.len());
+                for (s, y, rho) in history.iter().rev() {
+                    let a = rho * dot(s, &q);
+                    for (qi, yi) in q.iter_mut().zip(y) {
+                        *qi -= a * yi;
+                    }
+                    coefficients.push(a);
+                }
+                for qi in &mut q {
+                    *qi /= alpha;
+                }
+                for ((s, y, rho), a) in history.iter().zip(coefficients.iter().rev()) {
+                    let b = rho * dot(y, &q);
+                    for (qi, si) in q.iter_mut().zip(s) {
+                        *qi += (a - b) * si;
+                    }
+                }
+                if dot(&q, gradient) <= 0.0 {
+                    // Not a descent direction; restart from steepest descent
+                    history.clear();
+                    return gradient.iter().map(|g| -g / alpha).collect();
+                }
+                q.iter().map(|v| -v).collect()
+            }
+            _ => unreachable!("minimizer state does not match its relaxer"),
+        }
+    }
+
+    /// Records the accepted `step` and the gradient change it produced.
+    fn update(&mut self, kind: RelaxerKind, step: &[f64], old: &[f64], new: &[f64]) {
+        if let (MinimizerState::Lbfgs { history }, RelaxerKind::Lbfgs { memory, .. }) = (self, kind)
+        {
+            let y: Vec<f64> = new.iter().zip(old).map(|(n, o)| n - o).collect();
+            let curvature = dot(&y, step);
+            // Skip pairs that would make the inverse Hessian indefinite
+            if curvature > 1e-12 {
+                if history.len() == memory {
+                    history.pop_front();
+                }
+                history.push_back((step.to_vec(), y, 1.0 / curvature));
+            }
+        }
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
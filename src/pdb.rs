@@ -0,0 +1,304 @@
+use crate::elements::atomic_number;
+use crate::graph::{cell_from_parameters, MolecularGraph};
+use nalgebra::Vector3;
+use numpy::PyArray1;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// Atoms of a PDB file with their residue and chain annotations, one entry
+/// per atom in file order.
+#[pyclass]
+#[derive(Clone)]
+pub struct PdbStructure {
+    pub graph: MolecularGraph,
+    #[pyo3(get)]
+    pub serials: Vec<i32>,
+    #[pyo3(get)]
+    pub atom_names: Vec<String>,
+    /// Alternate location labels, empty for atoms without alternates.
+    #[pyo3(get)]
+    pub altlocs: Vec<String>,
+    #[pyo3(get)]
+    pub residue_names: Vec<String>,
+    #[pyo3(get)]
+    pub residue_numbers: Vec<i32>,
+    #[pyo3(get)]
+    pub insertion_codes: Vec<String>,
+    #[pyo3(get)]
+    pub chain_ids: Vec<String>,
+    /// `True` for `HETATM` records.
+    #[pyo3(get)]
+    pub hetero: Vec<bool>,
+    #[pyo3(get)]
+    pub formal_charges: Vec<i32>,
+    pub occupancies: Vec<f32>,
+    pub b_factors: Vec<f32>,
+}
+
+#[pymethods]
+impl PdbStructure {
+    #[getter]
+    #[must_use]
+    pub fn graph(&self) -> MolecularGraph {
+        self.graph.clone()
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn occupancies<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f32>> {
+        PyArray1::from_slice(py, &self.occupancies)
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn b_factors<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f32>> {
+        PyArray1::from_slice(py, &self.b_factors)
+    }
+
+    /// Residues in file order as `(chain_id, residue_number, insertion_code,
+    /// residue_name, atom_indices)` tuples.
+    #[must_use]
+    pub fn residues(&self) -> Vec<(String, i32, String, String, Vec<usize>)> {
+        let mut residues: Vec<(String, i32, String, String, Vec<usize>)> = Vec::new();
+        for i in 0..self.serials.len() {
+            let key = (
+                &self.chain_ids[i],
+                self.residue_numbers[i],
+                &self.insertion_codes[i],
+                &self.residue_names[i],
+            );
+            match residues.last_mut() {
+                Some(last) if (&last.0, last.1, &last.2, &last.3) == key => last.4.push(i),
+                _ => residues.push((key.0.clone(), key.1, key.2.clone(), key.3.clone(), vec![i])),
+            }
+        }
+        residues
+    }
+
+    /// Indices of the atoms in `chain`, in file order.
+    #[must_use]
+    pub fn chain(&self, chain: &str) -> Vec<usize> {
+        (0..self.chain_ids.len())
+            .filter(|&i| self.chain_ids[i] == chain)
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.serials.len()
+    }
+}
+
+/// Reads the `ATOM` and `HETATM` records of the first model of a PDB file.
+///
+/// Elements come from the element column, or from the atom name when it is
+/// blank. Of the alternate locations of an atom, only `altloc` is kept, or the
+/// first one listed by default. A `CRYST1` record makes the graph periodic,
+/// except for the `1 1 1 90 90 90` placeholder.
+///
+/// # Errors
+/// Returns an error if the file cannot be read, a record is malformed, or an
+/// element cannot be determined.
+#[pyfunction]
+#[pyo3(signature = (path, altloc=None))]
+pub fn read_pdb(path: &str, altloc: Option<char>) -> PyResult<PdbStructure> {
+    parse_pdb(BufReader::new(File::open(path)?), altloc)
+}
+
+/// Parses PDB records from `reader`; see [`read_pdb`].
+///
+/// # Errors
+/// Returns an error if the input cannot be read or is malformed.
+pub fn parse_pdb<R: BufRead>(reader: R, altloc: Option<char>) -> PyResult<PdbStructure> {
+    let mut structure = PdbStructure {
+        graph: MolecularGraph::from_parts(Vec::new(), Vec::new(), None, [false; 3])?,
+        serials: Vec::new(),
+        atom_names: Vec::new(),
+        altlocs: Vec::new(),
+        residue_names: Vec::new(),
+        residue_numbers: Vec::new(),
+        insertion_codes: Vec::new(),
+        chain_ids: Vec::new(),
+        hetero: Vec::new(),
+        formal_charges: Vec::new(),
+        occupancies: Vec::new(),
+        b_factors: Vec::new(),
+    };
+    let mut atomic_numbers = Vec::new();
+    let mut positions = Vec::new();
+    let mut cell = None;
+    // Atoms with alternates that already have a location kept
+    let mut placed = HashSet::new();
+
+    for (index, text) in reader.lines().enumerate() {
+        let text = text?;
+        let line = index + 1;
+        let record = column(&text, 0, 6);
+        match record {
+            "ENDMDL" | "END" => break,
+            "CRYST1" => {
+                let number = |start, end| -> PyResult<f64> {
+                    let field = column(&text, start, end);
+                    field
+                        .parse()
+                        .map_err(|_| parse_error(line, &format!("invalid CRYST1 value {field:?}")))
+                };
+                let lengths = [number(6, 15)?, number(15, 24)?, number(24, 33)?];
+                let angles = [number(33, 40)?, number(40, 47)?, number(47, 54)?];
+                let placeholder = lengths.iter().all(|&l| (l - 1.0).abs() < 1e-6)
+                    && angles.iter().all(|&a| (a - 90.0).abs() < 1e-6);
+                if !placeholder {
+                    cell = Some(
+                        cell_from_parameters(lengths, angles)
+                            .map_err(|err| parse_error(line, &err.to_string()))?,
+                    );
+                }
+            }
+            "ATOM" | "HETATM" => {
+                let alt = text.get(16..17).unwrap_or(" ").trim();
+                let chain_id = column(&text, 21, 22);
+                let residue_number = column(&text, 22, 26);
+                let insertion_code = column(&text, 26, 27);
+                let name = text.get(12..16).unwrap_or("");
+                if !alt.is_empty() {
+                    let wanted = altloc.is_none_or(|a| alt.starts_with(a));
+                    let key = (
+                        chain_id.to_string(),
+                        residue_number.to_string(),
+                        insertion_code.to_string(),
+                        name.trim().to_string(),
+                    );
+                    if !wanted || placed.contains(&key) {
+                        continue;
+                    }
+                    placed.insert(key);
+                }
+
+                push_atom(
+                    &mut structure,
+                    &mut atomic_numbers,
+                    &mut positions,
+                    &text,
+                    line,
+                )?;
+            }
+            _ => {}
+        }
+    }
+
+    let pbc = [cell.is_some(); 3];
+    structure.graph = MolecularGraph::from_parts(atomic_numbers, positions, cell, pbc)?;
+    Ok(structure)
+}
+
+/// Appends the atom of an `ATOM` or `HETATM` record.
+fn push_atom(
+    structure: &mut PdbStructure,
+    atomic_numbers: &mut Vec<i32>,
+    positions: &mut Vec<Vector3<f32>>,
+    text: &str,
+    line: usize,
+) -> PyResult<()> {
+    let name = text.get(12..16).unwrap_or("");
+    let residue_number = column(text, 22, 26);
+    let number = |start, end, what: &str| -> PyResult<f32> {
+        let field = column(text, start, end);
+        field
+            .parse()
+            .map_err(|_| parse_error(line, &format!("invalid {what} {field:?}")))
+    };
+    positions.push(Vector3::new(
+        number(30, 38, "x coordinate")?,
+        number(38, 46, "y coordinate")?,
+        number(46, 54, "z coordinate")?,
+    ));
+    let hetero = column(text, 0, 6) == "HETATM";
+    let element = column(text, 76, 78);
+    let z = if element.is_empty() {
+        element_from_name(name)
+    } else {
+        atomic_number(element)
+    };
+    atomic_numbers.push(
+        z.ok_or_else(|| parse_error(line, &format!("cannot determine the element of {name:?}")))?,
+    );
+
+    structure
+        .serials
+        .push(column(text, 6, 11).parse().unwrap_or(0));
+    structure.atom_names.push(name.trim().to_string());
+    structure.altlocs.push(column(text, 16, 17).to_string());
+    structure
+        .residue_names
+        .push(column(text, 17, 20).to_string());
+    structure.residue_numbers.push(
+        residue_number.parse().map_err(|_| {
+            parse_error(line, &format!("invalid residue number {residue_number:?}"))
+        })?,
+    );
+    structure
+        .insertion_codes
+        .push(column(text, 26, 27).to_string());
+    structure.chain_ids.push(column(text, 21, 22).to_string());
+    structure.hetero.push(hetero);
+    let charge = column(text, 78, 80);
+    structure.formal_charges.push(
+        formal_charge(charge)
+            .ok_or_else(|| parse_error(line, &format!("invalid formal charge {charge:?}")))?,
+    );
+    structure
+        .occupancies
+        .push(column(text, 54, 60).parse().unwrap_or(1.0));
+    structure
+        .b_factors
+        .push(column(text, 60, 66).parse().unwrap_or(0.0));
+    Ok(())
+}
+
+/// The trimmed text of columns `start..end`, empty past the end of the line.
+fn column(line: &str, start: usize, end: usize) -> &str {
+    line.get(start..end.min(line.len())).unwrap_or("").trim()
+}
+
+/// Element from a 4-character atom name field. Element symbols are right
+/// aligned in its first two columns, so a name starting in the first column
+/// has a two-letter element, unless it is a hydrogen such as `HD21`.
+fn element_from_name(name: &str) -> Option<i32> {
+    let letters: Vec<char> = name.chars().collect();
+    if let [first, second, ..] = letters[..] {
+        if first.is_ascii_alphabetic() && second.is_ascii_alphabetic() && first != 'H' {
+            if let Some(z) = atomic_number(&format!("{first}{second}")) {
+                return Some(z);
+            }
+        }
+    }
+    let symbol = letters.iter().find(|c| c.is_ascii_alphabetic())?;
+    atomic_number(&symbol.to_string())
+}
+
+/// Formal charge from the `2+`/`1-` charge column; 0 when blank and `None`
+/// when malformed.
+fn formal_charge(field: &str) -> Option<i32> {
+    let Some((split, sign)) = field.char_indices().last() else {
+        return Some(0);
+    };
+    let magnitude = &field[..split];
+    let magnitude = if magnitude.is_empty() {
+        1
+    } else if magnitude.bytes().all(|b| b.is_ascii_digit()) {
+        magnitude.parse().ok()?
+    } else {
+        return None;
+    };
+    match sign {
+        '+' => Some(magnitude),
+        '-' => Some(-magnitude),
+        _ => None,
+    }
+}
+
+fn parse_error(line: usize, message: &str) -> PyErr {
+    PyValueError::new_err(format!("PDB line {line}: {message}"))
+}
@@ -0,0 +1,335 @@
+use crate::batch::MolecularBatch;
+use crate::graph::{check_feature_shape, MolecularGraph};
+use crate::model::GNNModel;
+use crate::scheduler::{isolate, Scheduler};
+use numpy::ndarray;
+use numpy::{PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Work submitted to an [`InferencePool`].
+enum Job {
+    Single(MolecularGraph, ndarray::Array2<f32>),
+    Batch(Vec<MolecularGraph>, Vec<ndarray::Array2<f32>>),
+}
+
+/// What a job produces, before it is handed back to Python.
+enum JobOutput {
+    Single(ndarray::Array2<f32>),
+    Batch(Vec<ndarray::Array2<f32>>),
+}
+
+/// Futures of the jobs that have been submitted but not yet resolved.
+#[derive(Default)]
+struct Outstanding {
+    futures: Mutex<HashMap<u64, Py<PyAny>>>,
+    drained: Condvar,
+}
+
+/// A dedicated worker pool for inference jobs submitted from Python.
+///
+/// `submit` and `submit_batch` return `concurrent.futures.Future` objects,
+/// so callers can block on `result()`, add callbacks, or await them from
+/// asyncio via `asyncio.wrap_future`. A job still waiting in the queue can be
+/// cancelled with `Future.cancel()`. At most `max_queue` jobs may be pending at
+/// once; further submissions raise `queue.Full` instead of blocking.
+#[pyclass]
+pub struct InferencePool {
+    model: Arc<GNNModel>,
+    cutoff: f32,
+    num_offsets: usize,
+    #[pyo3(get)]
+    max_queue: usize,
+    workers: rayon::ThreadPool,
+    outstanding: Arc<Outstanding>,
+    next_id: u64,
+    closed: AtomicBool,
+}
+
+#[pymethods]
+impl InferencePool {
+    #[new]
+    #[pyo3(signature = (model, cutoff, num_offsets, workers=None, max_queue=64))]
+    /// Starts `workers` threads (one per core by default) for the pool.
+    ///
+    /// # Errors
+    /// Returns an error if `workers` or `max_queue` is zero or the threads
+    /// cannot be started.
+    pub fn new(
+        model: &GNNModel,
+        cutoff: f32,
+        num_offsets: usize,
+        workers: Option<usize>,
+        max_queue: usize,
+    ) -> PyResult<Self> {
+        if workers == Some(0) || max_queue == 0 {
+            return Err(PyValueError::new_err(
+                "workers and max_queue must be greater than zero",
+            ));
+        }
+        let workers = rayon::ThreadPoolBuilder::new()
+            .num_threads(workers.unwrap_or(0))
+            .thread_name(|i| format!("valence-pool-{i}"))
+            .build()
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+        Ok(InferencePool {
+            model: Arc::new(model.clone()),
+            cutoff,
+            num_offsets,
+            max_queue,
+            workers,
+            outstanding: Arc::new(Outstanding::default()),
+            next_id: 0,
+            closed: AtomicBool::new(false),
+        })
+    }
+
+    /// Queues inference for one graph; the future resolves to its
+    /// `(n_atoms, n_outputs)` output.
+    ///
+    /// # Errors
+    /// Returns an error if the features do not match the graph and model, the
+    /// queue is full, or the pool has been shut down.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn submit(
+        &mut self,
+        py: Python<'_>,
+        graph: &MolecularGraph,
+        atom_features: PyReadonlyArray2<f32>,
+    ) -> PyResult<Py<PyAny>> {
+        let features = self.checked_features(0, graph, &atom_features)?;
+        self.enqueue(py, Job::Single(graph.clone(), features))
+    }
+
+    /// Queues inference for every graph of `batch`; the future resolves to a
+    /// list with one output per graph.
+    ///
+    /// # Errors
+    /// Returns an error if the features do not match the batch and model, the
+    /// queue is full, or the pool has been shut down.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn submit_batch(
+        &mut self,
+        py: Python<'_>,
+        batch: &MolecularBatch,
+        all_atom_features: Vec<PyReadonlyArray2<f32>>,
+    ) -> PyResult<Py<PyAny>> {
+        if all_atom_features.len() != batch.graphs.len() {
+            return Err(PyValueError::new_err(format!(
+                "Expected one feature array per graph ({}), got {}",
+                batch.graphs.len(),
+                all_atom_features.len()
+            )));
+        }
+        let features = batch
+            .graphs
+            .iter()
+            .zip(&all_atom_features)
+            .enumerate()
+            .map(|(index, (graph, feats))| self.checked_features(index, graph, feats))
+            .collect::<PyResult<Vec<_>>>()?;
+        self.enqueue(py, Job::Batch(batch.graphs.clone(), features))
+    }
+
+    /// Number of submitted jobs whose futures have not resolved yet.
+    #[getter]
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.outstanding
+            .futures
+            .lock()
+            .map_or(0, |futures| futures.len())
+    }
+
+    /// Stops accepting jobs. With `cancel_futures`, jobs still waiting in the
+    /// queue are cancelled; with `wait`, blocks until the rest have finished.
+    ///
+    /// # Errors
+    /// Returns an error if a future cannot be cancelled.
+    #[pyo3(signature = (wait=true, cancel_futures=false))]
+    pub fn shutdown(&self, py: Python<'_>, wait: bool, cancel_futures: bool) -> PyResult<()> {
+        self.closed.store(true, Ordering::SeqCst);
+        if cancel_futures {
+            let futures: Vec<Py<PyAny>> = self
+                .lock_futures()?
+                .values()
+                .map(|future| future.clone_ref(py))
+                .collect();
+            for future in futures {
+                future.call_method0(py, "cancel")?;
+            }
+        }
+        if wait {
+            let outstanding = &self.outstanding;
+            py.detach(|| {
+                let mut futures = outstanding
+                    .futures
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner);
+                while !futures.is_empty() {
+                    futures = outstanding
+                        .drained
+                        .wait(futures)
+                        .unwrap_or_else(std::sync::PoisonError::into_inner);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: &Bound<'_, PyAny>,
+        _exc_value: &Bound<'_, PyAny>,
+        _traceback: &Bound<'_, PyAny>,
+    ) -> PyResult<bool> {
+        self.shutdown(py, true, false)?;
+        Ok(false)
+    }
+}
+
+impl InferencePool {
+    fn checked_features(
+        &self,
+        index: usize,
+        graph: &MolecularGraph,
+        features: &PyReadonlyArray2<f32>,
+    ) -> PyResult<ndarray::Array2<f32>> {
+        let features = features.as_array();
+        check_feature_shape(&features, graph.positions.len(), &self.model)
+            .map_err(|err| PyValueError::new_err(format!("Graph {index}: {err}")))?;
+        Ok(features.to_owned())
+    }
+
+    fn lock_futures(&self) -> PyResult<std::sync::MutexGuard<'_, HashMap<u64, Py<PyAny>>>> {
+        self.outstanding
+            .futures
+            .lock()
+            .map_err(|_| PyRuntimeError::new_err("Inference pool state is poisoned"))
+    }
+
+    /// Registers a future for `job` and hands the job to the workers.
+    fn enqueue(&mut self, py: Python<'_>, job: Job) -> PyResult<Py<PyAny>> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(PyRuntimeError::new_err(
+                "Cannot submit to an inference pool after shutdown",
+            ));
+        }
+        let future = py
+            .import("concurrent.futures")?
+            .getattr("Future")?
+            .call0()?
+            .unbind();
+        // No Python calls while the lock is held, as they may let a worker in
+        let full = {
+            let mut futures = self.lock_futures()?;
+            let full = futures.len() >= self.max_queue;
+            if !full {
+                futures.insert(self.next_id, future.clone_ref(py));
+            }
+            full
+        };
+        if full {
+            let full = py.import("queue")?.getattr("Full")?;
+            return Err(PyErr::from_value(full.call1((format!(
+                "Inference pool queue is full ({} jobs)",
+                self.max_queue
+            ),))?));
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let handle = future.clone_ref(py);
+        let model = Arc::clone(&self.model);
+        let outstanding = Arc::clone(&self.outstanding);
+        let (cutoff, num_offsets) = (self.cutoff, self.num_offsets);
+        self.workers.spawn(move || {
+            // Cancelled futures are skipped without running the job
+            let start = Python::attach(|py| {
+                handle
+                    .call_method0(py, "set_running_or_notify_cancel")
+                    .and_then(|running| running.extract::<bool>(py))
+                    .unwrap_or(false)
+            });
+            let output = start.then(|| isolate(|| run_job(job, &model, cutoff, num_offsets)));
+
+            Python::attach(|py| {
+                if let Some(output) = output {
+                    resolve(py, handle.bind(py), output);
+                }
+                let mut futures = outstanding
+                    .futures
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner);
+                let finished = futures.remove(&id);
+                if futures.is_empty() {
+                    outstanding.drained.notify_all();
+                }
+                drop(futures);
+                drop(finished);
+            });
+        });
+        Ok(future)
+    }
+}
+
+/// Evaluates `job` on the calling worker's pool.
+fn run_job(job: Job, model: &GNNModel, cutoff: f32, num_offsets: usize) -> JobOutput {
+    let n_outputs = model.weights.nrows();
+    match job {
+        Job::Single(graph, features) => {
+            let mut out = ndarray::Array2::zeros((graph.positions.len(), n_outputs));
+            let buffer = out.as_slice_mut().expect("fresh arrays are contiguous");
+            graph.write_fused(model, cutoff, num_offsets, &features.view(), buffer, true);
+            JobOutput::Single(out)
+        }
+        Job::Batch(graphs, features) => {
+            let mut outs: Vec<ndarray::Array2<f32>> = graphs
+                .iter()
+                .map(|graph| ndarray::Array2::zeros((graph.positions.len(), n_outputs)))
+                .collect();
+            let views: Vec<_> = features.iter().map(|f| f.view()).collect();
+            Scheduler::default().execute_into(
+                &graphs,
+                model,
+                &views,
+                &vec![cutoff; graphs.len()],
+                &vec![num_offsets; graphs.len()],
+                outs.iter_mut()
+                    .map(|out| out.as_slice_mut().expect("fresh arrays are contiguous"))
+                    .collect(),
+            );
+            JobOutput::Batch(outs)
+        }
+    }
+}
+
+/// Sets the result, or the failure, of a finished job on its future.
+fn resolve(py: Python<'_>, future: &Bound<'_, PyAny>, output: Result<JobOutput, String>) {
+    let outcome = match output {
+        Ok(JobOutput::Single(out)) => {
+            future.call_method1("set_result", (PyArray2::from_array(py, &out),))
+        }
+        Ok(JobOutput::Batch(outs)) => {
+            PyList::new(py, outs.iter().map(|out| PyArray2::from_array(py, out)))
+                .and_then(|outs| future.call_method1("set_result", (outs,)))
+        }
+        Err(reason) => future.call_method1(
+            "set_exception",
+            (PyRuntimeError::new_err(reason).into_value(py),),
+        ),
+    };
+    // Only fails if the future was resolved behind our back
+    if let Err(err) = outcome {
+        err.write_unraisable(py, Some(future));
+    }
+}
@@ -0,0 +1,112 @@
+use crate::graph::{check_feature_shape, readout_vector, MolecularGraph};
+use crate::model::GNNModel;
+use nalgebra::{DVector, Matrix3, Vector3};
+use numpy::{ndarray, PyArray2, PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// A `GNNModel` bound to the settings that turn it into an interatomic potential.
+///
+/// The energy is the scalar readout `E = sum_i readout . y_i`. The model
+/// weights are copied at construction, so later training does not change a
+/// running simulation.
+#[pyclass]
+#[derive(Clone)]
+pub struct Potential {
+    pub model: GNNModel,
+    pub readout: DVector<f32>,
+    #[pyo3(get)]
+    pub cutoff: f32,
+    #[pyo3(get)]
+    pub num_offsets: usize,
+}
+
+/// Energy, forces and strain derivative of one geometry.
+pub struct Evaluation {
+    pub energy: f32,
+    pub forces: Vec<Vector3<f32>>,
+    /// `dE/d(eps)`; divide by the cell volume for the stress.
+    pub strain: Matrix3<f64>,
+}
+
+#[pymethods]
+impl Potential {
+    /// # Errors
+    /// Returns an error if the readout length does not match the model outputs.
+    #[new]
+    #[pyo3(signature = (model, cutoff, num_offsets, readout=None))]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn new(
+        model: &GNNModel,
+        cutoff: f32,
+        num_offsets: usize,
+        readout: Option<PyReadonlyArray1<f32>>,
+    ) -> PyResult<Self> {
+        Ok(Potential {
+            model: model.clone(),
+            readout: readout_vector(readout.as_ref(), model)?,
+            cutoff,
+            num_offsets,
+        })
+    }
+
+    /// Returns `(energy, forces)` for `graph`.
+    ///
+    /// # Errors
+    /// Returns an error if the feature array does not match the graph and model.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn energy_and_forces(
+        &self,
+        graph: &MolecularGraph,
+        atom_features: PyReadonlyArray2<f32>,
+    ) -> PyResult<(f32, Py<PyArray2<f32>>)> {
+        let py = atom_features.py();
+        let atom_view = atom_features.as_array();
+        self.check_features(graph, &atom_view)?;
+        let evaluation = self.evaluate(graph, &atom_view);
+        Ok((
+            evaluation.energy,
+            PyArray2::from_array(py, &vectors_to_array(&evaluation.forces)).into(),
+        ))
+    }
+}
+
+impl Potential {
+    #[must_use]
+    pub fn evaluate(
+        &self,
+        graph: &MolecularGraph,
+        atom_view: &ndarray::ArrayView2<f32>,
+    ) -> Evaluation {
+        let (energy, gradient, strain) = graph.energy_gradient_strain_internal(
+            &self.model,
+            atom_view,
+            &self.readout,
+            self.cutoff,
+            self.num_offsets,
+        );
+        Evaluation {
+            energy,
+            forces: gradient.into_iter().map(|g| -g).collect(),
+            strain,
+        }
+    }
+
+    /// Validates that `atom_view` has one row per atom and one column per model input.
+    ///
+    /// # Errors
+    /// Returns an error describing the expected shape.
+    pub fn check_features(
+        &self,
+        graph: &MolecularGraph,
+        atom_view: &ndarray::ArrayView2<f32>,
+    ) -> PyResult<()> {
+        check_feature_shape(atom_view, graph.positions.len(), &self.model)
+            .map_err(PyValueError::new_err)
+    }
+}
+
+/// Packs per-atom 3-vectors into an `(n, 3)` array.
+pub(crate) fn vectors_to_array(vectors: &[Vector3<f32>]) -> ndarray::Array2<f32> {
+    ndarray::Array2::from_shape_fn((vectors.len(), 3), |(i, k)| vectors[i][k])
+}
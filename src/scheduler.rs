@@ -0,0 +1,354 @@
+use crate::batch::MolecularBatch;
+use crate::graph::MolecularGraph;
+use crate::model::GNNModel;
+use nalgebra::DVector;
+use numpy::ndarray;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use std::borrow::Borrow;
+use std::panic::AssertUnwindSafe;
+
+/// How the work inside a chunk is spread over the thread pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parallelism {
+    /// One task per graph, each graph evaluated serially.
+    Graphs,
+    /// A single graph whose atoms are split across tasks.
+    Atoms,
+}
+
+impl Parallelism {
+    fn name(self) -> &'static str {
+        match self {
+            Parallelism::Graphs => "graphs",
+            Parallelism::Atoms => "atoms",
+        }
+    }
+}
+
+/// Graphs that are evaluated together, by index into the batch.
+#[derive(Clone, Debug)]
+pub struct Chunk {
+    pub graphs: Vec<usize>,
+    pub parallelism: Parallelism,
+}
+
+/// Splits a batch into chunks bounded by a total atom and pair budget, where
+/// pairs are the candidate atom pairs the kernel checks against the cutoff.
+///
+/// Graphs with at least `atom_parallel_threshold` atoms, or whose candidate
+/// pairs alone exceed `max_pairs`, get a chunk of their own and are parallelised over
+/// atoms. The remaining graphs are packed into chunks parallelised over
+/// graphs. Chunks run one after another, most expensive first, so a single
+/// large molecule no longer finishes last behind a sea of small ones.
+#[pyclass]
+#[derive(Clone)]
+pub struct Scheduler {
+    #[pyo3(get)]
+    pub max_atoms: usize,
+    #[pyo3(get)]
+    pub max_pairs: usize,
+    #[pyo3(get)]
+    pub atom_parallel_threshold: usize,
+}
+
+#[pymethods]
+impl Scheduler {
+    #[new]
+    #[pyo3(signature = (max_atoms=8192, max_pairs=50_000_000, atom_parallel_threshold=512))]
+    /// Creates a scheduler with the given per-chunk budgets.
+    ///
+    /// # Errors
+    /// Returns an error if any budget is zero.
+    pub fn new(
+        max_atoms: usize,
+        max_pairs: usize,
+        atom_parallel_threshold: usize,
+    ) -> PyResult<Self> {
+        if max_atoms == 0 || max_pairs == 0 || atom_parallel_threshold == 0 {
+            return Err(PyValueError::new_err(
+                "Scheduler budgets must be greater than zero",
+            ));
+        }
+        Ok(Scheduler {
+            max_atoms,
+            max_pairs,
+            atom_parallel_threshold,
+        })
+    }
+
+    /// The chunks for `batch` in execution order, as `(graph_indices, parallelism)`
+    /// pairs where `parallelism` is `"graphs"` or `"atoms"`. `cutoff` is a
+    /// scalar or one value per graph.
+    ///
+    /// # Errors
+    /// Returns an error if `cutoff` is not a positive scalar or per-graph sequence.
+    pub fn plan(
+        &self,
+        batch: &MolecularBatch,
+        cutoff: &Bound<'_, PyAny>,
+    ) -> PyResult<Vec<(Vec<usize>, &'static str)>> {
+        let cutoffs = per_graph_cutoffs(cutoff, batch.graphs.len())?;
+        Ok(self
+            .chunks(&batch.graphs, &cutoffs)
+            .into_iter()
+            .map(|chunk| (chunk.graphs, chunk.parallelism.name()))
+            .collect())
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler {
+            max_atoms: 8192,
+            max_pairs: 50_000_000,
+            atom_parallel_threshold: 512,
+        }
+    }
+}
+
+impl Scheduler {
+    /// Groups `graphs` into chunks that respect the atom and pair budgets, with
+    /// periodic images counted at each graph's own cutoff.
+    #[must_use]
+    pub fn chunks<G: Borrow<MolecularGraph>>(&self, graphs: &[G], cutoffs: &[f32]) -> Vec<Chunk> {
+        let costs: Vec<(usize, usize)> = graphs
+            .iter()
+            .zip(cutoffs)
+            .map(|(graph, &cutoff)| {
+                let graph = graph.borrow();
+                (graph.positions.len(), candidate_pairs(graph, cutoff))
+            })
+            .collect();
+        // Most expensive first, ties kept in batch order
+        let mut order: Vec<usize> = (0..graphs.len()).collect();
+        order.sort_by_key(|&g| std::cmp::Reverse(costs[g].1));
+
+        let (large, small): (Vec<usize>, Vec<usize>) = order.into_iter().partition(|&g| {
+            costs[g].0 >= self.atom_parallel_threshold || costs[g].1 >= self.max_pairs
+        });
+
+        let mut chunks: Vec<Chunk> = large
+            .into_iter()
+            .map(|g| Chunk {
+                graphs: vec![g],
+                parallelism: Parallelism::Atoms,
+            })
+            .collect();
+
+        let mut current = Vec::new();
+        let (mut atoms, mut pairs) = (0, 0);
+        for g in small {
+            let (graph_atoms, graph_pairs) = costs[g];
+            if !current.is_empty()
+                && (atoms + graph_atoms > self.max_atoms || pairs + graph_pairs > self.max_pairs)
+            {
+                chunks.push(Chunk {
+                    graphs: std::mem::take(&mut current),
+                    parallelism: Parallelism::Graphs,
+                });
+                (atoms, pairs) = (0, 0);
+            }
+            current.push(g);
+            atoms += graph_atoms;
+            pairs += graph_pairs;
+        }
+        if !current.is_empty() {
+            chunks.push(Chunk {
+                graphs: current,
+                parallelism: Parallelism::Graphs,
+            });
+        }
+        chunks
+    }
+
+    /// Runs the model over every graph chunk by chunk and returns the per-atom
+    /// outputs in batch order. Graph `g` is evaluated with `cutoffs[g]` and
+    /// `num_offsets[g]` radial basis functions.
+    ///
+    /// # Panics
+    /// Panics if `features`, `cutoffs` or `num_offsets` do not hold one entry
+    /// per graph, or if evaluating any graph panics.
+    #[must_use]
+    pub fn execute<G: Borrow<MolecularGraph> + Sync>(
+        &self,
+        graphs: &[G],
+        model: &GNNModel,
+        features: &[ndarray::ArrayView2<f32>],
+        cutoffs: &[f32],
+        num_offsets: &[usize],
+    ) -> Vec<Vec<DVector<f32>>> {
+        self.try_execute(graphs, model, features, cutoffs, num_offsets)
+            .into_iter()
+            .map(|outcome| outcome.unwrap_or_else(|reason| panic!("{reason}")))
+            .collect()
+    }
+
+    /// Like [`Self::execute`], but writes the outputs of graph `g` straight
+    /// into `outs[g]`, row after row.
+    ///
+    /// # Panics
+    /// Panics if `features`, `cutoffs`, `num_offsets` or `outs` do not hold one
+    /// entry per graph, or a feature array or output does not match its graph.
+    /// Callers validate these first; the checks only guard that invariant.
+    pub fn execute_into<G: Borrow<MolecularGraph> + Sync>(
+        &self,
+        graphs: &[G],
+        model: &GNNModel,
+        features: &[ndarray::ArrayView2<f32>],
+        cutoffs: &[f32],
+        num_offsets: &[usize],
+        outs: Vec<&mut [f32]>,
+    ) {
+        assert!(
+            features.len() == graphs.len()
+                && cutoffs.len() == graphs.len()
+                && num_offsets.len() == graphs.len()
+                && outs.len() == graphs.len(),
+            "Expected one feature array, cutoff, num_offsets and output per graph"
+        );
+        let mut outs: Vec<Option<&mut [f32]>> = outs.into_iter().map(Some).collect();
+        for chunk in self.chunks(graphs, cutoffs) {
+            let jobs: Vec<(usize, &mut [f32])> = chunk
+                .graphs
+                .iter()
+                .filter_map(|&g| outs[g].take().map(|out| (g, out)))
+                .collect();
+            let parallel_atoms = chunk.parallelism == Parallelism::Atoms;
+            jobs.into_par_iter().for_each(|(g, out)| {
+                graphs[g].borrow().write_fused(
+                    model,
+                    cutoffs[g],
+                    num_offsets[g],
+                    &features[g],
+                    out,
+                    parallel_atoms,
+                );
+            });
+        }
+    }
+
+    /// Like [`Self::execute`], but a graph whose evaluation panics yields the
+    /// panic message instead of taking the rest of the batch down with it.
+    ///
+    /// # Panics
+    /// Panics if `features`, `cutoffs` or `num_offsets` do not hold one entry
+    /// per graph.
+    #[must_use]
+    pub fn try_execute<G: Borrow<MolecularGraph> + Sync>(
+        &self,
+        graphs: &[G],
+        model: &GNNModel,
+        features: &[ndarray::ArrayView2<f32>],
+        cutoffs: &[f32],
+        num_offsets: &[usize],
+    ) -> Vec<Result<Vec<DVector<f32>>, String>> {
+        assert!(
+            features.len() == graphs.len()
+                && cutoffs.len() == graphs.len()
+                && num_offsets.len() == graphs.len(),
+            "Expected one feature array, cutoff and num_offsets per graph"
+        );
+        let mut results = vec![Ok(Vec::new()); graphs.len()];
+        for chunk in self.chunks(graphs, cutoffs) {
+            match chunk.parallelism {
+                Parallelism::Atoms => {
+                    let g = chunk.graphs[0];
+                    results[g] = isolate(|| {
+                        graphs[g]
+                            .borrow()
+                            .compute_core_fused(cutoffs[g], num_offsets[g], &features[g])
+                            .into_par_iter()
+                            .map(|agg| &model.weights * agg)
+                            .collect()
+                    });
+                }
+                Parallelism::Graphs => {
+                    let outputs: Vec<_> = chunk
+                        .graphs
+                        .par_iter()
+                        .map(|&g| {
+                            let rows = isolate(|| {
+                                graphs[g]
+                                    .borrow()
+                                    .compute_core_serial(cutoffs[g], num_offsets[g], &features[g])
+                                    .into_iter()
+                                    .map(|agg| &model.weights * agg)
+                                    .collect()
+                            });
+                            (g, rows)
+                        })
+                        .collect();
+                    for (g, rows) in outputs {
+                        results[g] = rows;
+                    }
+                }
+            }
+        }
+        results
+    }
+}
+
+/// Runs `evaluate`, turning a panic into its message.
+pub(crate) fn isolate<T>(evaluate: impl FnOnce() -> T) -> Result<T, String> {
+    std::panic::catch_unwind(AssertUnwindSafe(evaluate)).map_err(|payload| {
+        payload
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Graph evaluation panicked".to_string())
+    })
+}
+
+/// Atom pairs, periodic images within `cutoff` included, that the fused kernel
+/// visits for `graph`. The kernel checks every pair against the cutoff, so this
+/// is what its run time scales with, rather than the edges that pass the check.
+#[must_use]
+pub fn candidate_pairs(graph: &MolecularGraph, cutoff: f32) -> usize {
+    let n = graph.positions.len();
+    let images = graph.image_shifts(cutoff).len();
+    (n * n * images).saturating_sub(n)
+}
+
+/// A cutoff given either as one scalar for the whole batch or as one value per
+/// graph.
+///
+/// # Errors
+/// Returns an error if the cutoffs are not positive and finite or their count
+/// does not match `n_graphs`.
+pub fn per_graph_cutoffs(cutoff: &Bound<'_, PyAny>, n_graphs: usize) -> PyResult<Vec<f32>> {
+    let cutoffs = match cutoff.extract::<f32>() {
+        Ok(value) => vec![value; n_graphs],
+        Err(_) => cutoff.extract::<Vec<f32>>()?,
+    };
+    if cutoffs.len() != n_graphs {
+        return Err(PyValueError::new_err(format!(
+            "Expected one cutoff per graph ({n_graphs}), got {}",
+            cutoffs.len()
+        )));
+    }
+    if cutoffs.iter().any(|c| !c.is_finite() || *c <= 0.0) {
+        return Err(PyValueError::new_err("Cutoffs must be positive and finite"));
+    }
+    Ok(cutoffs)
+}
+
+/// Radial basis sizes given either as one scalar for the whole batch or as one
+/// value per graph.
+///
+/// # Errors
+/// Returns an error if the counts are not integers or do not match `n_graphs`.
+pub fn per_graph_offsets(num_offsets: &Bound<'_, PyAny>, n_graphs: usize) -> PyResult<Vec<usize>> {
+    let offsets = match num_offsets.extract::<usize>() {
+        Ok(value) => vec![value; n_graphs],
+        Err(_) => num_offsets.extract::<Vec<usize>>()?,
+    };
+    if offsets.len() != n_graphs {
+        return Err(PyValueError::new_err(format!(
+            "Expected one num_offsets per graph ({n_graphs}), got {}",
+            offsets.len()
+        )));
+    }
+    Ok(offsets)
+}
@@ -0,0 +1,364 @@
+use crate::elements::atomic_number;
+use crate::graph::MolecularGraph;
+use nalgebra::Vector3;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// One record of an SDF file: a molecule with its bond table and data fields.
+#[pyclass]
+#[derive(Clone)]
+pub struct SdfRecord {
+    pub graph: MolecularGraph,
+    /// The first header line.
+    #[pyo3(get)]
+    pub name: String,
+    /// Bonds as `(i, j, order)` with zero-based atom indices; aromatic bonds
+    /// have order 4.
+    #[pyo3(get)]
+    pub bonds: Vec<(usize, usize, u8)>,
+    #[pyo3(get)]
+    pub formal_charges: Vec<i32>,
+    /// `> <NAME>` data fields in file order.
+    pub properties: Vec<(String, String)>,
+}
+
+#[pymethods]
+impl SdfRecord {
+    #[getter]
+    #[must_use]
+    pub fn graph(&self) -> MolecularGraph {
+        self.graph.clone()
+    }
+
+    /// Data fields as a dict of strings; multi-line values keep their newlines.
+    ///
+    /// # Errors
+    /// Returns an error if the dict cannot be built.
+    #[getter]
+    pub fn properties<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (key, value) in &self.properties {
+            dict.set_item(key, value)?;
+        }
+        Ok(dict)
+    }
+
+    fn __len__(&self) -> usize {
+        self.graph.positions.len()
+    }
+}
+
+/// Lazily reads the records of an SDF file, so files larger than memory can
+/// be processed one molecule at a time.
+#[pyclass]
+pub struct SdfReader {
+    lines: Lines<BufReader<File>>,
+}
+
+#[pymethods]
+impl SdfReader {
+    #[new]
+    /// Opens `path` for reading.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened.
+    pub fn new(path: &str) -> PyResult<Self> {
+        Ok(SdfReader {
+            lines: Lines::new(BufReader::new(File::open(path)?)),
+        })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<SdfRecord>> {
+        read_record(&mut self.lines)
+    }
+}
+
+/// Reads every record of an SDF or MOL file, in V2000 or V3000 format.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or a record is malformed.
+#[pyfunction]
+pub fn read_sdf(path: &str) -> PyResult<Vec<SdfRecord>> {
+    let mut lines = Lines::new(BufReader::new(File::open(path)?));
+    let mut records = Vec::new();
+    while let Some(record) = read_record(&mut lines)? {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Lines of the input, counted for error messages.
+pub struct Lines<R> {
+    reader: R,
+    line: usize,
+}
+
+impl<R: BufRead> Lines<R> {
+    pub fn new(reader: R) -> Self {
+        Lines { reader, line: 0 }
+    }
+
+    /// The next line without its line ending, or `None` at the end of the input.
+    fn next(&mut self) -> PyResult<Option<String>> {
+        let mut text = String::new();
+        if self.reader.read_line(&mut text)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        text.truncate(text.trim_end_matches(['\n', '\r']).len());
+        Ok(Some(text))
+    }
+
+    /// The next line, which must exist.
+    fn expect(&mut self, what: &str) -> PyResult<String> {
+        self.next()?
+            .ok_or_else(|| self.error(&format!("the file ended before the {what}")))
+    }
+
+    fn error(&self, message: &str) -> PyErr {
+        PyValueError::new_err(format!("SDF line {}: {message}", self.line))
+    }
+}
+
+/// Parses the next record, or `None` once only blank lines are left.
+///
+/// # Errors
+/// Returns an error if the input cannot be read or the record is malformed.
+pub fn read_record<R: BufRead>(lines: &mut Lines<R>) -> PyResult<Option<SdfRecord>> {
+    let Some(name) = lines.next()? else {
+        return Ok(None);
+    };
+    let Some(_program) = lines.next()? else {
+        // Trailing blank lines after the last `$$$$`
+        if name.trim().is_empty() {
+            return Ok(None);
+        }
+        return Err(lines.error("the file ended before the header"));
+    };
+    let _comment = lines.expect("header")?;
+    let counts = lines.expect("counts line")?;
+    let molecule = if counts.contains("V3000") {
+        read_v3000(lines)?
+    } else {
+        read_v2000(lines, &counts)?
+    };
+
+    // Each `> <NAME>` header is followed by value lines up to a blank line
+    let mut properties: Vec<(String, String)> = Vec::new();
+    let mut in_value = false;
+    while let Some(text) = lines.next()? {
+        if text.starts_with("$$$$") {
+            break;
+        }
+        if let Some(key) = data_header(&text) {
+            properties.push((key, String::new()));
+            in_value = true;
+        } else if text.trim().is_empty() {
+            in_value = false;
+        } else if let Some((_, value)) = properties.last_mut().filter(|_| in_value) {
+            if !value.is_empty() {
+                value.push('\n');
+            }
+            value.push_str(&text);
+        }
+    }
+    Ok(Some(SdfRecord {
+        graph: MolecularGraph::from_parts(
+            molecule.atomic_numbers,
+            molecule.positions,
+            None,
+            [false; 3],
+        )?,
+        name: name.trim().to_string(),
+        bonds: molecule.bonds,
+        formal_charges: molecule.charges,
+        properties,
+    }))
+}
+
+/// The connection table of a record.
+#[derive(Default)]
+struct Molecule {
+    atomic_numbers: Vec<i32>,
+    positions: Vec<Vector3<f32>>,
+    charges: Vec<i32>,
+    bonds: Vec<(usize, usize, u8)>,
+}
+
+impl Molecule {
+    /// Adds a bond given with one-based atom numbers.
+    fn bond<R: BufRead>(
+        &mut self,
+        lines: &Lines<R>,
+        a: usize,
+        b: usize,
+        order: u8,
+    ) -> PyResult<()> {
+        let n = self.atomic_numbers.len();
+        if a == 0 || b == 0 || a > n || b > n {
+            return Err(lines.error(&format!("bond between atoms {a} and {b} of {n}")));
+        }
+        self.bonds.push((a - 1, b - 1, order));
+        Ok(())
+    }
+}
+
+/// Reads a V2000 atom block, bond block and property block.
+fn read_v2000<R: BufRead>(lines: &mut Lines<R>, counts: &str) -> PyResult<Molecule> {
+    let count = |start: usize, end: usize| column(counts, start, end).parse::<usize>().ok();
+    let (Some(n_atoms), Some(n_bonds)) = (count(0, 3), count(3, 6)) else {
+        return Err(lines.error(&format!("invalid counts line {counts:?}")));
+    };
+
+    let mut molecule = Molecule::default();
+    for _ in 0..n_atoms {
+        let text = lines.expect("end of the atom block")?;
+        let number = |start, end| -> PyResult<f32> {
+            let field = column(&text, start, end);
+            field
+                .parse()
+                .map_err(|_| lines.error(&format!("invalid coordinate {field:?}")))
+        };
+        molecule.positions.push(Vector3::new(
+            number(0, 10)?,
+            number(10, 20)?,
+            number(20, 30)?,
+        ));
+        molecule
+            .atomic_numbers
+            .push(element(lines, column(&text, 31, 34))?);
+        // Charge codes 1 to 7 stand for +3, +2, +1, doublet radical, -1, -2, -3
+        let code: i32 = column(&text, 36, 39).parse().unwrap_or(0);
+        molecule.charges.push(match code {
+            1..=3 | 5..=7 => 4 - code,
+            _ => 0,
+        });
+    }
+    for _ in 0..n_bonds {
+        let text = lines.expect("end of the bond block")?;
+        let field = |start, end| column(&text, start, end).parse::<usize>().ok();
+        let (Some(a), Some(b), Some(order)) = (field(0, 3), field(3, 6), field(6, 9)) else {
+            return Err(lines.error(&format!("invalid bond line {text:?}")));
+        };
+        molecule.bond(lines, a, b, u8::try_from(order).unwrap_or(0))?;
+    }
+
+    // `M  CHG` lines replace every charge given in the atom block
+    let mut charges_reset = false;
+    loop {
+        let text = lines.expect("M  END line")?;
+        if text.starts_with("M  END") {
+            return Ok(molecule);
+        }
+        if let Some(entries) = text.strip_prefix("M  CHG") {
+            if !charges_reset {
+                molecule.charges.fill(0);
+                charges_reset = true;
+            }
+            let values: Vec<i64> = entries
+                .split_whitespace()
+                .skip(1)
+                .map(|v| {
+                    v.parse()
+                        .map_err(|_| lines.error(&format!("invalid charge {v:?}")))
+                })
+                .collect::<PyResult<_>>()?;
+            for pair in values.chunks_exact(2) {
+                let atom = usize::try_from(pair[0] - 1)
+                    .ok()
+                    .filter(|&a| a < molecule.charges.len())
+                    .ok_or_else(|| lines.error(&format!("charge on missing atom {}", pair[0])))?;
+                molecule.charges[atom] = i32::try_from(pair[1]).unwrap_or(0);
+            }
+        }
+    }
+}
+
+/// Reads a V3000 connection table, from `BEGIN CTAB` to `M  END`.
+fn read_v3000<R: BufRead>(lines: &mut Lines<R>) -> PyResult<Molecule> {
+    let mut molecule = Molecule::default();
+    let mut block = String::new();
+    loop {
+        let text = v3000_line(lines)?;
+        if text.starts_with("M  END") {
+            return Ok(molecule);
+        }
+        let Some(content) = text.strip_prefix("M  V30 ") else {
+            continue;
+        };
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        match fields.as_slice() {
+            ["BEGIN", name, ..] => block = (*name).to_string(),
+            ["END", ..] => block.clear(),
+            [_, symbol, x, y, z, _, rest @ ..] if block == "ATOM" => {
+                let number = |field: &str| -> PyResult<f32> {
+                    field
+                        .parse()
+                        .map_err(|_| lines.error(&format!("invalid coordinate {field:?}")))
+                };
+                molecule
+                    .positions
+                    .push(Vector3::new(number(x)?, number(y)?, number(z)?));
+                molecule.atomic_numbers.push(element(lines, symbol)?);
+                let charge = rest
+                    .iter()
+                    .find_map(|field| field.strip_prefix("CHG="))
+                    .map_or(Ok(0), str::parse)
+                    .map_err(|_| lines.error("invalid CHG value"))?;
+                molecule.charges.push(charge);
+            }
+            [_, order, a, b, ..] if block == "BOND" => {
+                let parse = |field: &str| field.parse::<usize>().ok();
+                let (Some(order), Some(a), Some(b)) = (parse(order), parse(a), parse(b)) else {
+                    return Err(lines.error(&format!("invalid bond line {text:?}")));
+                };
+                molecule.bond(lines, a, b, u8::try_from(order).unwrap_or(0))?;
+            }
+            _ if block == "ATOM" || block == "BOND" => {
+                return Err(lines.error(&format!("invalid {block} line {text:?}")));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The next V3000 line with `-` continuations joined.
+fn v3000_line<R: BufRead>(lines: &mut Lines<R>) -> PyResult<String> {
+    let mut text = lines.expect("M  END line")?;
+    while let Some(head) = text.strip_suffix('-') {
+        let next = lines.expect("continued line")?;
+        let next = next.strip_prefix("M  V30 ").unwrap_or(&next);
+        text = format!("{head}{next}");
+    }
+    Ok(text)
+}
+
+/// The field name of a `> <NAME>` data header line.
+fn data_header(text: &str) -> Option<String> {
+    let rest = text.strip_prefix('>')?;
+    let start = rest.find('<')? + 1;
+    let end = start + rest[start..].find('>')?;
+    Some(rest[start..end].to_string())
+}
+
+/// Atomic number of an atom symbol; deuterium and tritium count as hydrogen.
+fn element<R: BufRead>(lines: &Lines<R>, symbol: &str) -> PyResult<i32> {
+    match symbol {
+        "D" | "T" => Ok(1),
+        _ => {
+            atomic_number(symbol).ok_or_else(|| lines.error(&format!("unknown element {symbol:?}")))
+        }
+    }
+}
+
+/// The trimmed text of columns `start..end`, empty past the end of the line.
+fn column(line: &str, start: usize, end: usize) -> &str {
+    line.get(start..end.min(line.len())).unwrap_or("").trim()
+}
@@ -0,0 +1,257 @@
+use crate::graph::{check_feature_shape, MolecularGraph};
+use crate::model::GNNModel;
+use crate::scheduler::Scheduler;
+use nalgebra::DVector;
+use numpy::ndarray;
+use numpy::{PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyIterator;
+use std::collections::VecDeque;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// Outputs of one chunk, tagged with the stream index of its first item;
+/// `None` if evaluating the chunk panicked.
+type ChunkOutput = (usize, Option<Vec<Vec<DVector<f32>>>>);
+
+/// Streaming inference over an iterable of `(graph, atom_features)` pairs.
+///
+/// With a `featurize` callable, items may instead be graphs or records with a
+/// `graph` attribute, such as those yielded by `XyzReader`, `SdfReader` and
+/// `LammpsDumpReader`, and their features are `featurize(graph)`. A file is
+/// then streamed straight from disk.
+///
+/// Items are pulled from `source` in chunks of `chunk_size` and evaluated on
+/// the thread pool, with at most `max_in_flight` chunks evaluated or waiting
+/// to be yielded at a time.
+/// Iterating yields `(index, output)` pairs in the order chunks
+/// complete, where `index` is the position of the item in `source`. Only the
+/// in-flight chunks are held in memory, so arbitrarily long sources can be
+/// scored. An item that cannot be evaluated raises a `ValueError` naming its
+/// index; the items pulled before it are still yielded, and iterating again
+/// carries on after it.
+#[pyclass]
+pub struct InferenceStream {
+    source: Py<PyIterator>,
+    featurize: Option<Py<PyAny>>,
+    model: Arc<GNNModel>,
+    scheduler: Scheduler,
+    cutoff: f32,
+    num_offsets: usize,
+    chunk_size: usize,
+    max_in_flight: usize,
+    in_flight: usize,
+    pulled: usize,
+    exhausted: bool,
+    ready: VecDeque<(usize, Vec<DVector<f32>>)>,
+    sender: Sender<ChunkOutput>,
+    receiver: Mutex<Receiver<ChunkOutput>>,
+}
+
+#[pymethods]
+impl InferenceStream {
+    #[new]
+    #[pyo3(signature = (source, model, cutoff, num_offsets, chunk_size=256, max_in_flight=2, scheduler=None, featurize=None))]
+    /// Creates a stream over `source`, which is consumed lazily.
+    ///
+    /// # Errors
+    /// Returns an error if `source` is not iterable or `chunk_size` or
+    /// `max_in_flight` is zero.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        source: &Bound<'_, PyAny>,
+        model: &GNNModel,
+        cutoff: f32,
+        num_offsets: usize,
+        chunk_size: usize,
+        max_in_flight: usize,
+        scheduler: Option<Scheduler>,
+        featurize: Option<Py<PyAny>>,
+    ) -> PyResult<Self> {
+        if chunk_size == 0 || max_in_flight == 0 {
+            return Err(PyValueError::new_err(
+                "chunk_size and max_in_flight must be greater than zero",
+            ));
+        }
+        let (sender, receiver) = channel();
+        Ok(InferenceStream {
+            source: PyIterator::from_object(source)?.unbind(),
+            featurize,
+            model: Arc::new(model.clone()),
+            scheduler: scheduler.unwrap_or_default(),
+            cutoff,
+            num_offsets,
+            chunk_size,
+            max_in_flight,
+            in_flight: 0,
+            pulled: 0,
+            exhausted: false,
+            ready: VecDeque::new(),
+            sender,
+            receiver: Mutex::new(receiver),
+        })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<(usize, Py<PyArray2<f32>>)>> {
+        loop {
+            if let Some((index, rows)) = self.ready.pop_front() {
+                let array = ndarray::Array2::from_shape_fn(
+                    (rows.len(), self.model.weights.nrows()),
+                    |(i, j)| rows[i][j],
+                );
+                return Ok(Some((index, PyArray2::from_array(py, &array).into())));
+            }
+            // Refill only once the finished chunks are drained, so buffered
+            // outputs and running chunks together stay within `max_in_flight`
+            while !self.exhausted && self.in_flight < self.max_in_flight {
+                self.submit_chunk(py)?;
+            }
+            if self.in_flight == 0 {
+                return Ok(None);
+            }
+
+            // Wait for the next chunk without blocking other Python threads
+            let receiver = &self.receiver;
+            let outcome = py.detach(|| {
+                receiver
+                    .lock()
+                    .expect("stream receiver lock poisoned")
+                    .recv()
+            });
+            self.in_flight -= 1;
+            let Ok((start, Some(outputs))) = outcome else {
+                return Err(PyRuntimeError::new_err(
+                    "Inference worker stopped unexpectedly",
+                ));
+            };
+            self.ready.extend(
+                outputs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(offset, rows)| (start + offset, rows)),
+            );
+        }
+    }
+
+    /// Number of items pulled from the source so far.
+    #[getter]
+    #[must_use]
+    pub fn pulled(&self) -> usize {
+        self.pulled
+    }
+}
+
+impl InferenceStream {
+    /// The graph and features of a source item.
+    fn extract_item(
+        &self,
+        py: Python<'_>,
+        item: &Bound<'_, PyAny>,
+        index: usize,
+    ) -> PyResult<(MolecularGraph, ndarray::Array2<f32>)> {
+        let Some(featurize) = &self.featurize else {
+            let (graph, feats): (PyRef<'_, MolecularGraph>, PyReadonlyArray2<f32>) =
+                item.extract().map_err(|_| {
+                    PyValueError::new_err(format!(
+                        "Item {index} is not a (MolecularGraph, atom_features) pair"
+                    ))
+                })?;
+            return Ok((graph.clone(), feats.as_array().to_owned()));
+        };
+        let graph = match item.cast::<MolecularGraph>() {
+            Ok(graph) => graph.clone(),
+            Err(_) => item
+                .getattr("graph")
+                .and_then(|graph| Ok(graph.cast_into::<MolecularGraph>()?))
+                .map_err(|_| {
+                    PyValueError::new_err(format!(
+                        "Item {index} is neither a MolecularGraph nor has a graph attribute"
+                    ))
+                })?,
+        };
+        let feats: PyReadonlyArray2<f32> = featurize.bind(py).call1((&graph,))?.extract()?;
+        let feats = feats.as_array().to_owned();
+        Ok((graph.borrow().clone(), feats))
+    }
+
+    /// Pulls up to `chunk_size` items from the source and evaluates them in the
+    /// background.
+    ///
+    /// An item that cannot be evaluated stops the chunk early: the items before
+    /// it are still submitted, and the error is raised with the bad item
+    /// already counted, so iteration can resume after it at the right index.
+    fn submit_chunk(&mut self, py: Python<'_>) -> PyResult<()> {
+        let start = self.pulled;
+        let mut graphs = Vec::with_capacity(self.chunk_size);
+        let mut features = Vec::with_capacity(self.chunk_size);
+        let mut source = self.source.bind(py).clone();
+        let mut failure = None;
+        while graphs.len() < self.chunk_size {
+            let item = match source.next() {
+                None => {
+                    self.exhausted = true;
+                    break;
+                }
+                Some(Err(err)) => {
+                    failure = Some(err);
+                    break;
+                }
+                Some(Ok(item)) => item,
+            };
+            let index = self.pulled;
+            self.pulled += 1;
+            let checked = self
+                .extract_item(py, &item, index)
+                .and_then(|(graph, feats)| {
+                    check_feature_shape(&feats.view(), graph.positions.len(), &self.model)
+                        .map_err(|err| PyValueError::new_err(format!("Item {index}: {err}")))?;
+                    Ok((graph, feats))
+                });
+            match checked {
+                Ok((graph, feats)) => {
+                    graphs.push(graph);
+                    features.push(feats);
+                }
+                Err(err) => {
+                    failure = Some(err);
+                    break;
+                }
+            }
+        }
+        if !graphs.is_empty() {
+            self.spawn_chunk(start, graphs, features);
+        }
+        failure.map_or(Ok(()), Err)
+    }
+
+    /// Evaluates `graphs` on the thread pool, sending their outputs tagged with
+    /// the stream index `start` of the first.
+    fn spawn_chunk(
+        &mut self,
+        start: usize,
+        graphs: Vec<MolecularGraph>,
+        features: Vec<ndarray::Array2<f32>>,
+    ) {
+        let model = Arc::clone(&self.model);
+        let scheduler = self.scheduler.clone();
+        let sender = self.sender.clone();
+        let (cutoff, num_offsets) = (self.cutoff, self.num_offsets);
+        rayon::spawn(move || {
+            let outputs = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                let views: Vec<_> = features.iter().map(|f| f.view()).collect();
+                let cutoffs = vec![cutoff; graphs.len()];
+                let num_offsets = vec![num_offsets; graphs.len()];
+                scheduler.execute(&graphs, &model, &views, &cutoffs, &num_offsets)
+            }));
+            // The stream may have been dropped mid-iteration
+            let _ = sender.send((start, outputs.ok()));
+        });
+        self.in_flight += 1;
+    }
+}
@@ -0,0 +1,334 @@
+use crate::graph::EV_PER_A3_IN_BAR;
+use crate::md::{standard_normal, MolecularDynamics, BOLTZMANN_EV, FS_PER_TIME_UNIT};
+use nalgebra::{Matrix3, Vector3};
+use pyo3::prelude::*;
+use rand::rngs::StdRng;
+
+#[derive(Clone, Copy)]
+enum ThermostatKind {
+    /// Friction in inverse natural time units.
+    Langevin {
+        friction: f64,
+    },
+    /// Relaxation times in natural time units.
+    Berendsen {
+        tau: f64,
+    },
+    Bussi {
+        tau: f64,
+    },
+    NoseHoover {
+        tau: f64,
+        chain_length: usize,
+    },
+}
+
+/// Temperature control for `MolecularDynamics`.
+///
+/// Temperatures are in K, relaxation times in fs and friction in 1/fs.
+#[pyclass]
+#[derive(Clone)]
+pub struct Thermostat {
+    kind: ThermostatKind,
+    temperature: f64,
+}
+
+#[pymethods]
+impl Thermostat {
+    /// Langevin dynamics, applied as an Ornstein-Uhlenbeck half step on each
+    /// side of the velocity Verlet step.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (temperature, friction=0.01))]
+    pub fn langevin(temperature: f64, friction: f64) -> Self {
+        Thermostat {
+            kind: ThermostatKind::Langevin {
+                friction: friction * FS_PER_TIME_UNIT,
+            },
+            temperature,
+        }
+    }
+
+    /// Berendsen weak coupling; rescales velocities towards `temperature` with
+    /// relaxation time `tau`. Does not sample the canonical ensemble.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (temperature, tau=100.0))]
+    pub fn berendsen(temperature: f64, tau: f64) -> Self {
+        Thermostat {
+            kind: ThermostatKind::Berendsen {
+                tau: tau / FS_PER_TIME_UNIT,
+            },
+            temperature,
+        }
+    }
+
+    /// Canonical stochastic velocity rescaling (Bussi, Donadio and Parrinello).
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (temperature, tau=100.0))]
+    pub fn bussi(temperature: f64, tau: f64) -> Self {
+        Thermostat {
+            kind: ThermostatKind::Bussi {
+                tau: tau / FS_PER_TIME_UNIT,
+            },
+            temperature,
+        }
+    }
+
+    /// Nosé-Hoover chain of `chain_length` thermostats with period `tau`,
+    /// integrated with the Martyna-Tuckerman-Klein splitting.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (temperature, tau=100.0, chain_length=3))]
+    pub fn nose_hoover(temperature: f64, tau: f64, chain_length: usize) -> Self {
+        Thermostat {
+            kind: ThermostatKind::NoseHoover {
+                tau: tau / FS_PER_TIME_UNIT,
+                chain_length: chain_length.max(1),
+            },
+            temperature,
+        }
+    }
+
+    /// Target temperature in K.
+    #[getter]
+    #[must_use]
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+}
+
+impl Thermostat {
+    /// Whether the thermostat acts on the centre-of-mass motion too.
+    pub(crate) fn couples_center_of_mass(&self) -> bool {
+        matches!(self.kind, ThermostatKind::Langevin { .. })
+    }
+
+    /// Fresh chain state for a Nosé-Hoover thermostat, empty otherwise.
+    pub(crate) fn initial_chain(&self) -> NoseHooverChain {
+        let length = match self.kind {
+            ThermostatKind::NoseHoover { chain_length, .. } => chain_length,
+            _ => 0,
+        };
+        NoseHooverChain {
+            positions: vec![0.0; length],
+            velocities: vec![0.0; length],
+        }
+    }
+}
+
+/// Positions and velocities of the Nosé-Hoover chain variables.
+#[derive(Clone, Default)]
+pub struct NoseHooverChain {
+    positions: Vec<f64>,
+    velocities: Vec<f64>,
+}
+
+/// Pressure control for periodic `MolecularDynamics`.
+#[pyclass]
+#[derive(Clone)]
+pub struct Barostat {
+    /// In eV/A^3.
+    pressure: f64,
+    /// In natural time units.
+    tau: f64,
+    /// In A^3/eV.
+    compressibility: f64,
+}
+
+#[pymethods]
+impl Barostat {
+    /// Isotropic Berendsen coupling towards `pressure` (bar) with relaxation
+    /// time `tau` (fs) and isothermal `compressibility` (1/bar).
+    ///
+    /// Only periodic directions of the cell are scaled.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (pressure=1.01325, tau=1000.0, compressibility=4.57e-5))]
+    pub fn berendsen(pressure: f64, tau: f64, compressibility: f64) -> Self {
+        Barostat {
+            pressure: pressure / EV_PER_A3_IN_BAR,
+            tau: tau / FS_PER_TIME_UNIT,
+            compressibility: compressibility * EV_PER_A3_IN_BAR,
+        }
+    }
+
+    /// Target pressure in bar.
+    #[getter]
+    #[must_use]
+    pub fn pressure(&self) -> f64 {
+        self.pressure * EV_PER_A3_IN_BAR
+    }
+}
+
+impl MolecularDynamics {
+    /// Thermostat update before the first half kick of a step.
+    pub(crate) fn thermostat_pre_step(&mut self) {
+        let Some(thermostat) = self.thermostat.clone() else {
+            return;
+        };
+        let before = self.kinetic_energy();
+        match thermostat.kind {
+            ThermostatKind::Langevin { friction } => {
+                self.langevin_half_step(friction, thermostat.temperature);
+            }
+            ThermostatKind::NoseHoover { tau, .. } => {
+                self.chain_half_step(tau, thermostat.temperature);
+            }
+            ThermostatKind::Berendsen { .. } | ThermostatKind::Bussi { .. } => {}
+        }
+        self.thermostat_energy += before - self.kinetic_energy();
+    }
+
+    /// Thermostat update after the second half kick of a step.
+    pub(crate) fn thermostat_post_step(&mut self) {
+        let Some(thermostat) = self.thermostat.clone() else {
+            return;
+        };
+        let before = self.kinetic_energy();
+        let target = thermostat.temperature;
+        match thermostat.kind {
+            ThermostatKind::Langevin { friction } => self.langevin_half_step(friction, target),
+            ThermostatKind::NoseHoover { tau, .. } => self.chain_half_step(tau, target),
+            ThermostatKind::Berendsen { tau } => {
+                let current = self.temperature();
+                if current > 0.0 {
+                    let lambda = (1.0 + self.timestep / tau * (target / current - 1.0))
+                        .max(0.0)
+                        .sqrt()
+                        .clamp(0.9, 1.1);
+                    self.scale_velocities(lambda);
+                }
+            }
+            ThermostatKind::Bussi { tau } => {
+                let kinetic = self.kinetic_energy();
+                if kinetic > 0.0 {
+                    let alpha = self.bussi_scale(tau, target, kinetic);
+                    self.scale_velocities(alpha);
+                }
+            }
+        }
+        self.thermostat_energy += before - self.kinetic_energy();
+    }
+
+    /// `v <- c v + sqrt((1 - c^2) kT / m) xi` over half a timestep.
+    fn langevin_half_step(&mut self, friction: f64, temperature: f64) {
+        let c = (-0.5 * friction * self.timestep).exp();
+        let noise = (1.0 - c * c).sqrt();
+        for (v, m) in self.velocities.iter_mut().zip(&self.masses) {
+            let sigma = noise * (BOLTZMANN_EV * temperature / m).sqrt();
+            let xi = Vector3::new(
+                standard_normal(&mut self.rng),
+                standard_normal(&mut self.rng),
+                standard_normal(&mut self.rng),
+            );
+            *v = *v * c + xi * sigma;
+        }
+    }
+
+    /// Scaling factor that draws the new kinetic energy from the canonical distribution.
+    fn bussi_scale(&mut self, tau: f64, temperature: f64, kinetic: f64) -> f64 {
+        let dof = self.degrees_of_freedom();
+        let target = 0.5 * dof * BOLTZMANN_EV * temperature;
+        let c = (-self.timestep / tau).exp();
+        let ratio = target / (dof * kinetic);
+        let r1 = standard_normal(&mut self.rng);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let rest = sum_of_squared_normals(dof.round() as usize - 1, &mut self.rng);
+        let alpha2 =
+            c + (1.0 - c) * ratio * (r1 * r1 + rest) + 2.0 * r1 * (c * (1.0 - c) * ratio).sqrt();
+        alpha2.max(0.0).sqrt()
+    }
+
+    /// Half-step Trotter update of the Nosé-Hoover chain and the particle velocities.
+    fn chain_half_step(&mut self, tau: f64, temperature: f64) {
+        let kt = BOLTZMANN_EV * temperature;
+        let dof = self.degrees_of_freedom();
+        let dt = self.timestep;
+        let length = self.chain.velocities.len();
+        let masses: Vec<f64> = (0..length)
+            .map(|j| if j == 0 { dof * kt } else { kt } * tau * tau)
+            .collect();
+
+        let mut kinetic2 = 2.0 * self.kinetic_energy();
+        let force = |chain: &NoseHooverChain, j: usize, kinetic2: f64| {
+            if j == 0 {
+                (kinetic2 - dof * kt) / masses[0]
+            } else {
+                (masses[j - 1] * chain.velocities[j - 1].powi(2) - kt) / masses[j]
+            }
+        };
+        let update = |chain: &mut NoseHooverChain, j: usize, kinetic2: f64| {
+            let g = force(chain, j, kinetic2);
+            if j + 1 < length {
+                let damp = (-chain.velocities[j + 1] * dt / 8.0).exp();
+                chain.velocities[j] = (chain.velocities[j] * damp + g * dt / 4.0) * damp;
+            } else {
+                chain.velocities[j] += g * dt / 4.0;
+            }
+        };
+
+        for j in (0..length).rev() {
+            update(&mut self.chain, j, kinetic2);
+        }
+        let scale = (-self.chain.velocities[0] * dt / 2.0).exp();
+        self.scale_velocities(scale);
+        kinetic2 *= scale * scale;
+        for (xi, v) in self.chain.positions.iter_mut().zip(&self.chain.velocities) {
+            *xi += v * dt / 2.0;
+        }
+        for j in 0..length {
+            update(&mut self.chain, j, kinetic2);
+        }
+    }
+
+    fn scale_velocities(&mut self, factor: f64) {
+        for v in &mut self.velocities {
+            *v *= factor;
+        }
+    }
+
+    /// Berendsen rescaling of the periodic cell directions and the positions.
+    pub(crate) fn barostat_step(&mut self) {
+        let Some(barostat) = self.barostat.clone() else {
+            return;
+        };
+        let (Some(cell), Some(pressure)) = (self.graph.periodic_cell(), self.pressure_internal())
+        else {
+            return;
+        };
+        let mu = (1.0
+            - barostat.compressibility * self.timestep / barostat.tau
+                * (barostat.pressure - pressure))
+            .cbrt();
+
+        let cell = cell.cast::<f64>();
+        let mut scaled = cell;
+        for axis in 0..3 {
+            if self.graph.pbc[axis] {
+                scaled.row_mut(axis).scale_mut(mu);
+            }
+        }
+        // Keep fractional coordinates fixed: r' = C'^T C^-T r
+        let transform = scaled.transpose()
+            * cell
+                .transpose()
+                .try_inverse()
+                .unwrap_or_else(Matrix3::identity);
+        for r in &mut self.positions {
+            *r = transform * *r;
+        }
+        self.graph.cell = Some(scaled.cast::<f32>());
+    }
+
+    /// Instantaneous pressure `(2 K - tr(dE/d eps)) / 3V` in eV/A^3.
+    pub(crate) fn pressure_internal(&self) -> Option<f64> {
+        let volume = f64::from(self.graph.volume()?);
+        Some((2.0 * self.kinetic_energy() - self.strain.trace()) / (3.0 * volume))
+    }
+}
+
+fn sum_of_squared_normals(count: usize, rng: &mut StdRng) -> f64 {
+    (0..count).map(|_| standard_normal(rng).powi(2)).sum()
+}
@@ -0,0 +1,655 @@
+use crate::batch::MolecularBatch;
+use crate::graph::{readout_vector, MolecularGraph};
+use crate::model::GNNModel;
+use nalgebra::{DMatrix, DVector};
+use numpy::{ndarray, PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use std::ops::Add;
+
+#[derive(Clone, Copy)]
+enum OptimizerKind {
+    Sgd { momentum: f32 },
+    Adam { beta1: f32, beta2: f32, eps: f32 },
+    AdamW { beta1: f32, beta2: f32, eps: f32 },
+}
+
+/// First-order optimizer for the model weights.
+#[pyclass]
+#[derive(Clone)]
+pub struct Optimizer {
+    kind: OptimizerKind,
+    lr: f32,
+    weight_decay: f32,
+}
+
+#[pymethods]
+impl Optimizer {
+    /// Stochastic gradient descent with optional momentum and L2 weight decay.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (lr, momentum=0.0, weight_decay=0.0))]
+    pub fn sgd(lr: f32, momentum: f32, weight_decay: f32) -> Self {
+        Optimizer {
+            kind: OptimizerKind::Sgd { momentum },
+            lr,
+            weight_decay,
+        }
+    }
+
+    /// Adam with L2 weight decay folded into the gradient.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (lr=1e-3, beta1=0.9, beta2=0.999, eps=1e-8, weight_decay=0.0))]
+    pub fn adam(lr: f32, beta1: f32, beta2: f32, eps: f32, weight_decay: f32) -> Self {
+        Optimizer {
+            kind: OptimizerKind::Adam { beta1, beta2, eps },
+            lr,
+            weight_decay,
+        }
+    }
+
+    /// Adam with decoupled weight decay.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (lr=1e-3, beta1=0.9, beta2=0.999, eps=1e-8, weight_decay=1e-2))]
+    pub fn adamw(lr: f32, beta1: f32, beta2: f32, eps: f32, weight_decay: f32) -> Self {
+        Optimizer {
+            kind: OptimizerKind::AdamW { beta1, beta2, eps },
+            lr,
+            weight_decay,
+        }
+    }
+}
+
+/// Moment estimates carried between optimizer steps.
+struct OptimizerState {
+    first: DMatrix<f32>,
+    second: DMatrix<f32>,
+    step: i32,
+}
+
+impl Optimizer {
+    fn step(
+        &self,
+        lr: f32,
+        weights: &mut DMatrix<f32>,
+        grad: &DMatrix<f32>,
+        state: &mut OptimizerState,
+    ) {
+        state.step += 1;
+        match self.kind {
+            OptimizerKind::Sgd { momentum } => {
+                let g = grad + &*weights * self.weight_decay;
+                state.first = &state.first * momentum + g;
+                *weights -= &state.first * lr;
+            }
+            OptimizerKind::Adam { beta1, beta2, eps } => {
+                let g = grad + &*weights * self.weight_decay;
+                adam_update(lr, beta1, beta2, eps, weights, &g, state);
+            }
+            OptimizerKind::AdamW { beta1, beta2, eps } => {
+                *weights *= 1.0 - lr * self.weight_decay;
+                adam_update(lr, beta1, beta2, eps, weights, grad, state);
+            }
+        }
+    }
+}
+
+fn adam_update(
+    lr: f32,
+    beta1: f32,
+    beta2: f32,
+    eps: f32,
+    weights: &mut DMatrix<f32>,
+    grad: &DMatrix<f32>,
+    state: &mut OptimizerState,
+) {
+    state.first = &state.first * beta1 + grad * (1.0 - beta1);
+    state.second = &state.second * beta2 + grad.component_mul(grad) * (1.0 - beta2);
+    let first_correction = 1.0 - beta1.powi(state.step);
+    let second_correction = 1.0 - beta2.powi(state.step);
+    weights.zip_zip_apply(&state.first, &state.second, |w, m, v| {
+        *w -= lr * (m / first_correction) / ((v / second_correction).sqrt() + eps);
+    });
+}
+
+#[derive(Clone, Copy)]
+enum ScheduleKind {
+    Constant,
+    Step { step_size: usize, gamma: f32 },
+    Exponential { gamma: f32 },
+    Cosine { total_epochs: usize, min_lr: f32 },
+}
+
+/// Per-epoch learning-rate schedule applied on top of the optimizer's base rate.
+#[pyclass]
+#[derive(Clone)]
+pub struct LRSchedule {
+    kind: ScheduleKind,
+}
+
+#[pymethods]
+impl LRSchedule {
+    #[staticmethod]
+    #[must_use]
+    pub fn constant() -> Self {
+        LRSchedule {
+            kind: ScheduleKind::Constant,
+        }
+    }
+
+    /// Multiplies the rate by `gamma` every `step_size` epochs.
+    #[staticmethod]
+    #[must_use]
+    pub fn step(step_size: usize, gamma: f32) -> Self {
+        LRSchedule {
+            kind: ScheduleKind::Step {
+                step_size: step_size.max(1),
+                gamma,
+            },
+        }
+    }
+
+    /// Multiplies the rate by `gamma` every epoch.
+    #[staticmethod]
+    #[must_use]
+    pub fn exponential(gamma: f32) -> Self {
+        LRSchedule {
+            kind: ScheduleKind::Exponential { gamma },
+        }
+    }
+
+    /// Cosine annealing from the base rate down to `min_lr` over `total_epochs`.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (total_epochs, min_lr=0.0))]
+    pub fn cosine(total_epochs: usize, min_lr: f32) -> Self {
+        LRSchedule {
+            kind: ScheduleKind::Cosine {
+                total_epochs: total_epochs.max(1),
+                min_lr,
+            },
+        }
+    }
+}
+
+impl LRSchedule {
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap
+    )]
+    fn learning_rate(&self, base_lr: f32, epoch: usize) -> f32 {
+        match self.kind {
+            ScheduleKind::Constant => base_lr,
+            ScheduleKind::Step { step_size, gamma } => {
+                base_lr * gamma.powi((epoch / step_size) as i32)
+            }
+            ScheduleKind::Exponential { gamma } => base_lr * gamma.powi(epoch as i32),
+            ScheduleKind::Cosine {
+                total_epochs,
+                min_lr,
+            } => {
+                let progress = (epoch.min(total_epochs) as f32) / (total_epochs as f32);
+                min_lr + 0.5 * (base_lr - min_lr) * (1.0 + (std::f32::consts::PI * progress).cos())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum LossKind {
+    Mse,
+    Mae,
+    Huber {
+        delta: f32,
+    },
+    EnergyForce {
+        energy_weight: f32,
+        force_weight: f32,
+    },
+}
+
+/// Training objective.
+///
+/// `mse`, `mae` and `huber` compare per-atom model outputs with per-atom targets.
+/// `energy_force` compares the readout energy (and optionally forces) with
+/// per-graph reference energies and per-atom reference forces.
+#[pyclass]
+#[derive(Clone)]
+pub struct Loss {
+    kind: LossKind,
+}
+
+#[pymethods]
+impl Loss {
+    #[staticmethod]
+    #[must_use]
+    pub fn mse() -> Self {
+        Loss {
+            kind: LossKind::Mse,
+        }
+    }
+
+    #[staticmethod]
+    #[must_use]
+    pub fn mae() -> Self {
+        Loss {
+            kind: LossKind::Mae,
+        }
+    }
+
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (delta=1.0))]
+    pub fn huber(delta: f32) -> Self {
+        Loss {
+            kind: LossKind::Huber { delta },
+        }
+    }
+
+    /// `energy_weight * MSE(E) + force_weight * MSE(F)`.
+    #[staticmethod]
+    #[must_use]
+    #[pyo3(signature = (energy_weight=1.0, force_weight=1.0))]
+    pub fn energy_force(energy_weight: f32, force_weight: f32) -> Self {
+        Loss {
+            kind: LossKind::EnergyForce {
+                energy_weight,
+                force_weight,
+            },
+        }
+    }
+}
+
+impl Loss {
+    /// Value and derivative of an elementwise loss at `residual = prediction - target`.
+    fn elementwise(&self, residual: f32) -> (f32, f32) {
+        match self.kind {
+            LossKind::Mae => (residual.abs(), residual.signum()),
+            LossKind::Huber { delta } if residual.abs() > delta => (
+                delta * (residual.abs() - 0.5 * delta),
+                delta * residual.signum(),
+            ),
+            LossKind::Huber { .. } => (0.5 * residual * residual, residual),
+            LossKind::Mse | LossKind::EnergyForce { .. } => (residual * residual, 2.0 * residual),
+        }
+    }
+}
+
+/// Owned supervision signal, matched to the loss kind.
+enum Targets {
+    PerAtom(Vec<ndarray::Array2<f32>>),
+    EnergyForce {
+        energies: Vec<f32>,
+        forces: Option<Vec<ndarray::Array2<f32>>>,
+    },
+}
+
+/// Unnormalized loss sums and gradients for one or more graphs.
+///
+/// The primary term is the per-atom output loss or the energy loss; the
+/// secondary term is the force loss. Dividing by the counts gives means.
+struct LossTerms {
+    primary: f64,
+    primary_abs: f64,
+    primary_count: usize,
+    primary_grad: DMatrix<f32>,
+    secondary: f64,
+    secondary_abs: f64,
+    secondary_count: usize,
+    secondary_grad: DMatrix<f32>,
+}
+
+impl LossTerms {
+    fn zeros(rows: usize, cols: usize) -> Self {
+        LossTerms {
+            primary: 0.0,
+            primary_abs: 0.0,
+            primary_count: 0,
+            primary_grad: DMatrix::zeros(rows, cols),
+            secondary: 0.0,
+            secondary_abs: 0.0,
+            secondary_count: 0,
+            secondary_grad: DMatrix::zeros(rows, cols),
+        }
+    }
+
+    fn weights(loss: &Loss) -> (f32, f32) {
+        match loss.kind {
+            LossKind::EnergyForce {
+                energy_weight,
+                force_weight,
+            } => (energy_weight, force_weight),
+            _ => (1.0, 0.0),
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn mean_loss(&self, loss: &Loss) -> f64 {
+        let (w1, w2) = Self::weights(loss);
+        f64::from(w1) * self.primary / (self.primary_count.max(1) as f64)
+            + f64::from(w2) * self.secondary / (self.secondary_count.max(1) as f64)
+    }
+
+    /// Epoch summary handed to the Python callback.
+    #[allow(clippy::cast_precision_loss)]
+    fn metrics<'py>(
+        &self,
+        py: Python<'py>,
+        loss: &Loss,
+        targets: &Targets,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let metrics = PyDict::new(py);
+        metrics.set_item("loss", self.mean_loss(loss))?;
+        let primary_mae = self.primary_abs / self.primary_count.max(1) as f64;
+        match targets {
+            Targets::PerAtom(_) => metrics.set_item("mae", primary_mae)?,
+            Targets::EnergyForce { forces, .. } => {
+                metrics.set_item("energy_mae", primary_mae)?;
+                if forces.is_some() {
+                    let force_mae = self.secondary_abs / self.secondary_count.max(1) as f64;
+                    metrics.set_item("force_mae", force_mae)?;
+                }
+            }
+        }
+        Ok(metrics)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn mean_gradient(&self, loss: &Loss) -> DMatrix<f32> {
+        let (w1, w2) = Self::weights(loss);
+        &self.primary_grad * (w1 / self.primary_count.max(1) as f32)
+            + &self.secondary_grad * (w2 / self.secondary_count.max(1) as f32)
+    }
+}
+
+impl Add for LossTerms {
+    type Output = LossTerms;
+
+    fn add(self, other: LossTerms) -> LossTerms {
+        LossTerms {
+            primary: self.primary + other.primary,
+            primary_abs: self.primary_abs + other.primary_abs,
+            primary_count: self.primary_count + other.primary_count,
+            primary_grad: self.primary_grad + other.primary_grad,
+            secondary: self.secondary + other.secondary,
+            secondary_abs: self.secondary_abs + other.secondary_abs,
+            secondary_count: self.secondary_count + other.secondary_count,
+            secondary_grad: self.secondary_grad + other.secondary_grad,
+        }
+    }
+}
+
+/// Static configuration of a single `fit` call.
+struct FitContext<'a> {
+    weights: &'a DMatrix<f32>,
+    readout: &'a DVector<f32>,
+    cutoff: f32,
+    num_offsets: usize,
+}
+
+impl MolecularGraph {
+    /// Loss terms and weight gradients for one graph against per-atom targets.
+    fn per_atom_terms(
+        &self,
+        ctx: &FitContext,
+        loss: &Loss,
+        atom_view: &ndarray::ArrayView2<f32>,
+        target: &ndarray::Array2<f32>,
+    ) -> LossTerms {
+        let (rows, cols) = ctx.weights.shape();
+        let mut terms = LossTerms::zeros(rows, cols);
+        let aggregated = self.compute_core_fused(ctx.cutoff, ctx.num_offsets, atom_view);
+        for (i, agg) in aggregated.iter().enumerate() {
+            let prediction = ctx.weights * agg;
+            let mut upstream = DVector::zeros(rows);
+            for o in 0..rows {
+                let residual = prediction[o] - target[[i, o]];
+                let (value, derivative) = loss.elementwise(residual);
+                terms.primary += f64::from(value);
+                terms.primary_abs += f64::from(residual.abs());
+                upstream[o] = derivative;
+            }
+            terms.primary_grad.ger(1.0, &upstream, agg, 1.0);
+        }
+        terms.primary_count = aggregated.len() * rows;
+        terms
+    }
+
+    /// Loss terms and weight gradients for one graph against a reference energy and forces.
+    fn energy_force_terms(
+        &self,
+        ctx: &FitContext,
+        atom_view: &ndarray::ArrayView2<f32>,
+        energy: f32,
+        forces: Option<&ndarray::Array2<f32>>,
+    ) -> LossTerms {
+        let (rows, cols) = ctx.weights.shape();
+        let mut terms = LossTerms::zeros(rows, cols);
+
+        // E = r^T W A, so dE/dW = r A^T
+        let total = self
+            .compute_core_fused(ctx.cutoff, ctx.num_offsets, atom_view)
+            .iter()
+            .fold(DVector::zeros(cols), |acc, agg| acc + agg);
+        let residual = ctx.readout.dot(&(ctx.weights * &total)) - energy;
+        terms.primary = f64::from(residual * residual);
+        terms.primary_abs = f64::from(residual.abs());
+        terms.primary_count = 1;
+        terms
+            .primary_grad
+            .ger(2.0 * residual, ctx.readout, &total, 0.0);
+
+        // F_k = -sum_b h_b dA_b/dR_k with h = W^T r, so dL/dW = r (dL/dh)^T
+        if let Some(reference) = forces {
+            let jacobian = self.aggregate_jacobian_internal(atom_view, ctx.cutoff, ctx.num_offsets);
+            let sensitivity = ctx.weights.tr_mul(ctx.readout);
+            let mut grad_h = DVector::<f32>::zeros(cols);
+            for (k, columns) in jacobian.iter().enumerate() {
+                for axis in 0..3 {
+                    let force: f32 = -columns
+                        .iter()
+                        .zip(sensitivity.iter())
+                        .map(|(column, h)| h * column[axis])
+                        .sum::<f32>();
+                    let residual = force - reference[[k, axis]];
+                    terms.secondary += f64::from(residual * residual);
+                    terms.secondary_abs += f64::from(residual.abs());
+                    for (b, column) in columns.iter().enumerate() {
+                        grad_h[b] -= 2.0 * residual * column[axis];
+                    }
+                }
+            }
+            terms.secondary_count = 3 * jacobian.len();
+            terms.secondary_grad.ger(1.0, ctx.readout, &grad_h, 0.0);
+        }
+        terms
+    }
+}
+
+/// Epoch loop that fits a `GNNModel` to the graphs of a `MolecularBatch`.
+#[pyclass]
+pub struct Trainer {
+    optimizer: Optimizer,
+    loss: Loss,
+    schedule: LRSchedule,
+    batch_size: usize,
+    seed: u64,
+}
+
+#[pymethods]
+impl Trainer {
+    #[new]
+    #[must_use]
+    #[pyo3(signature = (optimizer, loss, schedule=None, batch_size=32, seed=0))]
+    pub fn new(
+        optimizer: Optimizer,
+        loss: Loss,
+        schedule: Option<LRSchedule>,
+        batch_size: usize,
+        seed: u64,
+    ) -> Self {
+        Trainer {
+            optimizer,
+            loss,
+            schedule: schedule.unwrap_or_else(LRSchedule::constant),
+            batch_size: batch_size.max(1),
+            seed,
+        }
+    }
+
+    /// Trains `model` in place for `epochs` passes over `batch`.
+    ///
+    /// Per-atom losses need `targets` (one `(n_atoms, n_outputs)` array per graph);
+    /// `energy_force` needs `energies` and optionally `forces` (one `(n_atoms, 3)`
+    /// array per graph). After every epoch `callback(epoch, metrics)` is called
+    /// with a dict of metrics, and the list of all metric dicts is returned.
+    ///
+    /// # Errors
+    /// Returns an error if the supplied targets do not match the loss or the batch,
+    /// or if the callback raises.
+    #[allow(clippy::too_many_arguments, clippy::needless_pass_by_value)]
+    #[pyo3(signature = (model, batch, all_atom_features, epochs, cutoff, num_offsets, targets=None, energies=None, forces=None, readout=None, callback=None))]
+    pub fn fit<'py>(
+        &self,
+        py: Python<'py>,
+        model: &Bound<'py, GNNModel>,
+        batch: &MolecularBatch,
+        all_atom_features: Vec<PyReadonlyArray2<f32>>,
+        epochs: usize,
+        cutoff: f32,
+        num_offsets: usize,
+        targets: Option<Vec<PyReadonlyArray2<f32>>>,
+        energies: Option<Vec<f32>>,
+        forces: Option<Vec<PyReadonlyArray2<f32>>>,
+        readout: Option<PyReadonlyArray1<f32>>,
+        callback: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let mut weights = model.borrow().weights.clone();
+        let (rows, cols) = weights.shape();
+        let num_graphs = batch.graphs.len();
+
+        let features = owned_per_graph(&all_atom_features, &batch.graphs, cols, "Feature")?;
+        let targets = match (self.loss.kind, targets, energies) {
+            (LossKind::EnergyForce { .. }, _, Some(energies)) => {
+                if energies.len() != num_graphs {
+                    return Err(PyValueError::new_err(format!(
+                        "Got {} energies for {num_graphs} graphs",
+                        energies.len()
+                    )));
+                }
+                let forces = forces
+                    .map(|f| owned_per_graph(&f, &batch.graphs, 3, "Force"))
+                    .transpose()?;
+                Targets::EnergyForce { energies, forces }
+            }
+            (LossKind::EnergyForce { .. }, _, None) => {
+                return Err(PyValueError::new_err(
+                    "The energy_force loss requires `energies`",
+                ))
+            }
+            (_, Some(targets), _) => {
+                Targets::PerAtom(owned_per_graph(&targets, &batch.graphs, rows, "Target")?)
+            }
+            (_, None, _) => return Err(PyValueError::new_err("Per-atom losses require `targets`")),
+        };
+
+        let readout = readout_vector(readout.as_ref(), &model.borrow())?;
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut order: Vec<usize> = (0..num_graphs).collect();
+        let mut state = OptimizerState {
+            first: DMatrix::zeros(rows, cols),
+            second: DMatrix::zeros(rows, cols),
+            step: 0,
+        };
+        let mut history = Vec::with_capacity(epochs);
+
+        for epoch in 0..epochs {
+            let lr = self.schedule.learning_rate(self.optimizer.lr, epoch);
+            order.shuffle(&mut rng);
+            let mut epoch_terms = LossTerms::zeros(rows, cols);
+
+            for minibatch in order.chunks(self.batch_size) {
+                let ctx = FitContext {
+                    weights: &weights,
+                    readout: &readout,
+                    cutoff,
+                    num_offsets,
+                };
+                let terms = minibatch
+                    .par_iter()
+                    .map(|&idx| {
+                        let graph = &batch.graphs[idx];
+                        let atom_view = features[idx].view();
+                        match &targets {
+                            Targets::PerAtom(t) => {
+                                graph.per_atom_terms(&ctx, &self.loss, &atom_view, &t[idx])
+                            }
+                            Targets::EnergyForce { energies, forces } => graph.energy_force_terms(
+                                &ctx,
+                                &atom_view,
+                                energies[idx],
+                                forces.as_ref().map(|f| &f[idx]),
+                            ),
+                        }
+                    })
+                    .reduce(|| LossTerms::zeros(rows, cols), |a, b| a + b);
+
+                let grad = terms.mean_gradient(&self.loss);
+                self.optimizer.step(lr, &mut weights, &grad, &mut state);
+                epoch_terms = epoch_terms + terms;
+            }
+            model.borrow_mut().weights.clone_from(&weights);
+
+            let metrics = epoch_terms.metrics(py, &self.loss, &targets)?;
+            metrics.set_item("epoch", epoch)?;
+            metrics.set_item("lr", lr)?;
+            if let Some(cb) = &callback {
+                cb.call1((epoch, &metrics))?;
+            }
+            history.push(metrics);
+        }
+        Ok(history)
+    }
+}
+
+/// Copies one `(n_atoms, width)` array per graph, checking the count and shapes.
+fn owned_per_graph(
+    arrays: &[PyReadonlyArray2<f32>],
+    graphs: &[MolecularGraph],
+    width: usize,
+    name: &str,
+) -> PyResult<Vec<ndarray::Array2<f32>>> {
+    if arrays.len() != graphs.len() {
+        return Err(PyValueError::new_err(format!(
+            "Got {} {} arrays for {} graphs",
+            arrays.len(),
+            name.to_lowercase(),
+            graphs.len()
+        )));
+    }
+    arrays
+        .iter()
+        .zip(graphs)
+        .enumerate()
+        .map(|(idx, (array, graph))| {
+            let array = array.as_array();
+            if array.shape() != [graph.positions.len(), width] {
+                return Err(PyValueError::new_err(format!(
+                    "{name} array {idx} has shape {:?}, expected ({}, {width})",
+                    array.shape(),
+                    graph.positions.len()
+                )));
+            }
+            Ok(array.to_owned())
+        })
+        .collect()
+}
@@ -0,0 +1,318 @@
+use crate::graph::{
+    cell_from_array, check_feature_shape, check_periodicity, MolecularGraph, RadialBasis,
+};
+use crate::model::GNNModel;
+use crate::neighbors::VerletList;
+use nalgebra::{DVector, DVectorViewMut, Matrix3, Vector3};
+use numpy::ndarray;
+use numpy::{PyArray2, PyArray3, PyReadonlyArray2, PyReadonlyArray3};
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// Frames that share one topology: the atoms, cell and periodicity are
+/// stored once and only the positions change from frame to frame.
+#[pyclass]
+#[derive(Clone)]
+pub struct Trajectory {
+    #[pyo3(get)]
+    pub atomic_numbers: Vec<i32>,
+    /// Positions of every frame back to back, `n_atoms` per frame.
+    pub positions: Vec<Vector3<f32>>,
+    /// Lattice vectors as rows; `None` for isolated molecules.
+    pub cell: Option<Matrix3<f32>>,
+    pub pbc: [bool; 3],
+}
+
+#[pymethods]
+impl Trajectory {
+    #[new]
+    /// Creates a trajectory from `(n_frames, n_atoms, 3)` positions.
+    ///
+    /// `cell` and `pbc` apply to every frame and default as for
+    /// `MolecularGraph`.
+    ///
+    /// # Errors
+    /// Returns an error if the positions do not match the atoms or the
+    /// periodic cell is missing or degenerate.
+    #[pyo3(signature = (atomic_numbers, positions, cell=None, pbc=None))]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn new(
+        atomic_numbers: Vec<i32>,
+        positions: PyReadonlyArray3<f32>,
+        cell: Option<PyReadonlyArray2<f32>>,
+        pbc: Option<[bool; 3]>,
+    ) -> PyResult<Self> {
+        let view = positions.as_array();
+        if view.shape()[1..] != [atomic_numbers.len(), 3] {
+            return Err(PyValueError::new_err(format!(
+                "Expected positions of shape (n_frames, {}, 3), got {:?}",
+                atomic_numbers.len(),
+                view.shape()
+            )));
+        }
+        let positions = view
+            .outer_iter()
+            .flat_map(|frame| {
+                frame
+                    .outer_iter()
+                    .map(|r| Vector3::new(r[0], r[1], r[2]))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let cell = cell.as_ref().map(cell_from_array).transpose()?;
+        let pbc = pbc.unwrap_or([cell.is_some(); 3]);
+        Self::from_parts(atomic_numbers, positions, cell, pbc)
+    }
+
+    /// Stacks `graphs` into a trajectory.
+    ///
+    /// # Errors
+    /// Returns an error if there are no graphs or they differ in atoms, cell
+    /// or periodicity.
+    #[staticmethod]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn from_graphs(graphs: Vec<PyRef<'_, MolecularGraph>>) -> PyResult<Self> {
+        let Some(first) = graphs.first() else {
+            return Err(PyValueError::new_err(
+                "A trajectory needs at least one graph",
+            ));
+        };
+        for (index, graph) in graphs.iter().enumerate() {
+            if graph.atomic_numbers != first.atomic_numbers {
+                return Err(PyValueError::new_err(format!(
+                    "Graph {index} has different atoms than graph 0"
+                )));
+            }
+            if graph.cell != first.cell || graph.pbc != first.pbc {
+                return Err(PyValueError::new_err(format!(
+                    "Graph {index} has a different cell or periodicity than graph 0"
+                )));
+            }
+        }
+        let positions = graphs
+            .iter()
+            .flat_map(|graph| graph.positions.iter().copied())
+            .collect();
+        Self::from_parts(
+            first.atomic_numbers.clone(),
+            positions,
+            first.cell,
+            first.pbc,
+        )
+    }
+
+    /// Positions of every frame as an `(n_frames, n_atoms, 3)` array.
+    #[getter]
+    #[must_use]
+    pub fn positions<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray3<f32>> {
+        let shape = (self.n_frames(), self.n_atoms(), 3);
+        let n = self.n_atoms();
+        let frames =
+            ndarray::Array3::from_shape_fn(shape, |(f, i, k)| self.positions[f * n + i][k]);
+        PyArray3::from_owned_array(py, frames)
+    }
+
+    /// Lattice vectors as rows, or `None` for isolated molecules.
+    #[getter]
+    #[must_use]
+    pub fn cell<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyArray2<f32>>> {
+        self.cell.map(|cell| {
+            let rows = ndarray::Array2::from_shape_fn((3, 3), |(r, c)| cell[(r, c)]);
+            PyArray2::from_owned_array(py, rows)
+        })
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn pbc(&self) -> [bool; 3] {
+        self.pbc
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn n_atoms(&self) -> usize {
+        self.atomic_numbers.len()
+    }
+
+    fn __len__(&self) -> usize {
+        self.n_frames()
+    }
+
+    /// Frame `index` as a graph; negative indices count from the end.
+    fn __getitem__(&self, index: isize) -> PyResult<MolecularGraph> {
+        let n_frames = self.n_frames();
+        let frame = if index < 0 {
+            n_frames.checked_sub(index.unsigned_abs())
+        } else {
+            Some(index.unsigned_abs()).filter(|&f| f < n_frames)
+        };
+        let frame = frame.ok_or_else(|| {
+            PyIndexError::new_err(format!(
+                "Frame index {index} out of range for {n_frames} frames"
+            ))
+        })?;
+        Ok(self.frame(frame))
+    }
+
+    /// Model outputs of every frame as an `(n_frames, n_atoms, n_outputs)` array.
+    ///
+    /// Frames are split into contiguous runs that are evaluated in parallel.
+    /// Within a run, neighbours are searched within `cutoff + skin` and that
+    /// list is reused until an atom has moved more than `skin / 2`, so a
+    /// larger `skin` trades fewer searches for more candidate pairs per frame.
+    /// The outputs do not depend on `skin`.
+    ///
+    /// # Errors
+    /// Returns an error if the features do not match the atoms and model, or
+    /// `cutoff` is not positive or `skin` is negative.
+    #[allow(clippy::needless_pass_by_value)]
+    #[pyo3(signature = (model, atom_features, cutoff, num_offsets, skin=0.5))]
+    pub fn run_inference<'py>(
+        &self,
+        py: Python<'py>,
+        model: &GNNModel,
+        atom_features: PyReadonlyArray2<'py, f32>,
+        cutoff: f32,
+        num_offsets: usize,
+        skin: f32,
+    ) -> PyResult<Bound<'py, PyArray3<f32>>> {
+        let atom_view = atom_features.as_array();
+        check_feature_shape(&atom_view, self.n_atoms(), model).map_err(PyValueError::new_err)?;
+        if !cutoff.is_finite() || cutoff <= 0.0 {
+            return Err(PyValueError::new_err("Cutoff must be positive and finite"));
+        }
+        if !skin.is_finite() || skin < 0.0 {
+            return Err(PyValueError::new_err(
+                "Skin must be non-negative and finite",
+            ));
+        }
+
+        let shape = (self.n_frames(), self.n_atoms(), model.weights.nrows());
+        let mut out = vec![0.0; shape.0 * shape.1 * shape.2];
+        py.detach(|| self.write_frames(model, &atom_view, cutoff, num_offsets, skin, &mut out));
+        let out = ndarray::Array3::from_shape_vec(shape, out)
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(PyArray3::from_owned_array(py, out))
+    }
+}
+
+impl Trajectory {
+    /// Rust-side constructor with the same validation as the Python one.
+    ///
+    /// # Errors
+    /// Returns an error if the positions are not whole frames or the periodic
+    /// cell is missing or degenerate.
+    pub fn from_parts(
+        atomic_numbers: Vec<i32>,
+        positions: Vec<Vector3<f32>>,
+        cell: Option<Matrix3<f32>>,
+        pbc: [bool; 3],
+    ) -> PyResult<Self> {
+        let n = atomic_numbers.len();
+        let whole = if n == 0 {
+            positions.is_empty()
+        } else {
+            positions.len().is_multiple_of(n)
+        };
+        if !whole {
+            return Err(PyValueError::new_err(format!(
+                "Got {} positions, which is not a whole number of frames of {n} atoms",
+                positions.len()
+            )));
+        }
+        check_periodicity(cell.as_ref(), pbc)?;
+        Ok(Trajectory {
+            atomic_numbers,
+            positions,
+            cell,
+            pbc,
+        })
+    }
+
+    #[must_use]
+    pub fn n_frames(&self) -> usize {
+        self.positions
+            .len()
+            .checked_div(self.atomic_numbers.len())
+            .unwrap_or(0)
+    }
+
+    /// Frame `frame` as a graph, which must be in range.
+    #[must_use]
+    pub fn frame(&self, frame: usize) -> MolecularGraph {
+        MolecularGraph {
+            atomic_numbers: self.atomic_numbers.clone(),
+            positions: self.frame_positions(frame).to_vec(),
+            cell: self.cell,
+            pbc: self.pbc,
+        }
+    }
+
+    fn frame_positions(&self, frame: usize) -> &[Vector3<f32>] {
+        let n = self.n_atoms();
+        &self.positions[frame * n..(frame + 1) * n]
+    }
+
+    /// Writes the outputs of every frame into consecutive blocks of `out`,
+    /// one run of frames per task.
+    fn write_frames(
+        &self,
+        model: &GNNModel,
+        atom_view: &ndarray::ArrayView2<f32>,
+        cutoff: f32,
+        num_offsets: usize,
+        skin: f32,
+        out: &mut [f32],
+    ) {
+        let frame_len = self.n_atoms() * model.weights.nrows();
+        if frame_len == 0 || self.n_frames() == 0 {
+            return;
+        }
+        let basis = RadialBasis::new(cutoff, num_offsets);
+        let run = self.n_frames().div_ceil(rayon::current_num_threads());
+        out.par_chunks_mut(run * frame_len)
+            .enumerate()
+            .for_each(|(r, block)| {
+                let mut list: Option<VerletList> = None;
+                for (k, frame_out) in block.chunks_mut(frame_len).enumerate() {
+                    let frame = r * run + k;
+                    let positions = self.frame_positions(frame);
+                    let current = match list.take() {
+                        Some(current) if current.is_valid(positions) => current,
+                        _ => VerletList::build(&self.frame(frame), cutoff, skin),
+                    };
+                    write_frame(positions, &current, &basis, model, atom_view, frame_out);
+                    list = Some(current);
+                }
+            });
+    }
+}
+
+/// Writes the model output of every atom at `positions` into consecutive rows of
+/// `out`, aggregating over the pairs of `list` within the basis cutoff.
+fn write_frame(
+    positions: &[Vector3<f32>],
+    list: &VerletList,
+    basis: &RadialBasis,
+    model: &GNNModel,
+    atom_view: &ndarray::ArrayView2<f32>,
+    out: &mut [f32],
+) {
+    let n_outputs = model.weights.nrows();
+    let num_feats = atom_view.shape()[1];
+    for (i, row) in out.chunks_mut(n_outputs).enumerate() {
+        let mut aggregated = DVector::zeros(num_feats);
+        for (j, shift) in &list.pairs[i] {
+            let dist = f64::from((positions[i] - positions[*j] - shift).norm());
+            if dist <= basis.cutoff {
+                #[allow(clippy::cast_possible_truncation)]
+                let rbf_weight = basis.value(dist) as f32;
+                for f in 0..num_feats {
+                    aggregated[f] += rbf_weight * atom_view[[*j, f]];
+                }
+            }
+        }
+        DVectorViewMut::from_slice(row, n_outputs).gemv(1.0, &model.weights, &aggregated, 0.0);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::elements;
+use crate::graph::MolecularGraph;
+use crate::potential::Potential;
+use nalgebra::{DMatrix, DVector, SymmetricEigen, Vector3};
+use numpy::{ndarray, PyArray1, PyArray2, PyArray3, PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// sqrt(eV / (A^2 amu)) expressed in wavenumbers (cm^-1).
+const WAVENUMBER_PER_SQRT_EV_A2_AMU: f64 = 521.470_9;
+
+/// Harmonic analysis from a finite-difference Hessian of model forces.
+#[pyclass]
+pub struct Vibrations {
+    hessian: DMatrix<f64>,
+    masses: Vec<f64>,
+    /// Squared angular frequencies of the mass-weighted Hessian, ascending.
+    eigenvalues: DVector<f64>,
+    /// Cartesian displacement of each mode, normalized, one mode per column.
+    modes: DMatrix<f64>,
+}
+
+#[pymethods]
+impl Vibrations {
+    /// Builds the Hessian by central differences of the forces, displacing
+    /// every coordinate by `+-delta` (Angstrom); displaced geometries are
+    /// evaluated in parallel.
+    ///
+    /// Masses default to the standard atomic masses of `atomic_numbers`.
+    /// With `project=True`, rigid translations (and rotations, for non-periodic
+    /// graphs) are projected out of the mass-weighted Hessian before diagonalizing.
+    ///
+    /// # Errors
+    /// Returns an error if an element has no tabulated mass or the inputs are misshaped.
+    #[new]
+    #[pyo3(signature = (graph, atom_features, potential, delta=0.01, masses=None, project=true))]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn new(
+        graph: &MolecularGraph,
+        atom_features: PyReadonlyArray2<f32>,
+        potential: &Potential,
+        delta: f32,
+        masses: Option<PyReadonlyArray1<f64>>,
+        project: bool,
+    ) -> PyResult<Self> {
+        let atom_view = atom_features.as_array();
+        potential.check_features(graph, &atom_view)?;
+        let masses = match masses {
+            Some(m) => m.as_array().to_vec(),
+            None => graph.masses()?,
+        };
+        if masses.len() != graph.positions.len() {
+            return Err(PyValueError::new_err(format!(
+                "Got {} masses for {} atoms",
+                masses.len(),
+                graph.positions.len()
+            )));
+        }
+
+        let hessian = finite_difference_hessian(graph, &atom_view, potential, delta);
+        let mut weighted = DMatrix::from_fn(hessian.nrows(), hessian.ncols(), |r, c| {
+            hessian[(r, c)] / (masses[r / 3] * masses[c / 3]).sqrt()
+        });
+        if project {
+            let rotations = graph.periodic_cell().is_none();
+            let projector = rigid_body_projector(&graph.positions, &masses, rotations);
+            weighted = &projector * weighted * &projector;
+        }
+
+        let eigen = SymmetricEigen::new(weighted);
+        let mut order: Vec<usize> = (0..eigen.eigenvalues.len()).collect();
+        order.sort_by(|&a, &b| eigen.eigenvalues[a].total_cmp(&eigen.eigenvalues[b]));
+
+        let eigenvalues =
+            DVector::from_iterator(order.len(), order.iter().map(|&k| eigen.eigenvalues[k]));
+        let mut modes = DMatrix::zeros(hessian.nrows(), order.len());
+        for (col, &k) in order.iter().enumerate() {
+            // Undo the mass weighting to get Cartesian displacements
+            let mut mode = DVector::from_fn(hessian.nrows(), |r, _| {
+                eigen.eigenvectors[(r, k)] / masses[r / 3].sqrt()
+            });
+            let norm = mode.norm();
+            if norm > 0.0 {
+                mode /= norm;
+            }
+            modes.set_column(col, &mode);
+        }
+
+        Ok(Vibrations {
+            hessian,
+            masses,
+            eigenvalues,
+            modes,
+        })
+    }
+
+    /// The symmetrized Cartesian Hessian, shape `(3N, 3N)`, in eV/A^2.
+    #[getter]
+    #[must_use]
+    pub fn hessian(&self, py: Python<'_>) -> Py<PyArray2<f64>> {
+        let (rows, cols) = self.hessian.shape();
+        let array = ndarray::Array2::from_shape_fn((rows, cols), |(r, c)| self.hessian[(r, c)]);
+        PyArray2::from_array(py, &array).into()
+    }
+
+    /// Harmonic frequencies in cm^-1, ascending; imaginary modes are negative.
+    #[getter]
+    #[must_use]
+    pub fn frequencies(&self, py: Python<'_>) -> Py<PyArray1<f64>> {
+        let frequencies: Vec<f64> = self
+            .eigenvalues
+            .iter()
+            .map(|&lambda| lambda.signum() * lambda.abs().sqrt() * WAVENUMBER_PER_SQRT_EV_A2_AMU)
+            .collect();
+        PyArray1::from_vec(py, frequencies).into()
+    }
+
+    /// Normalized Cartesian normal modes, shape `(3N, N, 3)`, in frequency order.
+    #[getter]
+    #[must_use]
+    pub fn modes(&self, py: Python<'_>) -> Py<PyArray3<f64>> {
+        let n = self.masses.len();
+        let array = ndarray::Array3::from_shape_fn((3 * n, n, 3), |(m, atom, axis)| {
+            self.modes[(3 * atom + axis, m)]
+        });
+        PyArray3::from_array(py, &array).into()
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn masses(&self, py: Python<'_>) -> Py<PyArray1<f64>> {
+        PyArray1::from_slice(py, &self.masses).into()
+    }
+}
+
+impl MolecularGraph {
+    /// Standard atomic masses (amu) of every atom.
+    ///
+    /// # Errors
+    /// Returns an error for atomic numbers outside the periodic table.
+    pub fn masses(&self) -> PyResult<Vec<f64>> {
+        self.atomic_numbers
+            .iter()
+            .map(|&z| {
+                elements::atomic_mass(z).ok_or_else(|| {
+                    PyValueError::new_err(format!("No atomic mass for atomic number {z}"))
+                })
+            })
+            .collect()
+    }
+}
+
+/// `H_rc = -dF_r/dx_c`, one column per displaced coordinate, symmetrized.
+fn finite_difference_hessian(
+    graph: &MolecularGraph,
+    atom_view: &ndarray::ArrayView2<f32>,
+    potential: &Potential,
+    delta: f32,
+) -> DMatrix<f64> {
+    let dim = 3 * graph.positions.len();
+    let columns: Vec<Vec<f64>> = (0..dim)
+        .into_par_iter()
+        .map(|c| {
+            let displaced_forces = |step: f32| {
+                let mut displaced = graph.clone();
+                displaced.positions[c / 3][c % 3] += step;
+                potential.evaluate(&displaced, atom_view).forces
+            };
+            let plus = displaced_forces(delta);
+            let minus = displaced_forces(-delta);
+            (0..dim)
+                .map(|r| {
+                    -(f64::from(plus[r / 3][r % 3]) - f64::from(minus[r / 3][r % 3]))
+                        / (2.0 * f64::from(delta))
+                })
+                .collect()
+        })
+        .collect();
+
+    let hessian = DMatrix::from_fn(dim, dim, |r, c| columns[c][r]);
+    (&hessian + hessian.transpose()) * 0.5
+}
+
+/// `P = I - sum_k u_k u_k^T` over orthonormalized mass-weighted rigid-body motions.
+fn rigid_body_projector(
+    positions: &[Vector3<f32>],
+    masses: &[f64],
+    rotations: bool,
+) -> DMatrix<f64> {
+    let dim = 3 * positions.len();
+    let total_mass: f64 = masses.iter().sum();
+    let center = positions
+        .iter()
+        .zip(masses)
+        .fold(Vector3::<f64>::zeros(), |acc, (r, &m)| {
+            acc + r.cast::<f64>() * m
+        })
+        / total_mass;
+
+    let mut motions: Vec<DVector<f64>> = Vec::with_capacity(6);
+    for axis in 0..3 {
+        motions.push(DVector::from_fn(dim, |r, _| {
+            if r % 3 == axis {
+                masses[r / 3].sqrt()
+            } else {
+                0.0
+            }
+        }));
+    }
+    if rotations {
+        for axis in 0..3 {
+            let unit = Vector3::ith(axis, 1.0);
+            motions.push(DVector::from_fn(dim, |r, _| {
+                let arm = positions[r / 3].cast::<f64>() - center;
+                unit.cross(&arm)[r % 3] * masses[r / 3].sqrt()
+            }));
+        }
+    }
+
+    // Gram-Schmidt; linear molecules lose one rotation here
+    let mut basis: Vec<DVector<f64>> = Vec::with_capacity(motions.len());
+    for mut motion in motions {
+        for u in &basis {
+            let overlap = u.dot(&motion);
+            motion -= u * overlap;
+        }
+        let norm = motion.norm();
+        if norm > 1e-8 {
+            basis.push(motion / norm);
+        }
+    }
+
+    let mut projector = DMatrix::identity(dim, dim);
+    for u in &basis {
+        projector -= u * u.transpose();
+    }
+    projector
+}
@@ -0,0 +1,708 @@
+use crate::elements::{atomic_number, symbol};
+use crate::graph::MolecularGraph;
+use nalgebra::{Matrix3, Vector3};
+use numpy::ndarray;
+use numpy::{AllowTypeChange, PyArray1, PyArray2, PyArrayLikeDyn, PyUntypedArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+/// A value of a `key=value` pair on an extended XYZ comment line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InfoValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Ints(Vec<i64>),
+    Floats(Vec<f64>),
+    Str(String),
+}
+
+/// The values of one per-atom column group, `ncols` values per atom.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Column {
+    Real(Vec<f64>),
+    Int(Vec<i64>),
+    Bool(Vec<bool>),
+    Str(Vec<String>),
+}
+
+/// A per-atom property declared in `Properties`, other than species and positions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerAtom {
+    pub name: String,
+    pub ncols: usize,
+    pub values: Column,
+}
+
+/// One frame of an (extended) XYZ file.
+#[pyclass]
+#[derive(Clone)]
+pub struct XyzFrame {
+    pub graph: MolecularGraph,
+    /// The raw comment line.
+    #[pyo3(get)]
+    pub comment: String,
+    /// `key=value` pairs of the comment line, without `Lattice`, `Properties` and `pbc`.
+    pub info: Vec<(String, InfoValue)>,
+    pub arrays: Vec<PerAtom>,
+}
+
+#[pymethods]
+impl XyzFrame {
+    #[new]
+    #[pyo3(signature = (graph, info=None))]
+    /// A frame for writing, with optional per-frame `info` (bools, numbers,
+    /// strings or sequences of numbers).
+    ///
+    /// # Errors
+    /// Returns an error if an `info` value has an unsupported type.
+    pub fn new(graph: MolecularGraph, info: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let info = match info {
+            Some(info) => info
+                .iter()
+                .map(|(key, value)| Ok((key.extract::<String>()?, info_from_python(&value)?)))
+                .collect::<PyResult<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        Ok(XyzFrame {
+            graph,
+            comment: String::new(),
+            info,
+            arrays: Vec::new(),
+        })
+    }
+
+    #[getter]
+    #[must_use]
+    pub fn graph(&self) -> MolecularGraph {
+        self.graph.clone()
+    }
+
+    /// Per-frame `key=value` pairs as a dict.
+    ///
+    /// # Errors
+    /// Returns an error if the dict cannot be built.
+    #[getter]
+    pub fn info<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (key, value) in &self.info {
+            dict.set_item(key, info_to_python(py, value)?)?;
+        }
+        Ok(dict)
+    }
+
+    /// Extra per-atom columns by property name: numeric columns as arrays of
+    /// shape `(n_atoms,)` or `(n_atoms, ncols)`, string columns as lists.
+    ///
+    /// # Errors
+    /// Returns an error if the dict cannot be built.
+    #[getter]
+    pub fn arrays<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        let n = self.graph.positions.len();
+        for array in &self.arrays {
+            let value = match &array.values {
+                Column::Real(v) => column_array(py, v, n, array.ncols)?,
+                Column::Int(v) => column_array(py, v, n, array.ncols)?,
+                Column::Bool(v) => column_array(py, v, n, array.ncols)?,
+                Column::Str(v) if array.ncols == 1 => PyList::new(py, v)?.into_any(),
+                Column::Str(v) => PyList::new(py, v.chunks(array.ncols))?.into_any(),
+            };
+            dict.set_item(&array.name, value)?;
+        }
+        Ok(dict)
+    }
+
+    fn __len__(&self) -> usize {
+        self.graph.positions.len()
+    }
+}
+
+/// Lazily reads the frames of an (extended) XYZ file, one per iteration.
+#[pyclass]
+pub struct XyzReader {
+    reader: BufReader<File>,
+    line: usize,
+}
+
+#[pymethods]
+impl XyzReader {
+    #[new]
+    /// Opens `path` for reading.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened.
+    pub fn new(path: &str) -> PyResult<Self> {
+        Ok(XyzReader {
+            reader: BufReader::new(File::open(path)?),
+            line: 0,
+        })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<XyzFrame>> {
+        read_frame(&mut self.reader, &mut self.line)
+    }
+}
+
+/// Reads every frame of an (extended) XYZ file.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or is malformed.
+#[pyfunction]
+pub fn read_xyz(path: &str) -> PyResult<Vec<XyzFrame>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut line = 0;
+    let mut frames = Vec::new();
+    while let Some(frame) = read_frame(&mut reader, &mut line)? {
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Writes frames (`XyzFrame`s or `MolecularGraph`s) as extended XYZ.
+///
+/// `predictions`, one array of shape `(n_atoms,)` or `(n_atoms, k)` per
+/// frame, is appended as extra per-atom columns under `prediction_name`.
+/// With `append`, frames are added to the end of an existing file.
+///
+/// # Errors
+/// Returns an error if the file cannot be written or the predictions do not
+/// match the frames.
+#[pyfunction]
+#[pyo3(signature = (path, frames, predictions=None, prediction_name="prediction", append=false))]
+#[allow(clippy::needless_pass_by_value)]
+pub fn write_xyz(
+    path: &str,
+    frames: Vec<Bound<'_, PyAny>>,
+    predictions: Option<Vec<PyArrayLikeDyn<'_, f64, AllowTypeChange>>>,
+    prediction_name: &str,
+    append: bool,
+) -> PyResult<()> {
+    let mut frames = frames
+        .iter()
+        .map(|frame| match frame.extract::<XyzFrame>() {
+            Ok(frame) => Ok(frame),
+            Err(_) => Ok(XyzFrame {
+                graph: frame.extract::<MolecularGraph>()?,
+                comment: String::new(),
+                info: Vec::new(),
+                arrays: Vec::new(),
+            }),
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+
+    if let Some(predictions) = predictions {
+        if predictions.len() != frames.len() {
+            return Err(PyValueError::new_err(format!(
+                "Expected one prediction array per frame ({}), got {}",
+                frames.len(),
+                predictions.len()
+            )));
+        }
+        for (index, (frame, prediction)) in frames.iter_mut().zip(&predictions).enumerate() {
+            let n = frame.graph.positions.len();
+            let ncols = match prediction.shape() {
+                [rows] if *rows == n => 1,
+                [rows, cols] if *rows == n => *cols,
+                shape => {
+                    return Err(PyValueError::new_err(format!(
+                        "Frame {index}: expected predictions with {n} rows, got shape {shape:?}"
+                    )))
+                }
+            };
+            frame.arrays.retain(|array| array.name != prediction_name);
+            frame.arrays.push(PerAtom {
+                name: prediction_name.to_string(),
+                ncols,
+                values: Column::Real(prediction.as_array().iter().copied().collect()),
+            });
+        }
+    }
+
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)?;
+    let mut out = BufWriter::new(file);
+    for frame in &frames {
+        write_frame(&mut out, frame)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Parses the next frame, skipping blank lines before it; `None` at the end
+/// of the input. `line` counts the lines consumed so far, for error messages.
+///
+/// # Errors
+/// Returns an error if the input cannot be read or the frame is malformed.
+pub fn read_frame<R: BufRead>(reader: &mut R, line: &mut usize) -> PyResult<Option<XyzFrame>> {
+    let mut buffer = String::new();
+    let header = loop {
+        buffer.clear();
+        if reader.read_line(&mut buffer)? == 0 {
+            return Ok(None);
+        }
+        *line += 1;
+        if !buffer.trim().is_empty() {
+            break buffer.trim().to_string();
+        }
+    };
+    let n_atoms: usize = header
+        .parse()
+        .map_err(|_| parse_error(*line, &format!("expected an atom count, got {header:?}")))?;
+
+    buffer.clear();
+    if reader.read_line(&mut buffer)? == 0 {
+        return Err(parse_error(*line + 1, "missing comment line"));
+    }
+    *line += 1;
+    let comment = buffer.trim_end_matches(['\n', '\r']).to_string();
+    let header = parse_comment(&comment).map_err(|message| parse_error(*line, &message))?;
+
+    // The count comes from the file, so the vectors grow as atoms are read
+    // rather than reserving for a corrupt header up front
+    let mut atomic_numbers = Vec::new();
+    let mut positions = Vec::new();
+    let mut arrays: Vec<PerAtom> = header
+        .properties
+        .iter()
+        .filter(|p| !matches!(p.role, Role::Species | Role::Positions))
+        .map(|p| PerAtom {
+            name: p.name.clone(),
+            ncols: p.ncols,
+            values: match p.kind {
+                'R' => Column::Real(Vec::new()),
+                'I' => Column::Int(Vec::new()),
+                'L' => Column::Bool(Vec::new()),
+                _ => Column::Str(Vec::new()),
+            },
+        })
+        .collect();
+    let width = header
+        .properties
+        .iter()
+        .map(|p| p.ncols)
+        .fold(0, usize::saturating_add);
+
+    for _ in 0..n_atoms {
+        buffer.clear();
+        if reader.read_line(&mut buffer)? == 0 {
+            return Err(parse_error(
+                *line + 1,
+                &format!("expected {n_atoms} atoms, the file ended early"),
+            ));
+        }
+        *line += 1;
+        let fields: Vec<&str> = buffer.split_whitespace().collect();
+        if fields.len() < width {
+            return Err(parse_error(
+                *line,
+                &format!("expected {width} columns, got {}", fields.len()),
+            ));
+        }
+        parse_atom(
+            &header.properties,
+            fields,
+            *line,
+            &mut atomic_numbers,
+            &mut positions,
+            &mut arrays,
+        )?;
+    }
+
+    let pbc = header.pbc.unwrap_or([header.lattice.is_some(); 3]);
+    let graph = MolecularGraph::from_parts(atomic_numbers, positions, header.lattice, pbc)
+        .map_err(|err| parse_error(*line, &err.to_string()))?;
+    Ok(Some(XyzFrame {
+        graph,
+        comment,
+        info: header.info,
+        arrays,
+    }))
+}
+
+/// Appends the species, position and extra columns of one atom line.
+fn parse_atom(
+    properties: &[Property],
+    fields: Vec<&str>,
+    line: usize,
+    atomic_numbers: &mut Vec<i32>,
+    positions: &mut Vec<Vector3<f32>>,
+    arrays: &mut [PerAtom],
+) -> PyResult<()> {
+    let mut fields = fields.into_iter();
+    let mut extra = arrays.iter_mut();
+    for property in properties {
+        let values: Vec<&str> = fields.by_ref().take(property.ncols).collect();
+        let bad = |value: &str| {
+            parse_error(
+                line,
+                &format!("invalid value {value:?} for property {}", property.name),
+            )
+        };
+        match property.role {
+            Role::Species => {
+                let z = match property.kind {
+                    'I' => values[0].parse().ok(),
+                    _ => atomic_number(values[0]).or_else(|| values[0].parse().ok()),
+                };
+                atomic_numbers.push(z.ok_or_else(|| bad(values[0]))?);
+            }
+            Role::Positions => {
+                let mut r = [0.0f32; 3];
+                for (slot, value) in r.iter_mut().zip(&values) {
+                    *slot = value.parse().map_err(|_| bad(value))?;
+                }
+                positions.push(Vector3::from(r));
+            }
+            // `arrays` holds one entry per extra property, in declaration order
+            Role::Other => {
+                let Some(array) = extra.next() else {
+                    continue;
+                };
+                for value in values {
+                    match &mut array.values {
+                        Column::Real(v) => v.push(value.parse().map_err(|_| bad(value))?),
+                        Column::Int(v) => v.push(value.parse().map_err(|_| bad(value))?),
+                        Column::Bool(v) => v.push(parse_bool(value).ok_or_else(|| bad(value))?),
+                        Column::Str(v) => v.push(value.to_string()),
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes one frame as extended XYZ.
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_frame<W: Write>(out: &mut W, frame: &XyzFrame) -> PyResult<()> {
+    let graph = &frame.graph;
+    let n = graph.positions.len();
+    writeln!(out, "{n}")?;
+
+    let mut header = Vec::new();
+    if let Some(cell) = graph.cell {
+        let lattice: Vec<String> = cell.transpose().iter().map(|x| format!("{x:.8}")).collect();
+        header.push(format!("Lattice=\"{}\"", lattice.join(" ")));
+    }
+    let mut properties = String::from("species:S:1:pos:R:3");
+    for array in &frame.arrays {
+        let kind = match array.values {
+            Column::Real(_) => 'R',
+            Column::Int(_) => 'I',
+            Column::Bool(_) => 'L',
+            Column::Str(_) => 'S',
+        };
+        properties.extend([":", &array.name, ":"]);
+        properties.push(kind);
+        properties.extend([":", &array.ncols.to_string()]);
+    }
+    header.push(format!("Properties={properties}"));
+    for (key, value) in &frame.info {
+        header.push(format!("{key}={}", format_info(value)));
+    }
+    if graph.cell.is_some() {
+        let pbc: Vec<&str> = graph
+            .pbc
+            .iter()
+            .map(|&p| if p { "T" } else { "F" })
+            .collect();
+        header.push(format!("pbc=\"{}\"", pbc.join(" ")));
+    }
+    writeln!(out, "{}", header.join(" "))?;
+
+    for (i, (&z, r)) in graph
+        .atomic_numbers
+        .iter()
+        .zip(&graph.positions)
+        .enumerate()
+    {
+        let species = symbol(z).map_or_else(|| z.to_string(), ToString::to_string);
+        write!(out, "{species:<2} {:16.8} {:16.8} {:16.8}", r.x, r.y, r.z)?;
+        for array in &frame.arrays {
+            let row = i * array.ncols..(i + 1) * array.ncols;
+            match &array.values {
+                Column::Real(v) => v[row].iter().try_for_each(|x| write!(out, " {x:16.8}"))?,
+                Column::Int(v) => v[row].iter().try_for_each(|x| write!(out, " {x}"))?,
+                Column::Bool(v) => v[row]
+                    .iter()
+                    .try_for_each(|&x| write!(out, " {}", if x { "T" } else { "F" }))?,
+                Column::Str(v) => v[row].iter().try_for_each(|x| write!(out, " {x}"))?,
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// What a declared property holds.
+#[derive(Clone, Copy, PartialEq)]
+enum Role {
+    Species,
+    Positions,
+    Other,
+}
+
+/// One `name:type:ncols` entry of `Properties`.
+struct Property {
+    name: String,
+    kind: char,
+    ncols: usize,
+    role: Role,
+}
+
+/// The parsed comment line of a frame.
+struct Header {
+    lattice: Option<Matrix3<f32>>,
+    pbc: Option<[bool; 3]>,
+    properties: Vec<Property>,
+    info: Vec<(String, InfoValue)>,
+}
+
+/// Parses the comment line. Lines that are not `key=value` pairs are plain
+/// XYZ comments with the default `species:S:1:pos:R:3` layout.
+fn parse_comment(comment: &str) -> Result<Header, String> {
+    let mut header = Header {
+        lattice: None,
+        pbc: None,
+        properties: parse_properties("species:S:1:pos:R:3")?,
+        info: Vec::new(),
+    };
+    let Some(pairs) = key_value_pairs(comment) else {
+        return Ok(header);
+    };
+    for (key, value) in pairs {
+        match key.to_ascii_lowercase().as_str() {
+            "lattice" => {
+                let values = parse_floats(&value)
+                    .filter(|v| v.len() == 9)
+                    .ok_or_else(|| format!("Lattice must hold 9 numbers, got {value:?}"))?;
+                #[allow(clippy::cast_possible_truncation)]
+                let cell = Matrix3::from_fn(|r, c| values[3 * r + c] as f32);
+                header.lattice = Some(cell);
+            }
+            "properties" => header.properties = parse_properties(&value)?,
+            "pbc" => {
+                let flags: Option<Vec<bool>> = value.split_whitespace().map(parse_bool).collect();
+                let flags = flags
+                    .filter(|f| f.len() == 3)
+                    .ok_or_else(|| format!("pbc must hold 3 flags, got {value:?}"))?;
+                header.pbc = Some([flags[0], flags[1], flags[2]]);
+            }
+            _ => header.info.push((key, parse_info(&value))),
+        }
+    }
+    Ok(header)
+}
+
+/// Parses `Properties`, e.g. `species:S:1:pos:R:3:forces:R:3`.
+fn parse_properties(value: &str) -> Result<Vec<Property>, String> {
+    let fields: Vec<&str> = value.split(':').collect();
+    if !fields.len().is_multiple_of(3) {
+        return Err(format!("malformed Properties {value:?}"));
+    }
+    let mut properties = Vec::new();
+    for entry in fields.chunks(3) {
+        let kind = entry[1]
+            .chars()
+            .next()
+            .map(|k| k.to_ascii_uppercase())
+            .filter(|k| "RILS".contains(*k))
+            .ok_or_else(|| format!("unknown property type {:?}", entry[1]))?;
+        let ncols: usize = entry[2]
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| format!("invalid column count {:?}", entry[2]))?;
+        let role = match (entry[0], kind, ncols) {
+            ("species", 'S', 1) | ("Z", 'I', 1) => Role::Species,
+            ("pos", 'R', 3) => Role::Positions,
+            _ => Role::Other,
+        };
+        properties.push(Property {
+            name: entry[0].to_string(),
+            kind,
+            ncols,
+            role,
+        });
+    }
+    let count = |role| properties.iter().filter(|p| p.role == role).count();
+    if count(Role::Species) != 1 || count(Role::Positions) != 1 {
+        return Err("Properties must declare species (or Z) and pos:R:3 once".to_string());
+    }
+    Ok(properties)
+}
+
+/// Splits `key=value key2="quoted value" flag` into pairs, or `None` if the
+/// line is not in that form. Bare keys are flags set to `T`, but a line
+/// needs at least one `=` to count as key-value pairs at all.
+fn key_value_pairs(line: &str) -> Option<Vec<(String, String)>> {
+    if !line.contains('=') {
+        return None;
+    }
+    let mut pairs = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while chars.peek().is_some() {
+        let key: String =
+            std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace() && *c != '=')).collect();
+        if key.is_empty() {
+            return None;
+        }
+        let value = if chars.next_if_eq(&'=').is_some() {
+            match chars.peek() {
+                Some('"') => {
+                    chars.next();
+                    let mut value = String::new();
+                    loop {
+                        match chars.next()? {
+                            '\\' => value.push(chars.next()?),
+                            '"' => break,
+                            c => value.push(c),
+                        }
+                    }
+                    value
+                }
+                Some('{') => {
+                    chars.next();
+                    let value: String =
+                        std::iter::from_fn(|| chars.next_if(|c| *c != '}')).collect();
+                    chars.next_if_eq(&'}')?;
+                    value
+                }
+                _ => std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect(),
+            }
+        } else {
+            "T".to_string()
+        };
+        pairs.push((key, value));
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+    (!pairs.is_empty()).then_some(pairs)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "T" | "True" | "true" | "TRUE" => Some(true),
+        "F" | "False" | "false" | "FALSE" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_floats(value: &str) -> Option<Vec<f64>> {
+    value
+        .replace(',', " ")
+        .split_whitespace()
+        .map(|x| x.parse().ok())
+        .collect()
+}
+
+/// Types a comment-line value as a bool, number, list of numbers or string.
+fn parse_info(value: &str) -> InfoValue {
+    if let Some(flag) = parse_bool(value) {
+        return InfoValue::Bool(flag);
+    }
+    if let Ok(int) = value.parse() {
+        return InfoValue::Int(int);
+    }
+    if let Ok(float) = value.parse() {
+        return InfoValue::Float(float);
+    }
+    if value.split_whitespace().count() > 1 {
+        let ints: Option<Vec<i64>> = value.split_whitespace().map(|x| x.parse().ok()).collect();
+        if let Some(ints) = ints {
+            return InfoValue::Ints(ints);
+        }
+        if let Some(floats) = parse_floats(value) {
+            return InfoValue::Floats(floats);
+        }
+    }
+    InfoValue::Str(value.to_string())
+}
+
+fn format_info(value: &InfoValue) -> String {
+    let join = |items: Vec<String>| format!("\"{}\"", items.join(" "));
+    match value {
+        InfoValue::Bool(flag) => if *flag { "T" } else { "F" }.to_string(),
+        InfoValue::Int(int) => int.to_string(),
+        // Debug formatting keeps the decimal point of whole floats, so 2.0
+        // reads back as a float rather than an integer
+        InfoValue::Float(float) => format!("{float:?}"),
+        InfoValue::Ints(ints) => join(ints.iter().map(ToString::to_string).collect()),
+        InfoValue::Floats(floats) => join(floats.iter().map(|x| format!("{x:?}")).collect()),
+        InfoValue::Str(text)
+            if text.is_empty()
+                || text.contains(char::is_whitespace)
+                || text.contains(['"', '=']) =>
+        {
+            format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+        InfoValue::Str(text) => text.clone(),
+    }
+}
+
+fn info_to_python<'py>(py: Python<'py>, value: &InfoValue) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        InfoValue::Bool(flag) => PyBool::new(py, *flag).to_owned().into_any(),
+        InfoValue::Int(int) => int.into_pyobject(py)?.into_any(),
+        InfoValue::Float(float) => PyFloat::new(py, *float).into_any(),
+        InfoValue::Ints(ints) => PyArray1::from_slice(py, ints).into_any(),
+        InfoValue::Floats(floats) => PyArray1::from_slice(py, floats).into_any(),
+        InfoValue::Str(text) => PyString::new(py, text).into_any(),
+    })
+}
+
+fn info_from_python(value: &Bound<'_, PyAny>) -> PyResult<InfoValue> {
+    if value.is_instance_of::<PyBool>() {
+        return Ok(InfoValue::Bool(value.extract()?));
+    }
+    if value.is_instance_of::<PyInt>() {
+        return Ok(InfoValue::Int(value.extract()?));
+    }
+    if value.is_instance_of::<PyFloat>() {
+        return Ok(InfoValue::Float(value.extract()?));
+    }
+    if let Ok(text) = value.extract::<String>() {
+        return Ok(InfoValue::Str(text));
+    }
+    if let Ok(ints) = value.extract::<Vec<i64>>() {
+        return Ok(InfoValue::Ints(ints));
+    }
+    if let Ok(floats) = value.extract::<Vec<f64>>() {
+        return Ok(InfoValue::Floats(floats));
+    }
+    Err(PyValueError::new_err(format!(
+        "Unsupported info value {value}; use a bool, number, string or sequence of numbers"
+    )))
+}
+
+/// A per-atom column as a `(n,)` or `(n, ncols)` array.
+fn column_array<'py, T: numpy::Element + Copy>(
+    py: Python<'py>,
+    values: &[T],
+    n: usize,
+    ncols: usize,
+) -> PyResult<Bound<'py, PyAny>> {
+    if ncols == 1 {
+        return Ok(PyArray1::from_slice(py, values).into_any());
+    }
+    let array = ndarray::Array2::from_shape_vec((n, ncols), values.to_vec())
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(PyArray2::from_owned_array(py, array).into_any())
+}
+
+fn parse_error(line: usize, message: &str) -> PyErr {
+    PyValueError::new_err(format!("XYZ line {line}: {message}"))
+}
@@ -0,0 +1,65 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+ase = pytest.importorskip("ase")
+
+
+def test_from_ase_molecule_and_crystal():
+    water = ase.Atoms(
+        "OH2", positions=[[0.0, 0.0, 0.0], [0.96, 0.0, 0.0], [-0.24, 0.93, 0.0]]
+    )
+    graph = _lowlevel.MolecularGraph.from_ase(water)
+    assert graph.atomic_numbers == [8, 1, 1]
+    np.testing.assert_allclose(graph.positions, water.positions, rtol=1e-6)
+    assert graph.cell is None
+    assert graph.pbc == [False, False, False]
+
+    cell = [[4.0, 0.0, 0.0], [1.0, 4.0, 0.0], [0.0, 0.0, 5.0]]
+    salt = ase.Atoms(
+        "NaCl",
+        scaled_positions=[[0, 0, 0], [0.5, 0.5, 0.5]],
+        cell=cell,
+        pbc=[True, True, False],
+    )
+    graph = _lowlevel.MolecularGraph.from_ase(salt)
+    assert graph.atomic_numbers == [11, 17]
+    np.testing.assert_allclose(graph.positions, salt.positions, rtol=1e-6)
+    np.testing.assert_allclose(graph.cell, cell)
+    assert graph.pbc == [True, True, False]
+
+
+def test_round_trip_with_predictions():
+    graph = _lowlevel.MolecularGraph(
+        [6, 8],
+        np.array([[0.0, 0.0, 0.0], [0.0, 0.0, 1.13]], dtype=np.float32),
+        cell=np.eye(3, dtype=np.float32) * 6.0,
+    )
+    model = _lowlevel.GNNModel(np.eye(2, dtype=np.float32))
+    predictions = graph.run_fused_with_model(
+        model, np.ones((2, 2), dtype=np.float32), 3.0, 8
+    )
+
+    atoms = graph.to_ase(predictions=predictions, prediction_name="latent")
+    assert isinstance(atoms, ase.Atoms)
+    assert atoms.get_chemical_symbols() == ["C", "O"]
+    np.testing.assert_allclose(atoms.cell[:], np.eye(3) * 6.0)
+    assert atoms.pbc.tolist() == [True, True, True]
+    np.testing.assert_allclose(atoms.arrays["latent"], predictions)
+
+    back = _lowlevel.MolecularGraph.from_ase(atoms)
+    assert back.atomic_numbers == graph.atomic_numbers
+    np.testing.assert_allclose(back.positions, graph.positions)
+    np.testing.assert_allclose(back.cell, graph.cell)
+
+    plain = _lowlevel.MolecularGraph.from_ase(graph.to_ase())
+    assert plain.pbc == graph.pbc
+
+
+def test_invalid_inputs_are_rejected():
+    with pytest.raises(ValueError, match="require a cell"):
+        _lowlevel.MolecularGraph.from_ase(ase.Atoms("H", pbc=True))
+
+    graph = _lowlevel.MolecularGraph.from_ase(ase.Atoms("H2", positions=np.eye(2, 3)))
+    with pytest.raises(ValueError, match="Expected predictions with 2 rows"):
+        graph.to_ase(predictions=np.zeros(3))
@@ -0,0 +1,341 @@
+import numpy as np
+import pytest
+import valence
+from valence import _lowlevel
+
+
+@pytest.fixture
+def packed_inputs():
+    rng = np.random.default_rng(0)
+    sizes = [3, 1, 4, 2]
+    graph_ptr = np.concatenate([[0], np.cumsum(sizes)]).astype(np.int64)
+    numbers = rng.integers(1, 9, size=graph_ptr[-1]).astype(np.int32)
+    positions = rng.uniform(-1.5, 1.5, size=(graph_ptr[-1], 3)).astype(np.float32)
+    feats = rng.uniform(0.0, 1.0, size=(graph_ptr[-1], 4)).astype(np.float32)
+    return numbers, positions, graph_ptr, feats
+
+
+def test_packed_round_trip(packed_inputs):
+    numbers, positions, graph_ptr, _ = packed_inputs
+    charges = np.array([0.0, -1.0, 2.0, 0.0], dtype=np.float32)
+    batch = _lowlevel.MolecularBatch.from_packed(
+        numbers, positions, graph_ptr, charges.tolist()
+    )
+
+    np.testing.assert_array_equal(batch.graph_ptr, graph_ptr)
+    packed = batch.to_packed()
+    np.testing.assert_array_equal(packed[0], numbers)
+    np.testing.assert_array_equal(packed[1], positions)
+    np.testing.assert_array_equal(packed[2], graph_ptr)
+    np.testing.assert_array_equal(packed[3], charges)
+    np.testing.assert_array_equal(packed[4], np.zeros((4, 3, 3)))
+    np.testing.assert_array_equal(packed[5], np.zeros((4, 3), dtype=bool))
+
+    rebuilt = _lowlevel.MolecularBatch.from_packed(*batch.to_packed())
+    np.testing.assert_array_equal(rebuilt.charges, charges)
+
+
+def test_packed_round_trip_of_molecules_and_materials():
+    molecule = _lowlevel.MolecularGraph(
+        [8, 1, 1], np.array([[0, 0, 0], [0.96, 0, 0], [-0.24, 0.93, 0]], np.float32)
+    )
+    slab = _lowlevel.MolecularGraph(
+        [11, 17],
+        np.array([[0, 0, 0], [1.4, 1.4, 1.4]], dtype=np.float32),
+        cell=np.diag([2.8, 2.8, 20.0]).astype(np.float32),
+        pbc=[True, True, False],
+    )
+    batch = _lowlevel.MolecularBatch([molecule, slab], charges=[-1.0, 0.0])
+    rebuilt = _lowlevel.MolecularBatch.from_packed(*batch.to_packed())
+    for ours, theirs in zip(rebuilt.to_packed(), batch.to_packed()):
+        np.testing.assert_array_equal(ours, theirs)
+    np.testing.assert_array_equal(rebuilt.to_packed()[5], [[False] * 3, [1, 1, 0]])
+
+
+def test_packed_layout_keeps_cells_and_periodicity(packed_inputs):
+    numbers, positions, graph_ptr, feats = packed_inputs
+    cells = np.zeros((4, 3, 3), dtype=np.float32)
+    cells[1] = np.eye(3) * 3.0
+    cells[3] = np.diag([4.0, 4.0, 10.0])
+    pbc = np.array([[False] * 3, [True] * 3, [False] * 3, [True, True, False]])
+    batch = _lowlevel.MolecularBatch.from_packed(
+        numbers, positions, graph_ptr, cells=cells, pbc=pbc
+    )
+    packed = batch.to_packed()
+    np.testing.assert_array_equal(packed[4], cells)
+    np.testing.assert_array_equal(packed[5], pbc)
+
+    # Periodic graphs see their images, exactly as when built one by one
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    outputs = batch.run_packed_inference(model, feats, 2.0, 8)
+    for g, (a, b) in enumerate(zip(graph_ptr[:-1], graph_ptr[1:])):
+        cell = cells[g] if cells[g].any() else None
+        graph = _lowlevel.MolecularGraph(
+            numbers[a:b].tolist(), positions[a:b], cell=cell, pbc=pbc[g].tolist()
+        )
+        expected = graph.run_fused_with_model(model, feats[a:b], 2.0, 8)
+        np.testing.assert_allclose(outputs[a:b], expected, rtol=1e-6)
+
+    # Without pbc, a graph is periodic exactly when it has a cell
+    batch = _lowlevel.MolecularBatch.from_packed(
+        numbers, positions, graph_ptr, cells=cells
+    )
+    periodic = np.repeat(cells.any(axis=(1, 2))[:, None], 3, axis=1)
+    np.testing.assert_array_equal(batch.to_packed()[5], periodic)
+
+    with pytest.raises(ValueError, match=r"Expected cells of shape \(4, 3, 3\)"):
+        _lowlevel.MolecularBatch.from_packed(
+            numbers, positions, graph_ptr, cells=cells[:3]
+        )
+    with pytest.raises(ValueError, match="Graph 0: Periodic boundary"):
+        _lowlevel.MolecularBatch.from_packed(
+            numbers, positions, graph_ptr, pbc=np.ones((4, 3), dtype=bool)
+        )
+
+
+def test_packed_inference_matches_per_graph_batch(packed_inputs):
+    numbers, positions, graph_ptr, feats = packed_inputs
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    batch = _lowlevel.MolecularBatch.from_packed(numbers, positions, graph_ptr)
+
+    packed = batch.run_packed_inference(model, feats, 2.0, 8)
+    chunks = [feats[a:b] for a, b in zip(graph_ptr[:-1], graph_ptr[1:])]
+    per_graph = batch.run_batch_inference(model, chunks, 2.0, 8)
+
+    assert packed.shape == (graph_ptr[-1], 4)
+    np.testing.assert_allclose(packed, np.concatenate(per_graph), rtol=1e-6)
+
+
+def test_engine_predict_packed(packed_inputs, tmp_path):
+    numbers, positions, graph_ptr, feats = packed_inputs
+    weights = tmp_path / "weights.npy"
+    np.save(weights, np.eye(4, dtype=np.float32))
+    engine = valence.ValenceEngine(str(weights))
+
+    molecules = [
+        valence.Molecule(
+            atomic_numbers=numbers[a:b].tolist(), positions=positions[a:b].tolist()
+        )
+        for a, b in zip(graph_ptr[:-1], graph_ptr[1:])
+    ]
+    chunks = [feats[a:b] for a, b in zip(graph_ptr[:-1], graph_ptr[1:])]
+    expected = engine.predict_batch(molecules, chunks, cutoff=2.0, k=8)
+
+    packed = engine.predict_packed(numbers, positions, graph_ptr, feats, 2.0, 8)
+    np.testing.assert_allclose(packed, np.concatenate(expected), rtol=1e-5)
+
+
+def test_packed_layout_validation(packed_inputs):
+    numbers, positions, graph_ptr, feats = packed_inputs
+    with pytest.raises(ValueError, match="graph_ptr"):
+        _lowlevel.MolecularBatch.from_packed(numbers, positions, graph_ptr[:-1])
+    with pytest.raises(ValueError, match="non-decreasing"):
+        bad = graph_ptr.copy()
+        bad[1], bad[2] = bad[2], bad[1]
+        _lowlevel.MolecularBatch.from_packed(numbers, positions, bad)
+    with pytest.raises(ValueError, match="positions"):
+        _lowlevel.MolecularBatch.from_packed(numbers, positions[:-1], graph_ptr)
+
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    batch = _lowlevel.MolecularBatch.from_packed(numbers, positions, graph_ptr)
+    with pytest.raises(ValueError, match="packed atom features"):
+        batch.run_packed_inference(model, feats[:-1], 2.0, 8)
+
+
+def test_scheduler_plan_respects_budgets():
+    rng = np.random.default_rng(1)
+    sizes = [4, 600, 3, 5, 2]
+    graphs = [
+        _lowlevel.MolecularGraph(
+            [6] * n, rng.uniform(0.0, 5.0, size=(n, 3)).astype(np.float32)
+        )
+        for n in sizes
+    ]
+    batch = _lowlevel.MolecularBatch(graphs)
+    scheduler = _lowlevel.Scheduler(max_atoms=8, atom_parallel_threshold=100)
+
+    plan = scheduler.plan(batch, 2.0)
+    # The large graph runs first and on its own, parallelised over its atoms
+    assert plan[0] == ([1], "atoms")
+    assert all(mode == "graphs" for _, mode in plan[1:])
+    assert sorted(g for chunk, _ in plan for g in chunk) == list(range(len(sizes)))
+    for chunk, _ in plan[1:]:
+        assert sum(sizes[g] for g in chunk) <= 8 or len(chunk) == 1
+
+    with pytest.raises(ValueError, match="greater than zero"):
+        _lowlevel.Scheduler(max_atoms=0)
+
+
+def test_scheduler_pair_budget_counts_periodic_images():
+    positions = np.zeros((3, 3), dtype=np.float32)
+    molecule = _lowlevel.MolecularGraph([1, 1, 1], positions)
+    crystal = _lowlevel.MolecularGraph(
+        [1, 1, 1], positions, cell=np.eye(3, dtype=np.float32) * 3.0
+    )
+    # Each molecule has 3 * 3 - 3 = 6 candidate pairs
+    batch = _lowlevel.MolecularBatch([molecule, molecule])
+    assert len(_lowlevel.Scheduler(max_pairs=12).plan(batch, 2.0)) == 1
+    assert len(_lowlevel.Scheduler(max_pairs=11).plan(batch, 2.0)) == 2
+
+    # The crystal's neighbouring images multiply its pairs, so it exceeds the
+    # budget alone and runs first, parallelised over atoms
+    batch = _lowlevel.MolecularBatch([molecule, crystal])
+    plan = _lowlevel.Scheduler(max_pairs=12).plan(batch, 2.0)
+    assert plan == [([1], "atoms"), ([0], "graphs")]
+
+
+def test_scheduled_batch_matches_default(packed_inputs):
+    numbers, positions, graph_ptr, feats = packed_inputs
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    batch = _lowlevel.MolecularBatch.from_packed(numbers, positions, graph_ptr)
+    chunks = [feats[a:b] for a, b in zip(graph_ptr[:-1], graph_ptr[1:])]
+
+    expected = batch.run_batch_inference(model, chunks, 2.0, 8)
+    scheduler = _lowlevel.Scheduler(max_atoms=4, atom_parallel_threshold=3)
+    scheduled = batch.run_batch_inference(model, chunks, 2.0, 8, scheduler=scheduler)
+    for ours, theirs in zip(scheduled, expected):
+        np.testing.assert_allclose(ours, theirs, rtol=1e-6)
+
+
+def test_try_batch_inference_reports_bad_graphs(packed_inputs):
+    numbers, positions, graph_ptr, feats = packed_inputs
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    batch = _lowlevel.MolecularBatch.from_packed(numbers, positions, graph_ptr)
+    chunks = [feats[a:b] for a, b in zip(graph_ptr[:-1], graph_ptr[1:])]
+    expected = batch.run_batch_inference(model, chunks, 2.0, 8)
+
+    bad = list(chunks)
+    bad[0] = bad[0][:-1]
+    bad[2] = np.full_like(bad[2], np.nan)
+    bad[3] = "not an array"
+    results = batch.try_run_batch_inference(model, bad, 2.0, 8)
+
+    assert len(results) == 4
+    for index, match in [(0, "shape"), (2, "non-finite"), (3, "float32")]:
+        assert isinstance(results[index], _lowlevel.GraphError)
+        assert results[index].index == index
+        assert match in results[index].reason
+    np.testing.assert_allclose(results[1], expected[1], rtol=1e-6)
+
+    # Graphs without a feature array are reported too
+    results = batch.try_run_batch_inference(model, chunks[:3], 2.0, 8)
+    assert isinstance(results[3], _lowlevel.GraphError)
+    assert "GraphError(index=3" in repr(results[3])
+
+
+def test_engine_predict_batch_returns_errors(tmp_path):
+    weights = tmp_path / "weights.npy"
+    np.save(weights, np.eye(4, dtype=np.float32))
+    engine = valence.ValenceEngine(str(weights))
+    molecules = [
+        valence.Molecule(atomic_numbers=[1, 1], positions=[[0, 0, 0], [0, 0, 1]])
+        for _ in range(2)
+    ]
+    feats = [np.ones((2, 4), dtype=np.float32), np.ones((3, 4), dtype=np.float32)]
+
+    results = engine.predict_batch(molecules, feats, cutoff=1.5, return_errors=True)
+    assert isinstance(results[0], np.ndarray)
+    assert isinstance(results[1], _lowlevel.GraphError)
+    with pytest.raises(ValueError, match="index 1"):
+        engine.predict_batch(molecules, feats, cutoff=1.5)
+
+
+def test_per_graph_cutoffs_and_charges():
+    model = _lowlevel.GNNModel(np.eye(2, dtype=np.float32))
+    molecule = _lowlevel.MolecularGraph(
+        [8, 1, 1],
+        np.array(
+            [[0.0, 0.0, 0.0], [0.96, 0.0, 0.0], [-0.24, 0.93, 0.0]], dtype=np.float32
+        ),
+    )
+    crystal = _lowlevel.MolecularGraph(
+        [11, 17],
+        np.array([[0.0, 0.0, 0.0], [1.4, 1.4, 1.4]], dtype=np.float32),
+        cell=np.eye(3, dtype=np.float32) * 2.8,
+    )
+    feats = [
+        np.ones((3, 2), dtype=np.float32),
+        np.array([[1.0, 0.0], [0.0, 1.0]], dtype=np.float32),
+    ]
+    batch = _lowlevel.MolecularBatch([molecule, crystal], charges=[-1.0, 0.0])
+    np.testing.assert_array_equal(batch.charges, [-1.0, 0.0])
+
+    cutoffs, offsets = [1.5, 4.0], [8, 16]
+    results = batch.run_batch_inference(model, feats, cutoffs, offsets)
+    packed = batch.run_packed_inference(model, np.vstack(feats), cutoffs, offsets)
+    for graph, feat, cutoff, k, result in zip(
+        [molecule, crystal], feats, cutoffs, offsets, results
+    ):
+        expected = graph.run_fused_with_model(model, feat, cutoff, k)
+        np.testing.assert_allclose(result, expected, rtol=1e-6)
+    np.testing.assert_allclose(packed, np.vstack(results), rtol=1e-6)
+
+    with pytest.raises(ValueError, match="one cutoff per graph"):
+        batch.run_batch_inference(model, feats, [1.5], 8)
+    with pytest.raises(ValueError, match="positive"):
+        batch.run_batch_inference(model, feats, [1.5, -1.0], 8)
+    with pytest.raises(ValueError, match="one charge per graph"):
+        _lowlevel.MolecularBatch([molecule], charges=[0.0, 1.0])
+
+
+def test_outputs_written_into_caller_buffers(packed_inputs):
+    numbers, positions, graph_ptr, feats = packed_inputs
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    batch = _lowlevel.MolecularBatch.from_packed(numbers, positions, graph_ptr)
+    chunks = [feats[a:b] for a, b in zip(graph_ptr[:-1], graph_ptr[1:])]
+    expected = batch.run_batch_inference(model, chunks, 2.0, 8)
+
+    # Per-graph outputs land in row slices of one packed buffer
+    buffer = np.full((graph_ptr[-1], 4), np.nan, dtype=np.float32)
+    views = [buffer[a:b] for a, b in zip(graph_ptr[:-1], graph_ptr[1:])]
+    results = batch.run_batch_inference(model, chunks, 2.0, 8, out=views)
+    assert all(result is view for result, view in zip(results, views))
+    np.testing.assert_allclose(buffer, np.concatenate(expected), rtol=1e-6)
+
+    packed_out = np.empty_like(buffer)
+    packed = batch.run_packed_inference(model, feats, 2.0, 8, out=packed_out)
+    assert packed is packed_out
+    np.testing.assert_allclose(packed_out, buffer, rtol=1e-6)
+
+    single = _lowlevel.MolecularGraph(numbers[:3].tolist(), positions[:3])
+    out = np.empty((3, 4), dtype=np.float32)
+    assert single.run_fused_with_model(model, feats[:3], 2.0, 8, out=out) is out
+    np.testing.assert_allclose(out, expected[0], rtol=1e-6)
+
+
+def test_out_buffers_are_validated(packed_inputs):
+    numbers, positions, graph_ptr, feats = packed_inputs
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    graph = _lowlevel.MolecularGraph(numbers[:3].tolist(), positions[:3])
+
+    short = np.empty((2, 4), dtype=np.float32)
+    with pytest.raises(ValueError, match="shape"):
+        graph.run_fused_with_model(model, feats[:3], 2.0, 8, out=short)
+    strided = np.empty((3, 8), dtype=np.float32)[:, ::2]
+    with pytest.raises(ValueError, match="C-contiguous"):
+        graph.run_fused_with_model(model, feats[:3], 2.0, 8, out=strided)
+    for bad in (feats[:2], feats[:3, :3]):
+        with pytest.raises(ValueError, match="Expected atom features of shape"):
+            graph.run_fused_with_model(model, bad, 2.0, 8)
+
+    batch = _lowlevel.MolecularBatch([graph, graph])
+    out = np.empty((3, 4), dtype=np.float32)
+    with pytest.raises(ValueError, match="cannot be written"):
+        batch.run_batch_inference(model, [feats[:3]] * 2, 2.0, 8, out=[out, out])
+    with pytest.raises(ValueError, match="one out array per graph"):
+        batch.run_batch_inference(model, [feats[:3]] * 2, 2.0, 8, out=[out])
+
+
+def test_batch_features_are_validated(packed_inputs):
+    numbers, positions, graph_ptr, feats = packed_inputs
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    batch = _lowlevel.MolecularBatch.from_packed(numbers, positions, graph_ptr)
+    chunks = [feats[a:b] for a, b in zip(graph_ptr[:-1], graph_ptr[1:])]
+
+    with pytest.raises(ValueError, match=r"one feature array per graph \(4\), got 3"):
+        batch.run_batch_inference(model, chunks[:3], 2.0, 8)
+    narrow = chunks[:2] + [chunks[2][:, :3]] + chunks[3:]
+    with pytest.raises(ValueError, match=r"Graph 2: Expected atom features of shape"):
+        batch.run_batch_inference(model, narrow, 2.0, 8)
+    with pytest.raises(ValueError, match=r"Graph 0: Expected atom features of shape"):
+        batch.run_batch_inference(model, [chunks[0][:2]] + chunks[1:], 2.0, 8)
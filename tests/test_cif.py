@@ -0,0 +1,121 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+NACL = """\
+data_NaCl
+_cell_length_a 5.6402(3)
+_cell_length_b 5.6402(3)
+_cell_length_c 5.6402(3)
+_cell_angle_alpha 90
+_cell_angle_beta 90
+_cell_angle_gamma 90
+_symmetry_space_group_name_H-M 'F m -3 m'
+_publ_section_title
+;
+Rock salt, with 'quotes' and a loop_ keyword inside a text field
+;
+loop_
+_symmetry_equiv_pos_as_xyz
+'x, y, z'
+'x, y+1/2, z+1/2'
+'x+1/2, y, z+1/2'
+'x+1/2, y+1/2, z'
+loop_
+_atom_site_label
+_atom_site_type_symbol
+_atom_site_fract_x
+_atom_site_fract_y
+_atom_site_fract_z
+Na1 Na+ 0.0 0.0 0.0
+Cl1 Cl- 0.5 0.5 0.5
+"""
+
+MIXED = """\
+data_mixed
+_cell.length_a 4
+_cell.length_b 4
+_cell.length_c 6
+_cell.angle_alpha 90
+_cell.angle_beta 90
+_cell.angle_gamma 120
+loop_
+_space_group_symop.operation_xyz
+x,y,z
+-x,-y,-z
+loop_
+_atom_site.label
+_atom_site.fract_x
+_atom_site.fract_y
+_atom_site.fract_z
+_atom_site.occupancy
+Fe1 0 0 0 0.7
+Co1 0 0 0 0.3
+O1 0.1 0.2 0.3 1
+H1 0.3 0.3 0.3 0.05
+"""
+
+
+def read(tmp_path, text, **kwargs):
+    path = tmp_path / "structure.cif"
+    path.write_text(text)
+    return _lowlevel.read_cif(str(path), **kwargs)
+
+
+def test_symmetry_expands_to_the_unit_cell(tmp_path):
+    (structure,) = read(tmp_path, NACL)
+    assert structure.name == "NaCl"
+    assert structure.space_group == "F m -3 m"
+    assert sorted(structure.graph.atomic_numbers) == [11] * 4 + [17] * 4
+    assert structure.graph.pbc == [True, True, True]
+    np.testing.assert_allclose(structure.graph.cell, np.eye(3) * 5.6402, atol=1e-5)
+
+    half = 5.6402 / 2
+    sodium = structure.graph.positions[: len(structure) // 2]
+    expected = [[0, 0, 0], [0, half, half], [half, 0, half], [half, half, 0]]
+    np.testing.assert_allclose(sodium, expected, atol=1e-4)
+
+
+def test_shared_sites_keep_the_majority_species(tmp_path):
+    (structure,) = read(tmp_path, MIXED)
+    assert structure.labels == ["Fe1", "O1", "O1", "H1", "H1"]
+    assert structure.graph.atomic_numbers == [26, 8, 8, 1, 1]
+    np.testing.assert_allclose(structure.occupancies[0], 0.7)
+
+
+def test_min_occupancy_drops_sparse_sites(tmp_path):
+    (structure,) = read(tmp_path, MIXED, min_occupancy=0.1)
+    assert structure.labels == ["Fe1", "O1", "O1"]
+
+
+def test_inversion_images_are_placed_in_the_cell(tmp_path):
+    (structure,) = read(tmp_path, MIXED, min_occupancy=0.1)
+    cell = structure.graph.cell
+    fractional = structure.graph.positions @ np.linalg.inv(cell)
+    np.testing.assert_allclose(fractional[2], [0.9, 0.8, 0.7], atol=1e-5)
+
+
+def test_missing_cell_is_reported(tmp_path):
+    text = NACL.replace("_cell_length_b 5.6402(3)\n", "")
+    with pytest.raises(ValueError, match="_cell_length_b"):
+        read(tmp_path, text)
+
+
+def test_site_columns_must_share_the_label_loop(tmp_path):
+    # The coordinates sit in a separate, shorter loop and cannot be matched
+    # to the labels row for row
+    sites = NACL[NACL.index("loop_\n_atom_site_label") :]
+    split = """\
+loop_
+_atom_site_label
+Na1
+Cl1
+loop_
+_atom_site_type_symbol
+_atom_site_fract_x
+_atom_site_fract_y
+_atom_site_fract_z
+Na+ 0.0 0.0 0.0
+"""
+    with pytest.raises(ValueError, match='site "Na1" has no valid coordinates'):
+        read(tmp_path, NACL.replace(sites, split))
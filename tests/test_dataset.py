@@ -0,0 +1,98 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+
+@pytest.fixture
+def graphs():
+    water = _lowlevel.MolecularGraph(
+        [8, 1, 1],
+        np.array(
+            [[0.0, 0.0, 0.0], [0.96, 0.0, 0.0], [-0.24, 0.93, 0.0]], dtype=np.float32
+        ),
+    )
+    salt = _lowlevel.MolecularGraph(
+        [11, 17],
+        np.array([[0.0, 0.0, 0.0], [1.4, 1.4, 1.4]], dtype=np.float32),
+        cell=np.array([[2.8, 0, 0], [0.5, 2.8, 0], [0, 0, 2.8]], dtype=np.float32),
+        pbc=[True, True, False],
+    )
+    return [water, salt]
+
+
+def test_round_trip_with_features_and_targets(tmp_path, graphs):
+    path = str(tmp_path / "data.bin")
+    features = [
+        np.arange(6, dtype=np.float32).reshape(3, 2),
+        np.arange(4, dtype=np.float32).reshape(2, 2) + 10,
+    ]
+    _lowlevel.write_dataset(path, graphs, features=features, targets=[-1.5, 2.0])
+
+    dataset = _lowlevel.GraphDataset(path)
+    assert len(dataset) == 2
+    assert (dataset.n_atoms, dataset.n_features, dataset.n_targets) == (5, 2, 1)
+    np.testing.assert_array_equal(dataset.sizes(), [3, 2])
+
+    for original, graph in zip(graphs, [dataset[0], dataset[-1]]):
+        assert graph.atomic_numbers == original.atomic_numbers
+        np.testing.assert_array_equal(graph.positions, original.positions)
+        assert graph.pbc == original.pbc
+    assert dataset[0].cell is None
+    np.testing.assert_array_equal(dataset[1].cell, graphs[1].cell)
+
+    for stored, original in zip(dataset.features([1, 0]), features[::-1]):
+        np.testing.assert_array_equal(stored, original)
+    np.testing.assert_array_equal(dataset.targets([1, 0]), [[2.0], [-1.5]])
+
+    with pytest.raises(IndexError, match="out of range"):
+        dataset[2]
+
+
+def test_batch_matches_in_memory_graphs(tmp_path, graphs):
+    path = str(tmp_path / "data.bin")
+    _lowlevel.write_dataset(path, graphs, targets=np.zeros((2, 3)))
+    dataset = _lowlevel.GraphDataset(path)
+    assert dataset.n_features == 0
+    assert dataset.targets([0]).shape == (1, 3)
+
+    model = _lowlevel.GNNModel(np.eye(2, dtype=np.float32))
+    feats = [np.ones((3, 2), dtype=np.float32), np.ones((2, 2), dtype=np.float32)]
+    stored = dataset.batch([0, 1]).run_batch_inference(model, feats, 4.0, 16)
+    expected = _lowlevel.MolecularBatch(graphs).run_batch_inference(
+        model, feats, 4.0, 16
+    )
+    for a, b in zip(stored, expected):
+        np.testing.assert_allclose(a, b)
+
+    np.testing.assert_array_equal(dataset.batch([1]).charges, [0.0])
+    np.testing.assert_array_equal(dataset.batch([1, 0], [-1.0, 1.0]).charges, [-1, 1])
+    with pytest.raises(ValueError, match="one charge per graph"):
+        dataset.batch([0, 1], [1.0])
+
+
+def test_mismatched_inputs_are_rejected(tmp_path, graphs):
+    path = str(tmp_path / "data.bin")
+    with pytest.raises(ValueError, match="one feature array per graph"):
+        _lowlevel.write_dataset(path, graphs, features=[np.zeros((3, 2), np.float32)])
+    with pytest.raises(ValueError, match="expected features of shape"):
+        _lowlevel.write_dataset(
+            path,
+            graphs,
+            features=[np.zeros((3, 2), np.float32), np.zeros((2, 3), np.float32)],
+        )
+    with pytest.raises(ValueError, match="Expected targets with 2 rows"):
+        _lowlevel.write_dataset(path, graphs, targets=[1.0])
+
+
+def test_invalid_files_are_rejected(tmp_path, graphs):
+    path = tmp_path / "data.bin"
+    _lowlevel.write_dataset(str(path), graphs)
+    truncated = tmp_path / "truncated.bin"
+    truncated.write_bytes(path.read_bytes()[:100])
+    with pytest.raises(ValueError, match="expected at least"):
+        _lowlevel.GraphDataset(str(truncated))
+
+    other = tmp_path / "other.bin"
+    other.write_bytes(b"\0" * 128)
+    with pytest.raises(ValueError, match="missing header"):
+        _lowlevel.GraphDataset(str(other))
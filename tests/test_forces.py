@@ -0,0 +1,127 @@
+import numpy as np
+import pytest
+import valence
+
+
+@pytest.fixture
+def water_engine():
+    rng = np.random.default_rng(0)
+    weights = rng.normal(size=(4, 6)).astype(np.float32)
+    np.save("test_weights.npy", weights)
+    engine = valence.ValenceEngine("test_weights.npy")
+    mol = valence.Molecule(
+        atomic_numbers=[8, 1, 1],
+        positions=[[0.0, 0.0, 0.0], [0.96, 0.0, 0.0], [-0.24, 0.93, 0.0]],
+    )
+    feats = rng.uniform(size=(3, 6)).astype(np.float32)
+    return engine, mol, feats
+
+
+def test_forces_match_finite_differences(water_engine):
+    engine, mol, feats = water_engine
+    energy, forces = engine.compute_forces(mol, feats, cutoff=2.5, k=8)
+    assert forces.shape == (3, 3)
+
+    output = engine.run(mol, feats, cutoff=2.5, k=8)
+    assert energy == pytest.approx(float(np.sum(output)), rel=1e-4)
+
+    h = 1e-2
+    positions = np.array(mol.positions)
+    numerical = np.zeros_like(positions)
+    for i in range(3):
+        for d in range(3):
+            energies = []
+            for sign in (1.0, -1.0):
+                displaced = positions.copy()
+                displaced[i, d] += sign * h
+                shifted = valence.Molecule(
+                    atomic_numbers=mol.atomic_numbers, positions=displaced.tolist()
+                )
+                energies.append(np.sum(engine.run(shifted, feats, cutoff=2.5, k=8)))
+            numerical[i, d] = -(energies[0] - energies[1]) / (2 * h)
+
+    np.testing.assert_allclose(forces, numerical, atol=2e-2, rtol=2e-2)
+    # Pairwise central interactions conserve momentum
+    np.testing.assert_allclose(forces.sum(axis=0), 0.0, atol=1e-4)
+
+
+def test_readout_length_is_validated(water_engine):
+    engine, mol, feats = water_engine
+    with pytest.raises(ValueError):
+        engine.compute_forces(
+            mol, feats, cutoff=2.5, k=8, readout=np.ones(3, dtype=np.float32)
+        )
+
+
+def simple_cubic(lattice_constant):
+    cell = np.eye(3, dtype=np.float32) * lattice_constant
+    return valence.Molecule(
+        atomic_numbers=[18], positions=[[0.0, 0.0, 0.0]], cell=cell.tolist()
+    )
+
+
+def test_periodic_stress_matches_strain_derivative():
+    weights = np.eye(4, dtype=np.float32)
+    np.save("test_weights.npy", weights)
+    engine = valence.ValenceEngine("test_weights.npy")
+    feats = np.ones((1, 4), dtype=np.float32)
+
+    # Six nearest neighbours at 2.0 A, the next shell (2.83 A) is outside the cutoff
+    graph = simple_cubic(2.0).build_graph()
+    assert graph.pbc == [True, True, True]
+    energy, forces, stress = graph.compute_forces_and_stress(
+        engine.model, feats, 2.5, 8
+    )
+    np.testing.assert_allclose(forces, 0.0, atol=1e-5)
+    np.testing.assert_allclose(stress, stress.T, atol=1e-6)
+    np.testing.assert_allclose(stress - np.diag(np.diag(stress)), 0.0, atol=1e-6)
+
+    # Isotropic strain: sigma_xx = (1/V) dE/d(eps_xx) = (1/3V) dE/d(eps_vol)
+    h = 1e-3
+    energies = [
+        simple_cubic(2.0 * (1 + s))
+        .build_graph()
+        .compute_forces(engine.model, feats, 2.5, 8)[0]
+        for s in (h, -h)
+    ]
+    numerical = (energies[0] - energies[1]) / (2 * h) / 3 / 2.0**3
+    assert stress[0, 0] == pytest.approx(numerical, rel=1e-2)
+
+    _, _, stress_gpa = graph.compute_forces_and_stress(
+        engine.model, feats, 2.5, 8, units="GPa"
+    )
+    np.testing.assert_allclose(stress_gpa, stress * 160.21766208, rtol=1e-5)
+
+
+def test_stress_requires_periodic_graph(water_engine):
+    engine, mol, feats = water_engine
+    with pytest.raises(ValueError):
+        mol.build_graph().compute_forces_and_stress(engine.model, feats, 2.5, 8)
+
+
+def test_energy_and_forces_vanish_smoothly_at_the_cutoff(water_engine):
+    engine, _, _ = water_engine
+    feats = np.ones((2, 6), dtype=np.float32)
+    results = []
+    for d in (2.4999, 2.5001):
+        dimer = valence.Molecule(
+            atomic_numbers=[1, 1], positions=[[0, 0, 0], [0, 0, d]]
+        )
+        results.append(dimer.build_graph().compute_forces(engine.model, feats, 2.5, 8))
+
+    (inside, inside_forces), (outside, outside_forces) = results
+    assert outside == 0.0
+    np.testing.assert_array_equal(outside_forces, 0.0)
+    assert inside == pytest.approx(0.0, abs=1e-8)
+    np.testing.assert_allclose(inside_forces, 0.0, atol=1e-5)
+
+
+def test_feature_columns_are_validated(water_engine):
+    engine, mol, feats = water_engine
+    graph = mol.build_graph()
+    with pytest.raises(ValueError, match=r"Expected atom features of shape \(3, 6\)"):
+        graph.compute_forces(engine.model, feats[:, :5], 2.5, 8)
+
+    periodic = simple_cubic(2.0).build_graph()
+    with pytest.raises(ValueError, match=r"Expected atom features of shape \(1, 6\)"):
+        periodic.compute_forces_and_stress(engine.model, feats[:1, :5], 2.5, 8)
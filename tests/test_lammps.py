@@ -0,0 +1,136 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+DATA = """\
+LAMMPS data file via write_data
+
+3 atoms
+2 atom types
+2 bonds
+
+0.0 10.0 xlo xhi
+-1.0 9.0 ylo yhi
+0.0 12.0 zlo zhi
+1.0 0.5 0.0 xy xz yz
+
+Masses
+
+1 15.9994
+2 1.008
+
+Atoms # full
+
+3 1 2 0.4238 1.0 0.0 0.0 0 0 0
+1 1 1 -0.8476 0.0 0.0 0.0 0 0 0
+2 1 2 0.4238 0.0 1.0 0.0 0 0 0
+
+Bonds
+
+1 1 1 2
+2 1 1 3
+"""
+
+DUMP = """\
+ITEM: TIMESTEP
+0
+ITEM: NUMBER OF ATOMS
+2
+ITEM: BOX BOUNDS xy xz yz pp pp ff
+-1.0 11.0 2.0
+0.0 10.0 0.0
+0.0 10.0 0.0
+ITEM: ATOMS id type xs ys zs c_pe
+2 2 0.5 0.5 0.5 -1.5
+1 1 0.0 0.0 0.0 -2.5
+ITEM: TIMESTEP
+10
+ITEM: NUMBER OF ATOMS
+2
+ITEM: BOX BOUNDS pp pp pp
+0.0 10.0
+0.0 10.0
+0.0 10.0
+ITEM: ATOMS id type x y z
+1 1 1.0 2.0 3.0
+2 2 4.0 5.0 6.0
+"""
+
+
+def write(tmp_path, name, text):
+    path = tmp_path / name
+    path.write_text(text)
+    return str(path)
+
+
+def test_data_file_with_triclinic_box(tmp_path):
+    frame = _lowlevel.read_lammps_data(write(tmp_path, "water.data", DATA))
+    assert frame.ids == [1, 2, 3]
+    assert frame.types == [1, 2, 2]
+    # Elements inferred from the Masses section
+    assert frame.graph.atomic_numbers == [8, 1, 1]
+    np.testing.assert_allclose(
+        frame.graph.cell, [[10, 0, 0], [1, 10, 0], [0.5, 0, 12]], atol=1e-6
+    )
+    # Positions are relative to the box origin
+    np.testing.assert_allclose(frame.graph.positions[1], [0.0, 2.0, 0.0])
+    np.testing.assert_allclose(frame.columns["q"], [-0.8476, 0.4238, 0.4238])
+    np.testing.assert_allclose(frame.columns["mol"], [1, 1, 1])
+
+
+def test_type_map_overrides_masses(tmp_path):
+    path = write(tmp_path, "water.data", DATA)
+    frame = _lowlevel.read_lammps_data(path, type_map={1: "O", 2: 9})
+    assert frame.graph.atomic_numbers == [8, 9, 9]
+
+
+def test_dump_frames(tmp_path):
+    path = write(tmp_path, "traj.dump", DUMP)
+    first, second = _lowlevel.read_lammps_dump(path, type_map={1: "C", 2: "O"})
+
+    assert first.timestep == 0
+    assert first.ids == [1, 2]
+    assert first.graph.atomic_numbers == [6, 8]
+    assert first.graph.pbc == [True, True, False]
+    np.testing.assert_allclose(first.graph.cell[1], [2.0, 10.0, 0.0])
+    # Scaled coordinates go through the tilted cell
+    np.testing.assert_allclose(first.graph.positions[1], [6.0, 5.0, 5.0])
+    np.testing.assert_allclose(first.columns["c_pe"], [-2.5, -1.5])
+
+    assert second.timestep == 10
+    np.testing.assert_allclose(second.graph.positions, [[1, 2, 3], [4, 5, 6]])
+
+    streamed = _lowlevel.LammpsDumpReader(path, type_map={1: "C", 2: "O"})
+    assert [frame.timestep for frame in streamed] == [0, 10]
+
+
+def test_dump_skips_unknown_items(tmp_path):
+    # Dumps written with thermo_modify time or units add these items
+    extra = "ITEM: UNITS\nmetal\nITEM: TIME\n0.5\nITEM: TIMESTEP\n"
+    text = DUMP.replace("ITEM: TIMESTEP\n", extra)
+    path = write(tmp_path, "traj.dump", text)
+    frames = _lowlevel.read_lammps_dump(path, type_map={1: "C", 2: "O"})
+    assert [frame.timestep for frame in frames] == [0, 10]
+    np.testing.assert_allclose(frames[1].graph.positions, [[1, 2, 3], [4, 5, 6]])
+
+    path = write(tmp_path, "bad.dump", "0\n" + DUMP)
+    with pytest.raises(ValueError, match="expected an ITEM: line"):
+        _lowlevel.read_lammps_dump(path, type_map={1: "C", 2: "O"})
+
+    # A corrupt atom count runs into the next frame instead of being reserved
+    text = DUMP.replace("ATOMS\n2\n", "ATOMS\n100000000000\n", 1)
+    path = write(tmp_path, "bad.dump", text)
+    with pytest.raises(ValueError, match="line 12: expected 6 columns, got 2"):
+        _lowlevel.read_lammps_dump(path, type_map={1: "C", 2: "O"})
+
+
+def test_dump_needs_an_element_for_every_type(tmp_path):
+    path = write(tmp_path, "traj.dump", DUMP)
+    with pytest.raises(ValueError, match="Atom type 2 has no element"):
+        _lowlevel.read_lammps_dump(path, type_map={1: "C"})
+
+
+def test_unsupported_atom_style(tmp_path):
+    path = write(tmp_path, "water.data", DATA)
+    with pytest.raises(ValueError, match="Unsupported atom style"):
+        _lowlevel.read_lammps_data(path, atom_style="ellipsoid")
@@ -0,0 +1,119 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+
+@pytest.fixture
+def water_md_inputs():
+    # A cutoff wider than the molecule keeps the energy smooth along the trajectory
+    model = _lowlevel.GNNModel(np.array([[-1.0, -0.5]], dtype=np.float32))
+    potential = _lowlevel.Potential(model, 8.0, 8)
+    positions = np.array(
+        [[0.0, 0.0, 0.0], [1.0, 0.1, 0.0], [-0.3, 0.9, 0.2]], dtype=np.float32
+    )
+    graph = _lowlevel.MolecularGraph([8, 1, 1], positions)
+    feats = np.array([[0.5, 0.6], [0.6, 0.7], [0.7, 0.8]], dtype=np.float32)
+    return graph, feats, potential
+
+
+def test_velocity_verlet_conserves_energy(water_md_inputs):
+    graph, feats, potential = water_md_inputs
+    md = _lowlevel.MolecularDynamics(graph, feats, potential, 0.25)
+    start = md.potential_energy + md.kinetic_energy
+
+    energies = []
+    md.run(
+        400,
+        interval=20,
+        energy_callback=lambda step, epot, ekin, temp: energies.append(epot + ekin),
+    )
+
+    assert md.step == 400
+    assert md.time == pytest.approx(100.0)
+    assert len(energies) == 20
+    assert md.kinetic_energy > 0
+    np.testing.assert_allclose(energies, start, atol=1e-3)
+
+
+def test_trajectory_callback_and_seeded_velocities(water_md_inputs):
+    graph, feats, potential = water_md_inputs
+    frames = []
+    runs = []
+    for _ in range(2):
+        md = _lowlevel.MolecularDynamics(
+            graph, feats, potential, 0.5, temperature=300.0, seed=11
+        )
+        md.run(
+            10,
+            interval=5,
+            trajectory_callback=lambda step, pos, vel: frames.append((step, pos)),
+        )
+        runs.append(md.positions)
+
+    assert [step for step, _ in frames] == [5, 10, 5, 10]
+    assert frames[0][1].shape == (3, 3)
+    np.testing.assert_array_equal(runs[0], runs[1])
+    np.testing.assert_allclose(md.graph.atomic_numbers, [8, 1, 1])
+
+
+def test_berendsen_thermostat_reaches_target(water_md_inputs):
+    graph, feats, potential = water_md_inputs
+    thermostat = _lowlevel.Thermostat.berendsen(300.0, tau=10.0)
+    md = _lowlevel.MolecularDynamics(
+        graph, feats, potential, 0.25, temperature=50.0, thermostat=thermostat
+    )
+    md.run(2000)
+    assert md.temperature == pytest.approx(300.0, rel=0.05)
+
+
+@pytest.mark.parametrize(
+    "thermostat",
+    [
+        _lowlevel.Thermostat.langevin(300.0, friction=0.05),
+        _lowlevel.Thermostat.bussi(300.0, tau=20.0),
+        _lowlevel.Thermostat.nose_hoover(300.0, tau=20.0, chain_length=3),
+    ],
+)
+def test_thermostats_conserve_extended_energy(water_md_inputs, thermostat):
+    graph, feats, potential = water_md_inputs
+    runs = []
+    for _ in range(2):
+        md = _lowlevel.MolecularDynamics(
+            graph,
+            feats,
+            potential,
+            0.25,
+            temperature=300.0,
+            seed=5,
+            thermostat=thermostat,
+        )
+        start = md.conserved_energy
+        md.run(400)
+        assert md.conserved_energy == pytest.approx(start, abs=1e-3)
+        runs.append(md.velocities)
+
+    # The thermostat noise comes from the seeded generator
+    np.testing.assert_array_equal(runs[0], runs[1])
+
+
+def test_berendsen_barostat_rescales_cell():
+    model = _lowlevel.GNNModel(np.array([[1.0]], dtype=np.float32))
+    potential = _lowlevel.Potential(model, 2.5, 8)
+    cell = np.eye(3, dtype=np.float32) * 2.0
+    graph = _lowlevel.MolecularGraph([6], np.zeros((1, 3), dtype=np.float32), cell=cell)
+    feats = np.ones((1, 1), dtype=np.float32)
+
+    barostat = _lowlevel.Barostat.berendsen(pressure=1.0, tau=100.0)
+    md = _lowlevel.MolecularDynamics(graph, feats, potential, 1.0, barostat=barostat)
+    assert barostat.pressure == pytest.approx(1.0)
+    assert md.pressure is not None
+    md.run(10)
+
+    # A repulsive lattice under pressure expands isotropically
+    new_cell = md.cell
+    assert new_cell[0, 0] > 2.0
+    np.testing.assert_allclose(np.diag(new_cell), new_cell[0, 0])
+
+    isolated = _lowlevel.MolecularGraph([6], np.zeros((1, 3), dtype=np.float32))
+    with pytest.raises(ValueError, match="periodic cell"):
+        _lowlevel.MolecularDynamics(isolated, feats, potential, 1.0, barostat=barostat)
@@ -0,0 +1,83 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+# Two radial centres over an 8 A cutoff, damped by the cutoff envelope, make the
+# edge weight peak at 1.8319 A. A hydrogen passing between carbons at
+# (0, +-1, 0) is therefore bound at x = +-sqrt(1.8319^2 - 1) = +-1.5349.
+WELL_X = 1.5349
+
+
+def hop_graph(x):
+    # A hydrogen passing between two carbons at (0, +-1, 0)
+    positions = np.array([[0.0, 1.0, 0.0], [0.0, -1.0, 0.0], [x, 0.0, 0.0]])
+    return _lowlevel.MolecularGraph([6, 6, 1], positions.astype(np.float32))
+
+
+@pytest.fixture
+def hop():
+    model = _lowlevel.GNNModel(np.array([[-1.0, 0.0]], dtype=np.float32))
+    potential = _lowlevel.Potential(model, 8.0, 2)
+    feats = np.array([[1.0, 0.0], [1.0, 0.0], [1.0, 0.0]], dtype=np.float32)
+    images = _lowlevel.NEB.interpolate(hop_graph(-WELL_X), hop_graph(WELL_X), 4)
+    return images, feats, potential
+
+
+def test_linear_interpolation(hop):
+    images, feats, potential = hop
+    band = _lowlevel.NEB(images, feats, potential)
+    positions = band.positions
+    assert positions.shape == (6, 3, 3)
+    path = np.linspace(-WELL_X, WELL_X, 6)
+    np.testing.assert_allclose(positions[:, 2, 0], path, atol=1e-6)
+    # Both end points sit at the bottom of their wells
+    for end in (images[0], images[-1]):
+        _, forces = potential.energy_and_forces(end, feats)
+        np.testing.assert_allclose(forces[2], 0.0, atol=1e-4)
+    np.testing.assert_allclose(positions[:, :2] - positions[:1, :2], 0.0)
+
+    with pytest.raises(ValueError, match="Unknown interpolation"):
+        _lowlevel.NEB.interpolate(images[0], images[-1], 3, method="spline")
+    other = _lowlevel.MolecularGraph([6, 6, 8], np.zeros((3, 3), dtype=np.float32))
+    with pytest.raises(ValueError, match="same atoms"):
+        _lowlevel.NEB.interpolate(images[0], other, 3)
+    with pytest.raises(ValueError, match="at least 3 images"):
+        _lowlevel.NEB(images[:2], feats, potential)
+
+
+def test_idpp_keeps_bond_lengths_through_a_rotation():
+    initial = _lowlevel.MolecularGraph(
+        [1, 1], np.array([[1.0, 0.0, 0.0], [-1.0, 0.0, 0.0]], dtype=np.float32)
+    )
+    final = _lowlevel.MolecularGraph(
+        [1, 1], np.array([[0.0, 1.0, 0.0], [0.0, -1.0, 0.0]], dtype=np.float32)
+    )
+    model = _lowlevel.GNNModel(np.array([[1.0]], dtype=np.float32))
+    potential = _lowlevel.Potential(model, 5.0, 4)
+    feats = np.ones((2, 1), dtype=np.float32)
+
+    for method, expected in [("linear", np.sqrt(2.0)), ("idpp", 2.0)]:
+        images = _lowlevel.NEB.interpolate(initial, final, 3, method=method)
+        middle = _lowlevel.NEB(images, feats, potential).positions[2]
+        distance = np.linalg.norm(middle[0] - middle[1])
+        assert distance == pytest.approx(expected, abs=1e-2)
+
+
+def test_climbing_image_finds_the_saddle(hop):
+    images, feats, potential = hop
+    plain = _lowlevel.NEB(images, feats, potential, fixed=[0, 1])
+    assert plain.run()
+
+    band = _lowlevel.NEB(images, feats, potential, climb=True, fixed=[0, 1])
+    converged = band.run(_lowlevel.Relaxer.fire(fmax=0.005, max_steps=2000))
+    assert converged
+    assert band.steps > 0
+
+    saddle_energy, _ = potential.energy_and_forces(hop_graph(0.0), feats)
+    energies = band.energies
+    top = int(np.argmax(energies))
+    assert band.barrier == pytest.approx(saddle_energy - energies[0], abs=2e-3)
+    assert plain.barrier < band.barrier
+    assert band.positions[top, 2, 0] == pytest.approx(0.0, abs=0.05)
+    np.testing.assert_allclose(band.positions[:, :2] - band.positions[:1, :2], 0.0)
+    assert band.forces.shape == (6, 3, 3)
@@ -0,0 +1,96 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+PAIRS = [(i, j) for i in range(4) for j in range(i + 1, 4)]
+
+
+@pytest.fixture
+def cluster():
+    # Two radial centres over an 8 A cutoff, damped by the cutoff envelope, make
+    # every pair energy lowest at 1.832 A, so the cluster relaxes towards a
+    # regular tetrahedron of that edge length
+    model = _lowlevel.GNNModel(np.array([[-0.3, -0.2]], dtype=np.float32))
+    potential = _lowlevel.Potential(model, 8.0, 2)
+    positions = np.array(
+        [[0.0, 0.0, 0.0], [1.4, 0.9, 0.1], [0.7, 2.7, 0.6], [2.3, 1.5, 2.2]],
+        dtype=np.float32,
+    )
+    graph = _lowlevel.MolecularGraph([6, 6, 6, 6], positions)
+    feats = np.array([[0.5, 0.6], [0.6, 0.7], [0.7, 0.8], [0.8, 0.9]], dtype=np.float32)
+    return graph, feats, potential
+
+
+@pytest.mark.parametrize(
+    "relaxer",
+    [_lowlevel.Relaxer.fire(fmax=0.01), _lowlevel.Relaxer.lbfgs(fmax=0.01)],
+)
+def test_relaxers_reach_force_criterion(cluster, relaxer):
+    graph, feats, potential = cluster
+    result = relaxer.run(graph, feats, potential)
+
+    assert result.converged
+    assert np.linalg.norm(result.forces, axis=1).max() < 0.01
+    assert result.energy < result.energies[0]
+    assert result.trajectory.shape == (len(result.energies), 4, 3)
+    assert result.steps == len(result.energies) - 1
+    assert result.cells is None
+    positions = result.graph.positions
+    edges = [np.linalg.norm(positions[i] - positions[j]) for i, j in PAIRS]
+    np.testing.assert_allclose(edges, 1.832, atol=0.15)
+
+    energy, forces = potential.energy_and_forces(result.graph, feats)
+    assert energy == pytest.approx(result.energy, abs=1e-5)
+    np.testing.assert_allclose(forces, result.forces, atol=1e-5)
+
+
+def test_fixed_atoms_stay_in_place(cluster):
+    graph, feats, potential = cluster
+    result = _lowlevel.Relaxer.lbfgs(fmax=0.01).run(
+        graph, feats, potential, fixed=[0, 2]
+    )
+    assert result.converged
+    displacement = result.trajectory - result.trajectory[0]
+    np.testing.assert_array_equal(displacement[:, [0, 2]], 0.0)
+    assert np.abs(displacement[:, [1, 3]]).max() > 0
+
+    with pytest.raises(ValueError, match="out of range"):
+        _lowlevel.Relaxer.fire().run(graph, feats, potential, fixed=[4])
+
+
+def test_energy_tolerance_and_step_limit(cluster):
+    graph, feats, potential = cluster
+    result = _lowlevel.Relaxer.fire(fmax=0.0, energy_tol=1e-3).run(
+        graph, feats, potential
+    )
+    assert result.converged
+    assert abs(result.energies[-1] - result.energies[-2]) < 1e-3
+
+    capped = _lowlevel.Relaxer.fire(fmax=0.0, max_steps=3, max_step=0.05)
+    result = capped.run(graph, feats, potential)
+    assert not result.converged
+    assert result.steps == 3
+    steps = np.linalg.norm(np.diff(result.trajectory, axis=0), axis=2)
+    assert steps.max() <= 0.05 + 1e-6
+
+
+def test_cell_relaxation_removes_stress():
+    model = _lowlevel.GNNModel(np.array([[1.0]], dtype=np.float32))
+    potential = _lowlevel.Potential(model, 2.5, 8)
+    cell = np.eye(3, dtype=np.float32) * 2.0
+    graph = _lowlevel.MolecularGraph([6], np.zeros((1, 3), dtype=np.float32), cell=cell)
+    feats = np.ones((1, 1), dtype=np.float32)
+
+    result = _lowlevel.Relaxer.fire(fmax=1e-3, max_step=0.1).run(
+        graph, feats, potential, relax_cell=True
+    )
+    assert result.converged
+    assert result.cells.shape == (len(result.energies), 3, 3)
+    relaxed_cell = result.graph.cell
+    assert np.all(np.diag(relaxed_cell) > 2.0)
+    _, _, stress = result.graph.compute_forces_and_stress(model, feats, 2.5, 8)
+    np.testing.assert_allclose(stress, 0.0, atol=1e-3)
+
+    isolated = _lowlevel.MolecularGraph([6], np.zeros((1, 3), dtype=np.float32))
+    with pytest.raises(ValueError, match="periodic"):
+        _lowlevel.Relaxer.fire().run(isolated, feats, potential, relax_cell=True)
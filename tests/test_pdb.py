@@ -0,0 +1,100 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+PDB = """\
+HEADER    TEST
+CRYST1   10.000   12.000   14.000  90.00  90.00 120.00 P 1           1
+ATOM      1  N   ALA A   1      11.104   6.134  -6.504  1.00  0.00           N
+ATOM      2  CA AALA A   1      11.639   6.071  -5.147  0.60  0.00           C
+ATOM      3  CA BALA A   1      11.700   6.000  -5.100  0.40  0.00           C
+ATOM      4  HB1 ALA A   1      12.000   7.000  -5.000  1.00  0.00
+ATOM      5 HD21 ASN A   2A     12.500   7.000  -5.000  1.00  0.00
+TER
+HETATM    6 ZN    ZN B 101       1.000   2.000   3.000  1.00 20.00          ZN2+
+HETATM    7 CL1  LIG B 102       2.000   2.000   3.000  1.00 20.00
+HETATM    8  O   HOH W 201       0.000   0.000   0.000  1.00 30.00           O
+ENDMDL
+MODEL        2
+ATOM      9  N   ALA A   1      11.104   6.134  -6.504  1.00  0.00           N
+"""
+
+
+@pytest.fixture
+def pdb_path(tmp_path):
+    path = tmp_path / "complex.pdb"
+    path.write_text(PDB)
+    return str(path)
+
+
+def test_atoms_and_elements(pdb_path):
+    structure = _lowlevel.read_pdb(pdb_path)
+    assert len(structure) == 7
+    assert structure.graph.atomic_numbers == [7, 6, 1, 1, 30, 17, 8]
+    assert structure.atom_names == ["N", "CA", "HB1", "HD21", "ZN", "CL1", "O"]
+    assert structure.hetero == [False] * 4 + [True] * 3
+    assert structure.formal_charges == [0, 0, 0, 0, 2, 0, 0]
+    np.testing.assert_allclose(structure.b_factors[4:], [20.0, 20.0, 30.0])
+
+
+def test_residue_and_chain_annotations(pdb_path):
+    structure = _lowlevel.read_pdb(pdb_path)
+    assert structure.chain_ids == ["A", "A", "A", "A", "B", "B", "W"]
+    assert structure.residue_numbers == [1, 1, 1, 2, 101, 102, 201]
+    assert structure.insertion_codes[3] == "A"
+    assert structure.residues() == [
+        ("A", 1, "", "ALA", [0, 1, 2]),
+        ("A", 2, "A", "ASN", [3]),
+        ("B", 101, "", "ZN", [4]),
+        ("B", 102, "", "LIG", [5]),
+        ("W", 201, "", "HOH", [6]),
+    ]
+    assert structure.chain("B") == [4, 5]
+
+
+def test_alternate_locations(pdb_path):
+    first = _lowlevel.read_pdb(pdb_path)
+    assert first.altlocs[1] == "A"
+    np.testing.assert_allclose(first.graph.positions[1], [11.639, 6.071, -5.147])
+
+    second = _lowlevel.read_pdb(pdb_path, altloc="B")
+    assert second.altlocs[1] == "B"
+    np.testing.assert_allclose(second.occupancies[1], 0.4)
+    np.testing.assert_allclose(second.graph.positions[1], [11.7, 6.0, -5.1])
+
+
+def test_cryst1_sets_a_periodic_cell(pdb_path):
+    graph = _lowlevel.read_pdb(pdb_path).graph
+    assert graph.pbc == [True, True, True]
+    cell = graph.cell
+    np.testing.assert_allclose(np.linalg.norm(cell, axis=1), [10, 12, 14], rtol=1e-5)
+    cos_gamma = cell[0] @ cell[1] / (10 * 12)
+    np.testing.assert_allclose(cos_gamma, np.cos(np.radians(120.0)), atol=1e-6)
+
+
+def test_placeholder_cryst1_is_not_periodic(tmp_path):
+    path = tmp_path / "nmr.pdb"
+    path.write_text(
+        "CRYST1    1.000    1.000    1.000  90.00  90.00  90.00 P 1           1\n"
+        + PDB.splitlines(keepends=True)[2]
+    )
+    graph = _lowlevel.read_pdb(str(path)).graph
+    assert graph.cell is None
+    assert graph.pbc == [False, False, False]
+
+
+def test_malformed_coordinates_report_the_line(tmp_path):
+    path = tmp_path / "bad.pdb"
+    path.write_text(
+        "ATOM      1  N   ALA A   1      11.104   abcde  -6.504  1.00  0.00           N\n"
+    )
+    with pytest.raises(ValueError, match="PDB line 1"):
+        _lowlevel.read_pdb(str(path))
+
+
+@pytest.mark.parametrize("charge", ["é", "x-", "2"])
+def test_malformed_formal_charge_is_reported(tmp_path, charge):
+    path = tmp_path / "bad.pdb"
+    path.write_text(PDB.splitlines()[2] + charge + "\n", encoding="utf-8")
+    with pytest.raises(ValueError, match="PDB line 1: invalid formal charge"):
+        _lowlevel.read_pdb(str(path))
@@ -0,0 +1,85 @@
+import asyncio
+import concurrent.futures
+import queue
+
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+
+@pytest.fixture
+def model():
+    return _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+
+
+def random_graph(n, seed=0):
+    rng = np.random.default_rng(seed)
+    positions = rng.uniform(0.0, 12.0, size=(n, 3)).astype(np.float32)
+    feats = rng.uniform(0.0, 1.0, size=(n, 4)).astype(np.float32)
+    return _lowlevel.MolecularGraph([6] * n, positions), feats
+
+
+def test_pool_futures_match_direct_inference(model):
+    graph, feats = random_graph(20)
+    other, other_feats = random_graph(7, seed=1)
+    batch = _lowlevel.MolecularBatch([graph, other])
+
+    with _lowlevel.InferencePool(model, 4.0, 8, workers=2) as pool:
+        single = pool.submit(graph, feats)
+        batched = pool.submit_batch(batch, [feats, other_feats])
+        assert isinstance(single, concurrent.futures.Future)
+
+        np.testing.assert_allclose(
+            single.result(timeout=60),
+            graph.run_fused_with_model(model, feats, 4.0, 8),
+            rtol=1e-6,
+        )
+        expected = batch.run_batch_inference(model, [feats, other_feats], 4.0, 8)
+        for ours, theirs in zip(batched.result(timeout=60), expected):
+            np.testing.assert_allclose(ours, theirs, rtol=1e-6)
+    assert pool.pending == 0
+
+
+def test_pool_futures_are_awaitable(model):
+    graph, feats = random_graph(10)
+    pool = _lowlevel.InferencePool(model, 4.0, 8)
+
+    async def score():
+        futures = [asyncio.wrap_future(pool.submit(graph, feats)) for _ in range(3)]
+        return await asyncio.gather(*futures)
+
+    results = asyncio.run(score())
+    assert len(results) == 3
+    np.testing.assert_array_equal(results[0], results[2])
+    pool.shutdown()
+
+
+def test_pool_queue_depth_and_cancellation(model):
+    # A single worker stays busy with the large graph while the rest queue up
+    large, large_feats = random_graph(3000)
+    graph, feats = random_graph(5)
+    pool = _lowlevel.InferencePool(model, 4.0, 8, workers=1, max_queue=3)
+
+    running = pool.submit(large, large_feats)
+    queued = pool.submit(graph, feats)
+    kept = pool.submit(graph, feats)
+    with pytest.raises(queue.Full):
+        pool.submit(graph, feats)
+
+    assert queued.cancel()
+    pool.shutdown(wait=True)
+    assert queued.cancelled()
+    assert running.result().shape == (3000, 4)
+    assert kept.result().shape == (5, 4)
+    with pytest.raises(RuntimeError, match="shutdown"):
+        pool.submit(graph, feats)
+
+
+def test_pool_validates_inputs(model):
+    graph, feats = random_graph(5)
+    pool = _lowlevel.InferencePool(model, 4.0, 8)
+    with pytest.raises(ValueError, match="shape"):
+        pool.submit(graph, feats[:3])
+    with pytest.raises(ValueError, match="greater than zero"):
+        _lowlevel.InferencePool(model, 4.0, 8, max_queue=0)
+    pool.shutdown()
@@ -0,0 +1,101 @@
+import pytest
+from valence import _lowlevel
+
+SDF = """\
+acetate
+  valence          3D
+
+  4  3  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.5000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    2.1000    1.0000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    2.1000   -1.0000    0.0000 O   0  5  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0
+  2  3  2  0
+  2  4  1  0
+M  CHG  1   4  -1
+M  END
+> <ID>
+mol-1
+
+>  <notes> (1)
+line one
+line two
+
+$$$$
+ammonium
+  valence          3D
+
+  0  0  0     0  0            999 V3000
+M  V30 BEGIN CTAB
+M  V30 COUNTS 2 1 0 0 0
+M  V30 BEGIN ATOM
+M  V30 1 N 0.0 0.0 0.0 0 CHG=1
+M  V30 2 H 1.0 0.0 -
+M  V30 0.0 0
+M  V30 END ATOM
+M  V30 BEGIN BOND
+M  V30 1 1 1 2
+M  V30 END BOND
+M  V30 END CTAB
+M  END
+$$$$
+"""
+
+
+@pytest.fixture
+def sdf_path(tmp_path):
+    path = tmp_path / "library.sdf"
+    path.write_text(SDF)
+    return str(path)
+
+
+def test_v2000_record(sdf_path):
+    acetate, _ = _lowlevel.read_sdf(sdf_path)
+    assert acetate.name == "acetate"
+    assert acetate.graph.atomic_numbers == [6, 6, 8, 8]
+    assert acetate.bonds == [(0, 1, 1), (1, 2, 2), (1, 3, 1)]
+    assert acetate.formal_charges == [0, 0, 0, -1]
+    assert acetate.properties == {"ID": "mol-1", "notes": "line one\nline two"}
+
+
+def test_v3000_record(sdf_path):
+    _, ammonium = _lowlevel.read_sdf(sdf_path)
+    assert ammonium.graph.atomic_numbers == [7, 1]
+    assert ammonium.graph.positions.tolist() == [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]]
+    assert ammonium.bonds == [(0, 1, 1)]
+    assert ammonium.formal_charges == [1, 0]
+    assert ammonium.properties == {}
+
+
+def test_reader_streams_records(sdf_path):
+    names = [record.name for record in _lowlevel.SdfReader(sdf_path)]
+    assert names == ["acetate", "ammonium"]
+
+
+def test_bond_to_a_missing_atom_is_reported(tmp_path):
+    path = tmp_path / "bad.sdf"
+    path.write_text(
+        "bad\n\n\n"
+        "  1  1  0  0  0  0  0  0  0  0999 V2000\n"
+        "    0.0000    0.0000    0.0000 C   0  0\n"
+        "  1  2  1  0\n"
+        "M  END\n"
+    )
+    with pytest.raises(ValueError, match="SDF line 6: bond between atoms 1 and 2"):
+        _lowlevel.read_sdf(str(path))
+
+
+def test_truncated_header_is_reported(tmp_path):
+    path = tmp_path / "truncated.sdf"
+    path.write_text("caffeine\n  valence          3D\n")
+    with pytest.raises(
+        ValueError, match=r"^SDF line 2: the file ended before the header$"
+    ):
+        _lowlevel.read_sdf(str(path))
+
+    path.write_text("caffeine\n")
+    with pytest.raises(
+        ValueError, match=r"^SDF line 1: the file ended before the header$"
+    ):
+        _lowlevel.read_sdf(str(path))
@@ -0,0 +1,118 @@
+import numpy as np
+import pytest
+import valence
+from valence import _lowlevel
+
+
+def make_items(count, pulled):
+    rng = np.random.default_rng(2)
+    for i in range(count):
+        n = 2 + i % 4
+        positions = rng.uniform(-1.5, 1.5, size=(n, 3)).astype(np.float32)
+        feats = rng.uniform(0.0, 1.0, size=(n, 4)).astype(np.float32)
+        pulled.append(i)
+        yield _lowlevel.MolecularGraph([6] * n, positions), feats
+
+
+def test_stream_matches_single_graph_inference():
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    expected = [
+        graph.run_fused_with_model(model, feats, 2.0, 8)
+        for graph, feats in make_items(10, [])
+    ]
+
+    pulled = []
+    stream = _lowlevel.InferenceStream(
+        make_items(10, pulled), model, 2.0, 8, chunk_size=3, max_in_flight=2
+    )
+    index, first = next(stream)
+    # Only the chunks in flight have been pulled from the source
+    assert len(pulled) == stream.pulled <= 6
+
+    results = dict([(index, first), *stream])
+    assert sorted(results) == list(range(10))
+    assert stream.pulled == 10
+    for i, output in results.items():
+        np.testing.assert_allclose(output, expected[i], rtol=1e-6)
+
+
+def test_stream_reports_bad_items():
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    graph = _lowlevel.MolecularGraph([6, 6], np.eye(2, 3, dtype=np.float32))
+    items = [(graph, np.ones((2, 4), dtype=np.float32)), (graph, np.ones((3, 4)))]
+
+    stream = _lowlevel.InferenceStream(iter(items), model, 2.0, 8, chunk_size=1)
+    with pytest.raises(ValueError, match="Item 1"):
+        list(stream)
+    with pytest.raises(ValueError, match="greater than zero"):
+        _lowlevel.InferenceStream(iter(items), model, 2.0, 8, chunk_size=0)
+
+
+def test_stream_resumes_after_a_bad_item():
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    graph = _lowlevel.MolecularGraph([6, 6], np.eye(2, 3, dtype=np.float32))
+    good = np.ones((2, 4), dtype=np.float32)
+    items = [(graph, good), (graph, good[:1]), (graph, good), (graph, good)]
+
+    stream = _lowlevel.InferenceStream(
+        iter(items), model, 2.0, 8, chunk_size=3, max_in_flight=1
+    )
+    with pytest.raises(ValueError, match="Item 1"):
+        next(stream)
+    assert stream.pulled == 2
+
+    # The item before the bad one is not lost and later items keep their index
+    results = dict(stream)
+    assert sorted(results) == [0, 2, 3]
+    assert stream.pulled == 4
+
+
+def test_engine_predict_stream(tmp_path):
+    weights = tmp_path / "weights.npy"
+    np.save(weights, np.eye(4, dtype=np.float32))
+    engine = valence.ValenceEngine(str(weights))
+    molecules = [
+        valence.Molecule(atomic_numbers=[1, 1], positions=[[0, 0, 0], [0, 0, d]])
+        for d in (0.8, 1.0, 1.2)
+    ]
+    feats = [np.ones((2, 4), dtype=np.float32) for _ in molecules]
+
+    expected = engine.predict_batch(molecules, feats, cutoff=1.5)
+    streamed = dict(engine.predict_stream(zip(molecules, feats), 1.5, chunk_size=2))
+    for i, output in streamed.items():
+        np.testing.assert_allclose(output, expected[i], rtol=1e-6)
+    assert sorted(streamed) == [0, 1, 2]
+
+
+def test_stream_from_file_reader(tmp_path):
+    model = _lowlevel.GNNModel(np.eye(4, dtype=np.float32))
+    graphs = [
+        _lowlevel.MolecularGraph(
+            [1, 1], np.array([[0, 0, 0], [0, 0, d]], dtype=np.float32)
+        )
+        for d in (0.7, 0.8, 0.9, 1.0, 1.1)
+    ]
+    path = tmp_path / "frames.xyz"
+    _lowlevel.write_xyz(str(path), graphs)
+
+    def featurize(graph):
+        return np.ones((len(graph.atomic_numbers), 4), dtype=np.float32)
+
+    reader = _lowlevel.XyzReader(str(path))
+    stream = _lowlevel.InferenceStream(
+        reader, model, 1.5, 8, chunk_size=2, max_in_flight=1, featurize=featurize
+    )
+    index, first = next(stream)
+    assert stream.pulled == 2
+
+    results = dict([(index, first), *stream])
+    assert sorted(results) == list(range(5))
+    for i, graph in enumerate(graphs):
+        expected = graph.run_fused_with_model(model, featurize(graph), 1.5, 8)
+        np.testing.assert_allclose(results[i], expected, rtol=1e-6)
+
+    stream = _lowlevel.InferenceStream(
+        iter([object()]), model, 1.5, 8, featurize=featurize
+    )
+    with pytest.raises(ValueError, match="Item 0 is neither a MolecularGraph"):
+        next(stream)
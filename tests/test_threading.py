@@ -0,0 +1,59 @@
+import threading
+import time
+
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+
+@pytest.fixture
+def dense_system():
+    # Large enough that inference takes a noticeable amount of wall time
+    rng = np.random.default_rng(3)
+    positions = rng.uniform(0.0, 15.0, size=(4000, 3)).astype(np.float32)
+    graph = _lowlevel.MolecularGraph([6] * 4000, positions)
+    feats = rng.uniform(0.0, 1.0, size=(4000, 8)).astype(np.float32)
+    model = _lowlevel.GNNModel(np.eye(8, dtype=np.float32))
+    return graph, feats, model
+
+
+def longest_stall(call):
+    """Runs `call` while a Python thread ticks every millisecond and returns the
+    longest gap between ticks, relative to the duration of the call."""
+    stamps = []
+    stop = threading.Event()
+
+    def heartbeat():
+        while not stop.is_set():
+            stamps.append(time.perf_counter())
+            time.sleep(0.001)
+
+    thread = threading.Thread(target=heartbeat)
+    thread.start()
+    time.sleep(0.02)
+    start = time.perf_counter()
+    call()
+    end = time.perf_counter()
+    stop.set()
+    thread.join()
+
+    inside = [stamp for stamp in stamps if start <= stamp <= end]
+    return np.diff([start, *inside, end]).max() / (end - start)
+
+
+def test_fused_inference_releases_gil(dense_system):
+    graph, feats, model = dense_system
+    stall = longest_stall(lambda: graph.run_fused_with_model(model, feats, 6.0, 16))
+    assert stall < 0.5
+
+
+def test_batch_inference_releases_gil(dense_system):
+    graph, feats, model = dense_system
+    batch = _lowlevel.MolecularBatch([graph, graph])
+    packed = np.vstack([feats, feats])
+    stall = longest_stall(
+        lambda: batch.run_batch_inference(model, [feats, feats], 6.0, 16)
+    )
+    assert stall < 0.5
+    stall = longest_stall(lambda: batch.run_packed_inference(model, packed, 6.0, 16))
+    assert stall < 0.5
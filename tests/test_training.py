@@ -0,0 +1,152 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+
+@pytest.fixture
+def toy_problem():
+    rng = np.random.default_rng(1)
+    positions = rng.uniform(0.0, 2.0, size=(5, 3)).astype(np.float32)
+    graph = _lowlevel.MolecularGraph([6, 1, 1, 8, 1], positions)
+    feats = rng.uniform(size=(5, 4)).astype(np.float32)
+    weights = rng.normal(size=(3, 4)).astype(np.float32)
+    target = rng.normal(size=(5, 3)).astype(np.float32)
+    return graph, feats, weights, target
+
+
+def mse(graph, weights, feats, target):
+    model = _lowlevel.GNNModel(weights)
+    out = graph.run_fused_with_model(model, feats, 2.5, 8)
+    return float(np.mean((out - target) ** 2)), out
+
+
+def test_weight_gradient_matches_finite_differences(toy_problem):
+    graph, feats, weights, target = toy_problem
+    model = _lowlevel.GNNModel(weights)
+    _, out = mse(graph, weights, feats, target)
+    grad_output = (2.0 * (out - target) / out.size).astype(np.float32)
+
+    grad = model.backward(graph, feats, grad_output, 2.5, 8)
+    assert grad.shape == weights.shape
+
+    h = 1e-2
+    numerical = np.zeros_like(weights)
+    for idx in np.ndindex(weights.shape):
+        plus, minus = weights.copy(), weights.copy()
+        plus[idx] += h
+        minus[idx] -= h
+        numerical[idx] = (
+            mse(graph, plus, feats, target)[0] - mse(graph, minus, feats, target)[0]
+        ) / (2 * h)
+
+    np.testing.assert_allclose(grad, numerical, atol=1e-2, rtol=1e-2)
+
+
+def test_weights_round_trip_and_update(toy_problem):
+    graph, feats, weights, target = toy_problem
+    model = _lowlevel.GNNModel(weights)
+    np.testing.assert_array_equal(model.weights, weights)
+
+    loss_before, out = mse(graph, weights, feats, target)
+    grad_output = (2.0 * (out - target) / out.size).astype(np.float32)
+    grad = model.backward(graph, feats, grad_output, 2.5, 8)
+    model.weights = (model.weights - 1e-3 * grad).astype(np.float32)
+
+    loss_after, _ = mse(graph, model.weights, feats, target)
+    assert loss_after < loss_before
+
+    with pytest.raises(ValueError):
+        model.weights = np.zeros((2, 2), dtype=np.float32)
+
+
+def make_dataset(rng, true_weights, n_graphs=6):
+    graphs, feats, targets = [], [], []
+    model = _lowlevel.GNNModel(true_weights)
+    for _ in range(n_graphs):
+        n = int(rng.integers(3, 7))
+        positions = rng.uniform(0.0, 2.5, size=(n, 3)).astype(np.float32)
+        graph = _lowlevel.MolecularGraph([6] * n, positions)
+        x = rng.uniform(size=(n, true_weights.shape[1])).astype(np.float32)
+        graphs.append(graph)
+        feats.append(x)
+        targets.append(graph.run_fused_with_model(model, x, 2.5, 8))
+    return graphs, feats, targets
+
+
+@pytest.mark.parametrize(
+    "optimizer",
+    [
+        _lowlevel.Optimizer.sgd(1e-3, momentum=0.9),
+        _lowlevel.Optimizer.adam(1e-2),
+        _lowlevel.Optimizer.adamw(1e-2, weight_decay=1e-4),
+    ],
+)
+def test_trainer_reduces_per_atom_loss(optimizer):
+    rng = np.random.default_rng(2)
+    true_weights = rng.normal(size=(2, 3)).astype(np.float32)
+    graphs, feats, targets = make_dataset(rng, true_weights)
+    model = _lowlevel.GNNModel(np.zeros((2, 3), dtype=np.float32))
+
+    seen = []
+    trainer = _lowlevel.Trainer(
+        optimizer,
+        _lowlevel.Loss.mse(),
+        schedule=_lowlevel.LRSchedule.cosine(30),
+        batch_size=2,
+        seed=3,
+    )
+    history = trainer.fit(
+        model,
+        _lowlevel.MolecularBatch(graphs),
+        feats,
+        30,
+        2.5,
+        8,
+        targets=targets,
+        callback=lambda epoch, metrics: seen.append(epoch),
+    )
+
+    assert seen == list(range(30))
+    assert history[-1]["loss"] < history[0]["loss"]
+    assert set(history[0]) >= {"epoch", "lr", "loss", "mae"}
+
+
+def test_trainer_energy_force_loss():
+    rng = np.random.default_rng(4)
+    true_weights = rng.normal(size=(2, 3)).astype(np.float32)
+    graphs, feats, _ = make_dataset(rng, true_weights)
+    true_model = _lowlevel.GNNModel(true_weights)
+    energies, forces = [], []
+    for graph, x in zip(graphs, feats):
+        energy, force = graph.compute_forces(true_model, x, 2.5, 8)
+        energies.append(energy)
+        forces.append(force)
+
+    model = _lowlevel.GNNModel(np.zeros((2, 3), dtype=np.float32))
+    trainer = _lowlevel.Trainer(
+        _lowlevel.Optimizer.adam(5e-2),
+        _lowlevel.Loss.energy_force(energy_weight=1.0, force_weight=10.0),
+    )
+    history = trainer.fit(
+        model,
+        _lowlevel.MolecularBatch(graphs),
+        feats,
+        40,
+        2.5,
+        8,
+        energies=energies,
+        forces=forces,
+    )
+    assert history[-1]["loss"] < history[0]["loss"]
+    assert "force_mae" in history[-1]
+
+
+def test_trainer_requires_matching_targets():
+    rng = np.random.default_rng(5)
+    graphs, feats, _ = make_dataset(rng, np.ones((2, 3), dtype=np.float32))
+    model = _lowlevel.GNNModel(np.zeros((2, 3), dtype=np.float32))
+    trainer = _lowlevel.Trainer(
+        _lowlevel.Optimizer.sgd(1e-2), _lowlevel.Loss.huber(0.5)
+    )
+    with pytest.raises(ValueError):
+        trainer.fit(model, _lowlevel.MolecularBatch(graphs), feats, 1, 2.5, 8)
@@ -0,0 +1,91 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+
+@pytest.fixture
+def frames():
+    rng = np.random.default_rng(0)
+    start = rng.uniform(0.0, 4.0, size=(8, 3))
+    steps = rng.normal(scale=0.05, size=(30, 8, 3))
+    return (start + np.cumsum(steps, axis=0)).astype(np.float32)
+
+
+def test_construction_and_frames(frames):
+    numbers = [8, 1, 1, 8, 1, 1, 6, 6]
+    cell = np.eye(3, dtype=np.float32) * 4.0
+    trajectory = _lowlevel.Trajectory(numbers, frames, cell=cell)
+    assert len(trajectory) == 30
+    assert trajectory.n_atoms == 8
+    assert trajectory.pbc == [True, True, True]
+    np.testing.assert_array_equal(trajectory.positions, frames)
+    np.testing.assert_array_equal(trajectory.cell, cell)
+
+    last = trajectory[-1]
+    assert last.atomic_numbers == numbers
+    np.testing.assert_array_equal(last.positions, frames[-1])
+    np.testing.assert_array_equal(last.cell, cell)
+    with pytest.raises(IndexError, match="out of range"):
+        trajectory[30]
+
+    stacked = _lowlevel.Trajectory.from_graphs([trajectory[0], trajectory[1]])
+    np.testing.assert_array_equal(stacked.positions, frames[:2])
+
+
+@pytest.mark.parametrize("cell", [None, np.eye(3, dtype=np.float32) * 4.0])
+def test_inference_matches_single_frames(frames, cell):
+    numbers = [6] * 8
+    trajectory = _lowlevel.Trajectory(numbers, frames, cell=cell)
+    model = _lowlevel.GNNModel(np.arange(6, dtype=np.float32).reshape(3, 2) / 6)
+    feats = np.linspace(0.0, 1.0, 16, dtype=np.float32).reshape(8, 2)
+
+    results = trajectory.run_inference(model, feats, 2.0, 8)
+    assert results.shape == (30, 8, 3)
+    for frame, result in zip(frames, results):
+        graph = _lowlevel.MolecularGraph(numbers, frame, cell=cell)
+        expected = graph.run_fused_with_model(model, feats, 2.0, 8)
+        np.testing.assert_allclose(result, expected, rtol=1e-6)
+
+    for skin in [0.0, 3.0]:
+        np.testing.assert_allclose(
+            trajectory.run_inference(model, feats, 2.0, 8, skin=skin),
+            results,
+            rtol=1e-6,
+        )
+
+
+def test_empty_trajectory(frames):
+    model = _lowlevel.GNNModel(np.eye(2, dtype=np.float32))
+    feats = np.ones((8, 2), dtype=np.float32)
+    trajectory = _lowlevel.Trajectory([1] * 8, frames[:0])
+    assert len(trajectory) == 0
+    assert trajectory.run_inference(model, feats, 2.0, 8).shape == (0, 8, 2)
+
+    atomless = _lowlevel.Trajectory([], np.zeros((0, 0, 3), dtype=np.float32))
+    assert len(atomless) == 0
+    results = atomless.run_inference(model, np.zeros((0, 2), np.float32), 2.0, 8)
+    assert results.shape == (0, 0, 2)
+
+
+def test_invalid_inputs_are_rejected(frames):
+    with pytest.raises(ValueError, match="Expected positions of shape"):
+        _lowlevel.Trajectory([1] * 7, frames)
+    with pytest.raises(ValueError, match="require a cell"):
+        _lowlevel.Trajectory([1] * 8, frames, pbc=[True, False, False])
+
+    first = _lowlevel.MolecularGraph([1] * 8, frames[0])
+    other = _lowlevel.MolecularGraph([2] * 8, frames[1])
+    with pytest.raises(ValueError, match="Graph 1 has different atoms"):
+        _lowlevel.Trajectory.from_graphs([first, other])
+    with pytest.raises(ValueError, match="at least one graph"):
+        _lowlevel.Trajectory.from_graphs([])
+
+    trajectory = _lowlevel.Trajectory([1] * 8, frames)
+    model = _lowlevel.GNNModel(np.eye(2, dtype=np.float32))
+    feats = np.ones((8, 2), dtype=np.float32)
+    with pytest.raises(ValueError, match="Expected atom features of shape"):
+        trajectory.run_inference(model, feats[:4], 2.0, 8)
+    with pytest.raises(ValueError, match="Cutoff must be positive"):
+        trajectory.run_inference(model, feats, 0.0, 8)
+    with pytest.raises(ValueError, match="Skin must be non-negative"):
+        trajectory.run_inference(model, feats, 2.0, 8, skin=-0.1)
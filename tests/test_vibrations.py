@@ -0,0 +1,44 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+
+@pytest.fixture
+def diatomic():
+    rng = np.random.default_rng(6)
+    model = _lowlevel.GNNModel(rng.normal(size=(2, 3)).astype(np.float32))
+    potential = _lowlevel.Potential(model, 2.0, 8)
+    positions = np.array([[0.0, 0.0, 0.0], [0.0, 0.0, 1.1]], dtype=np.float32)
+    graph = _lowlevel.MolecularGraph([6, 8], positions)
+    feats = rng.uniform(size=(2, 3)).astype(np.float32)
+    return graph, feats, potential
+
+
+def test_hessian_is_symmetric_and_translation_invariant(diatomic):
+    graph, feats, potential = diatomic
+    vib = _lowlevel.Vibrations(graph, feats, potential, delta=0.01)
+    hessian = vib.hessian
+    assert hessian.shape == (6, 6)
+    np.testing.assert_allclose(hessian, hessian.T, atol=1e-8)
+    # Moving both atoms together does not change the forces
+    np.testing.assert_allclose(
+        hessian.reshape(6, 2, 3).sum(axis=1), 0.0, atol=1e-2 * np.abs(hessian).max()
+    )
+    np.testing.assert_allclose(vib.masses, [12.011, 15.999])
+
+
+def test_projected_diatomic_has_a_single_stretch_mode(diatomic):
+    graph, feats, potential = diatomic
+    vib = _lowlevel.Vibrations(graph, feats, potential, delta=0.01)
+    frequencies = vib.frequencies
+    modes = vib.modes
+    assert frequencies.shape == (6,)
+    assert modes.shape == (6, 2, 3)
+
+    stretch = int(np.argmax(np.abs(frequencies)))
+    others = np.delete(np.abs(frequencies), stretch)
+    assert np.all(others < 1e-3 * abs(frequencies[stretch]))
+    # The stretch moves the atoms in opposite directions along the bond (z) axis
+    mode = modes[stretch]
+    np.testing.assert_allclose(mode[:, :2], 0.0, atol=1e-6)
+    assert mode[0, 2] * mode[1, 2] < 0
@@ -0,0 +1,136 @@
+import numpy as np
+import pytest
+from valence import _lowlevel
+
+EXTENDED = """\
+3
+Lattice="5.0 0.0 0.0 0.0 6.0 0.0 0.0 0.0 7.0" Properties=species:S:1:pos:R:3:forces:R:3:tag:I:1 energy=-1.5 name="water box" step=3 pbc="T T F"
+O 0.0 0.0 0.0 0.1 0.2 0.3 1
+H 0.0 0.0 0.96 0.0 0.0 0.0 2
+H 0.93 0.0 -0.24 0.0 0.0 0.0 3
+"""
+
+PLAIN = """\
+2
+carbon monoxide
+C 0.0 0.0 0.0
+O 0.0 0.0 1.13
+
+1
+a lone atom
+Ar 1.0 2.0 3.0
+"""
+
+
+def test_read_plain_multi_frame(tmp_path):
+    path = tmp_path / "plain.xyz"
+    path.write_text(PLAIN)
+
+    frames = _lowlevel.read_xyz(str(path))
+    assert len(frames) == 2
+    assert frames[0].graph.atomic_numbers == [6, 8]
+    assert frames[0].comment == "carbon monoxide"
+    assert frames[0].info == {}
+    assert frames[0].graph.cell is None
+    np.testing.assert_allclose(frames[1].graph.positions, [[1.0, 2.0, 3.0]])
+
+    streamed = list(_lowlevel.XyzReader(str(path)))
+    assert [len(frame) for frame in streamed] == [2, 1]
+
+
+def test_read_extended_lattice_info_and_arrays(tmp_path):
+    path = tmp_path / "extended.xyz"
+    path.write_text(EXTENDED)
+
+    (frame,) = _lowlevel.read_xyz(str(path))
+    graph = frame.graph
+    assert graph.atomic_numbers == [8, 1, 1]
+    np.testing.assert_allclose(graph.cell, np.diag([5.0, 6.0, 7.0]))
+    assert graph.pbc == [True, True, False]
+    assert frame.info == {"energy": -1.5, "name": "water box", "step": 3}
+    assert frame.arrays["forces"].shape == (3, 3)
+    np.testing.assert_allclose(frame.arrays["forces"][0], [0.1, 0.2, 0.3])
+    np.testing.assert_array_equal(frame.arrays["tag"], [1, 2, 3])
+
+
+def test_lattice_defaults_to_fully_periodic(tmp_path):
+    path = tmp_path / "cell.xyz"
+    path.write_text('1\nLattice="3 0 0 0 3 0 0 0 3"\nNa 0 0 0\n')
+
+    (frame,) = _lowlevel.read_xyz(str(path))
+    assert frame.graph.pbc == [True, True, True]
+
+
+def test_write_round_trip_with_predictions(tmp_path):
+    source = tmp_path / "extended.xyz"
+    source.write_text(EXTENDED)
+    frames = _lowlevel.read_xyz(str(source))
+    predictions = [np.arange(6, dtype=np.float32).reshape(3, 2)]
+
+    target = tmp_path / "scored.xyz"
+    _lowlevel.write_xyz(
+        str(target), frames, predictions=predictions, prediction_name="energies"
+    )
+    (frame,) = _lowlevel.read_xyz(str(target))
+    assert frame.info == frames[0].info
+    np.testing.assert_allclose(frame.graph.cell, frames[0].graph.cell)
+    np.testing.assert_allclose(frame.graph.positions, frames[0].graph.positions)
+    np.testing.assert_allclose(frame.arrays["forces"], frames[0].arrays["forces"])
+    np.testing.assert_allclose(frame.arrays["energies"], predictions[0])
+
+
+def test_write_graphs_and_append(tmp_path):
+    graph = _lowlevel.MolecularGraph(
+        [6, 8], np.array([[0, 0, 0], [0, 0, 1.13]], dtype=np.float32)
+    )
+    path = tmp_path / "out.xyz"
+    _lowlevel.write_xyz(str(path), [graph])
+    _lowlevel.write_xyz(
+        str(path),
+        [
+            _lowlevel.XyzFrame(
+                graph, info={"energy": 2.0, "label": "co", "dipole": [1.0, 2.0]}
+            )
+        ],
+        predictions=[np.array([0.5, -0.5])],
+        append=True,
+    )
+
+    first, second = _lowlevel.read_xyz(str(path))
+    assert first.graph.atomic_numbers == [6, 8]
+    assert second.info.keys() == {"energy", "label", "dipole"}
+    assert second.info["label"] == "co"
+    assert type(second.info["energy"]) is float
+    assert second.info["energy"] == 2.0
+    assert second.info["dipole"].dtype == np.float64
+    np.testing.assert_array_equal(second.info["dipole"], [1.0, 2.0])
+    np.testing.assert_allclose(second.arrays["prediction"], [0.5, -0.5])
+
+
+def test_malformed_files_report_line_numbers(tmp_path):
+    path = tmp_path / "bad.xyz"
+    path.write_text("2\ncomment\nC 0 0 0\n")
+    with pytest.raises(ValueError, match="line 4"):
+        _lowlevel.read_xyz(str(path))
+
+    path.write_text("1\ncomment\nXx 0 0 0\n")
+    with pytest.raises(ValueError, match="line 3"):
+        _lowlevel.read_xyz(str(path))
+
+    # A corrupt atom count is reported, not reserved for up front
+    path.write_text("100000000000\ncomment\nC 0 0 0\n")
+    with pytest.raises(ValueError, match="expected 100000000000 atoms"):
+        _lowlevel.read_xyz(str(path))
+
+    huge = "Properties=species:S:1:pos:R:3:spin:R:18446744073709551615"
+    path.write_text(f"1\n{huge}\nC 0 0 0 1\n")
+    with pytest.raises(ValueError, match="line 3: expected 18446744073709551615"):
+        _lowlevel.read_xyz(str(path))
+
+
+def test_prediction_shape_is_checked(tmp_path):
+    graph = _lowlevel.MolecularGraph([1], np.zeros((1, 3), dtype=np.float32))
+    with pytest.raises(ValueError, match="expected predictions with 1 rows"):
+        _lowlevel.write_xyz(
+            str(tmp_path / "x.xyz"), [graph], predictions=[np.zeros(2)]
+        )